@@ -1,10 +1,92 @@
 use crate::migration::migration_plan::MigrationPlan;
+use crate::schema::data_type::DataType;
+use crate::schema::relationship::{ReferentialAction, Relationship};
 use crate::schema::Schema;
 
+use super::type_mapping::{normalize_db_type, TypeMapping};
+
 pub trait DatabaseEngine: Default {
     /// Returns the name of the database engine (e.g., "PostgreSQL", "MySQL").
     fn name() -> &'static str;
 
-    /// Generates a migration plan based on the current and desired schema.
+    /// Returns the type overrides of this engine.
+    fn get_type_mapping(&self) -> &TypeMapping;
+
+    /// Returns a mutable reference to the type overrides of this engine.
+    fn get_type_mapping_mut(&mut self) -> &mut TypeMapping;
+
+    /// Returns the engine's built-in native type for a data type,
+    /// or `None` if the engine can't store it.
+    fn default_db_type(&self, data_type: &DataType) -> Option<String>;
+
+    /// Returns the data type the engine's built-in mapping uses for a native type.
+    fn default_data_type(&self, db_type: &str) -> Option<DataType>;
+
+    /// Returns the native type for a data type, honouring the type overrides.
+    fn get_db_type(&self, data_type: &DataType) -> Result<String, String> {
+        if let Some(db_type) = self.get_type_mapping().get_override(data_type) {
+            return Ok(db_type.to_string());
+        }
+        self.default_db_type(data_type).ok_or(format!(
+            "Data type {:?} is not supported by {}",
+            data_type,
+            Self::name()
+        ))
+    }
+
+    /// Returns the data type for a native type name, honouring the type overrides.
+    fn get_data_type(&self, db_type: &str) -> Option<DataType> {
+        self.get_type_mapping()
+            .find_data_type(db_type)
+            .or_else(|| self.default_data_type(&normalize_db_type(db_type)))
+    }
+
+    /// Generates a migration plan that creates the given schema from scratch.
     fn generate_migration_plan(&self, schema: &mut Schema) -> Result<MigrationPlan, String>;
+
+    /// Generates a migration plan that evolves the `current` schema into the `desired` one.
+    fn generate_diff_migration_plan(
+        &self,
+        current: &Schema,
+        desired: &Schema,
+    ) -> Result<MigrationPlan, String>;
+}
+
+/// Renders the referential actions and deferral options following `REFERENCES`, for the
+/// engines which support them all or have checked the relationship against their limits.
+pub(crate) fn referential_actions_sql(relationship: &Relationship) -> String {
+    let mut sql = String::default();
+    if relationship.get_on_delete() != ReferentialAction::NoAction {
+        sql.push_str(&format!(
+            " ON DELETE {}",
+            relationship.get_on_delete().to_sql()
+        ));
+    }
+    if relationship.get_on_update() != ReferentialAction::NoAction {
+        sql.push_str(&format!(
+            " ON UPDATE {}",
+            relationship.get_on_update().to_sql()
+        ));
+    }
+    if relationship.is_deferrable() {
+        sql.push_str(" DEFERRABLE");
+    }
+    if relationship.is_initially_deferred() {
+        sql.push_str(" INITIALLY DEFERRED");
+    }
+    sql
+}
+
+/// Quotes an identifier in double quotes unless it can be written as is: a lower case name
+/// which is none of the `reserved` keywords, given in upper case. Quoting keeps the case of
+/// mixed-case names, which the database would fold otherwise.
+pub(crate) fn quote_identifier(identifier: &str, reserved: &[&str]) -> String {
+    let plain = identifier.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && identifier
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if plain && !reserved.contains(&identifier.to_ascii_uppercase().as_str()) {
+        return identifier.to_string();
+    }
+    format!("\"{}\"", identifier.replace('"', "\"\""))
 }
@@ -1,2 +1,8 @@
 pub mod engine;
+pub mod mysql_engine;
+pub mod oracle_engine;
 pub mod postgres_engine;
+#[cfg(feature = "postgres")]
+pub mod postgres_executor;
+pub mod sqlite_engine;
+pub mod type_mapping;
@@ -0,0 +1,972 @@
+use crate::migration::migration_plan::{MigrationPlan, MigrationStep};
+use crate::migration::schema_diff::{SchemaChange, SchemaDiff};
+use crate::schema::column::Column;
+use crate::schema::constraint::PrimaryKey;
+use crate::schema::data_type::DataType;
+use crate::schema::index::{Index, IndexMethod, SortOrder};
+use crate::schema::relationship::{ReferentialAction, Relationship};
+use crate::schema::table::Table;
+use crate::schema::Schema;
+
+use super::engine::{referential_actions_sql, DatabaseEngine};
+use super::type_mapping::{base_db_type, db_type_parameters, time_db_type, TypeMapping};
+
+/// Engine for MySQL and MariaDB. Tables are created with the InnoDB storage engine,
+/// which is the only one enforcing foreign keys.
+#[derive(Default)]
+pub struct MySqlEngine {
+    type_mapping: TypeMapping,
+}
+
+impl MySqlEngine {
+    fn quote(&self, identifier: &str) -> String {
+        format!("`{}`", identifier.replace('`', "``"))
+    }
+
+    fn quote_list(&self, identifiers: &[String]) -> String {
+        identifiers
+            .iter()
+            .map(|identifier| self.quote(identifier))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    fn column_type(&self, table: &Table, column: &Column) -> Result<String, String> {
+        // TEXT columns can't be part of a key or an index without a prefix length
+        let indexed = table.get_indexes().iter().any(|i| {
+            i.get_columns()
+                .iter()
+                .any(|c| c.get_name() == column.get_name())
+        });
+        if column.get_data_type() == DataType::String
+            && (column.is_primary_key() || column.is_foreign_key() || column.is_unique() || indexed)
+            && self.type_mapping.get_override(&DataType::String).is_none()
+        {
+            return Ok("VARCHAR(255)".to_string());
+        }
+        self.get_db_type(&column.get_data_type())
+    }
+
+    /// Column definition without key and check constraints, as used by `MODIFY COLUMN`.
+    fn column_type_definition(&self, table: &Table, column: &Column) -> Result<String, String> {
+        let mut definition = format!(
+            "{} {}",
+            self.quote(column.get_name()),
+            self.column_type(table, column)?
+        );
+        if let Some(default) = column.get_default() {
+            definition.push_str(&format!(" DEFAULT {}", default.to_sql()));
+        }
+        if !column.is_primary_key() && !column.is_nullable() {
+            definition.push_str(" NOT NULL");
+        }
+        Ok(definition)
+    }
+
+    /// Column definition with the key and check constraints which can be declared inline.
+    /// MySQL can't name an inline `UNIQUE`, so unique keys are declared separately.
+    fn column_definition(
+        &self,
+        table: &Table,
+        column: &Column,
+        inline_primary_key: bool,
+    ) -> Result<String, String> {
+        let mut definition = self.column_type_definition(table, column)?;
+        if inline_primary_key {
+            definition.push_str(" PRIMARY KEY");
+        }
+        for check in column.get_checks() {
+            definition.push_str(&format!(
+                " CONSTRAINT {} CHECK ({})",
+                self.quote(check.get_name()),
+                check.get_expression()
+            ));
+        }
+        Ok(definition)
+    }
+
+    fn unique_key_definition(&self, table: &str, column: &Column) -> String {
+        format!(
+            "CONSTRAINT {} UNIQUE ({})",
+            self.quote(&column.get_unique_constraint_name(table)),
+            self.quote(column.get_name())
+        )
+    }
+
+    /// MySQL always names the primary key `PRIMARY`, so a name of the key is ignored.
+    fn primary_key_definition(&self, primary_key: &PrimaryKey) -> String {
+        format!(
+            "PRIMARY KEY ({})",
+            self.quote_list(primary_key.get_columns())
+        )
+    }
+
+    fn create_table_sql(&self, table: &Table) -> Result<String, String> {
+        let primary_key = table.get_primary_key();
+        let inline_primary_key = primary_key.is_some_and(|p| p.is_inline());
+        let mut definitions = table
+            .get_columns()
+            .iter()
+            .map(|column| {
+                self.column_definition(table, column, inline_primary_key && column.is_primary_key())
+            })
+            .collect::<Result<Vec<String>, String>>()?;
+        if let (false, Some(primary_key)) = (inline_primary_key, primary_key) {
+            definitions.push(self.primary_key_definition(primary_key));
+        }
+        definitions.extend(
+            table
+                .get_columns()
+                .iter()
+                .filter(|column| column.is_unique())
+                .map(|column| self.unique_key_definition(table.get_name(), column)),
+        );
+        let mut sql = format!(
+            "CREATE TABLE {} (\n    {}\n) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;\n",
+            self.quote(table.get_name()),
+            definitions.join(",\n    ")
+        );
+        for index in table.get_indexes() {
+            sql.push_str(&self.create_index_sql(table, index)?);
+        }
+        Ok(sql)
+    }
+
+    fn drop_table_sql(&self, table: &Table) -> String {
+        format!("DROP TABLE {};\n", self.quote(table.get_name()))
+    }
+
+    fn add_column_sql(&self, table: &Table, column: &Column) -> Result<String, String> {
+        let mut sql = format!(
+            "ALTER TABLE {} ADD COLUMN {};\n",
+            self.quote(table.get_name()),
+            self.column_definition(table, column, false)?
+        );
+        if column.is_unique() {
+            sql.push_str(&format!(
+                "ALTER TABLE {} ADD {};\n",
+                self.quote(table.get_name()),
+                self.unique_key_definition(table.get_name(), column)
+            ));
+        }
+        Ok(sql)
+    }
+
+    fn remove_column_sql(&self, table: &str, column: &Column) -> String {
+        format!(
+            "ALTER TABLE {} DROP COLUMN {};\n",
+            self.quote(table),
+            self.quote(column.get_name())
+        )
+    }
+
+    /// Alters the `from` column of the `from_table` into the `to` column of the `to_table`,
+    /// the tables telling whether the column is indexed.
+    fn alter_column_sql(
+        &self,
+        from_table: &Table,
+        to_table: &Table,
+        from: &Column,
+        to: &Column,
+    ) -> Result<String, String> {
+        let table = to_table.get_name();
+        let mut sql = String::default();
+        for check in from.get_checks() {
+            if !to.get_checks().contains(check) {
+                sql.push_str(&format!(
+                    "ALTER TABLE {} DROP CHECK {};\n",
+                    self.quote(table),
+                    self.quote(check.get_name())
+                ));
+            }
+        }
+        if from.is_unique() && !to.is_unique() {
+            sql.push_str(&format!(
+                "ALTER TABLE {} DROP INDEX {};\n",
+                self.quote(table),
+                self.quote(&from.get_unique_constraint_name(table))
+            ));
+        }
+        if self.column_type(from_table, from)? != self.column_type(to_table, to)?
+            || from.get_default() != to.get_default()
+            || from.is_nullable() != to.is_nullable()
+        {
+            sql.push_str(&format!(
+                "ALTER TABLE {} MODIFY COLUMN {};\n",
+                self.quote(table),
+                self.column_type_definition(to_table, to)?
+            ));
+        }
+        if !from.is_unique() && to.is_unique() {
+            sql.push_str(&format!(
+                "ALTER TABLE {} ADD {};\n",
+                self.quote(table),
+                self.unique_key_definition(table, to)
+            ));
+        }
+        for check in to.get_checks() {
+            if !from.get_checks().contains(check) {
+                sql.push_str(&format!(
+                    "ALTER TABLE {} ADD CONSTRAINT {} CHECK ({});\n",
+                    self.quote(table),
+                    self.quote(check.get_name()),
+                    check.get_expression()
+                ));
+            }
+        }
+        Ok(sql)
+    }
+
+    /// Retypes the columns of `index` which are the same in both tables but whose type depends
+    /// on the index, as String columns are TEXT unless they're indexed. Changed columns are
+    /// retyped by their `AlterColumn` step.
+    fn index_columns_sql(
+        &self,
+        from_table: &Table,
+        to_table: &Table,
+        index: &Index,
+    ) -> Result<String, String> {
+        let mut sql = String::default();
+        for index_column in index.get_columns() {
+            let name = index_column.get_name();
+            if let (Ok(from), Ok(to)) = (from_table.get_column(name), to_table.get_column(name)) {
+                if from == to
+                    && self.column_type(from_table, from)? != self.column_type(to_table, to)?
+                {
+                    sql.push_str(&format!(
+                        "ALTER TABLE {} MODIFY COLUMN {};\n",
+                        self.quote(to_table.get_name()),
+                        self.column_type_definition(to_table, to)?
+                    ));
+                }
+            }
+        }
+        Ok(sql)
+    }
+
+    fn add_primary_key_sql(&self, table: &str, primary_key: &PrimaryKey) -> String {
+        format!(
+            "ALTER TABLE {} ADD {};\n",
+            self.quote(table),
+            self.primary_key_definition(primary_key)
+        )
+    }
+
+    fn drop_primary_key_sql(&self, table: &str) -> String {
+        format!("ALTER TABLE {} DROP PRIMARY KEY;\n", self.quote(table))
+    }
+
+    /// Renders the referential actions following `REFERENCES`. InnoDB rejects `SET DEFAULT`
+    /// and checks foreign keys immediately, so those options are reported as errors.
+    fn checked_referential_actions_sql(
+        &self,
+        relationship: &Relationship,
+    ) -> Result<String, String> {
+        if relationship.is_deferrable() {
+            return Err(format!(
+                "Relationship {} is deferrable, which {} doesn't support",
+                relationship.get_code(),
+                MySqlEngine::name()
+            ));
+        }
+        for (clause, action) in [
+            ("ON DELETE", relationship.get_on_delete()),
+            ("ON UPDATE", relationship.get_on_update()),
+        ] {
+            if action == ReferentialAction::SetDefault {
+                return Err(format!(
+                    "Relationship {} uses {} SET DEFAULT, which {} doesn't support",
+                    relationship.get_code(),
+                    clause,
+                    MySqlEngine::name()
+                ));
+            }
+        }
+        Ok(referential_actions_sql(relationship))
+    }
+
+    /// TEXT, BLOB and JSON columns can only be indexed with a prefix length, which indexes
+    /// don't have, so indexes on such columns are errors.
+    fn create_index_sql(&self, table: &Table, index: &Index) -> Result<String, String> {
+        for index_column in index.get_columns() {
+            let column = table.get_column(index_column.get_name())?;
+            let db_type = self.column_type(table, column)?;
+            if matches!(
+                base_db_type(&db_type).as_str(),
+                "TEXT"
+                    | "TINYTEXT"
+                    | "MEDIUMTEXT"
+                    | "LONGTEXT"
+                    | "BLOB"
+                    | "TINYBLOB"
+                    | "MEDIUMBLOB"
+                    | "LONGBLOB"
+                    | "JSON"
+            ) {
+                return Err(format!(
+                    "Index {}: column {} has the type {}, which MySQL can't index without a prefix length",
+                    index.get_name(),
+                    column.get_name(),
+                    db_type
+                ));
+            }
+        }
+        if index.get_predicate().is_some() || !index.get_include().is_empty() {
+            return Err(format!(
+                "Index {}: partial and covering indexes are not supported by MySQL",
+                index.get_name()
+            ));
+        }
+        if index.get_columns().iter().any(|c| c.get_nulls().is_some()) {
+            return Err(format!(
+                "Index {}: NULLS FIRST and NULLS LAST are not supported by MySQL",
+                index.get_name()
+            ));
+        }
+        let method = match index.get_method() {
+            IndexMethod::BTree => "",
+            IndexMethod::Hash => " USING HASH",
+            method => {
+                return Err(format!(
+                    "Index {}: method {} is not supported by MySQL",
+                    index.get_name(),
+                    method.to_sql()
+                ))
+            }
+        };
+        let columns = index
+            .get_columns()
+            .iter()
+            .map(|c| match c.get_order() {
+                SortOrder::Asc => self.quote(c.get_name()),
+                SortOrder::Desc => format!("{} DESC", self.quote(c.get_name())),
+            })
+            .collect::<Vec<String>>();
+        Ok(format!(
+            "CREATE {}INDEX {} ON {} ({}){};\n",
+            if index.is_unique() { "UNIQUE " } else { "" },
+            self.quote(index.get_name()),
+            self.quote(table.get_name()),
+            columns.join(", "),
+            method
+        ))
+    }
+
+    fn drop_index_sql(&self, table: &str, index: &Index) -> String {
+        format!(
+            "DROP INDEX {} ON {};\n",
+            self.quote(index.get_name()),
+            self.quote(table)
+        )
+    }
+
+    fn add_relationship_sql(&self, relationship: &Relationship) -> Result<String, String> {
+        Ok(format!(
+            "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({}){};\n",
+            self.quote(relationship.get_to_table()),
+            self.quote(&relationship.get_constraint_name()),
+            self.quote_list(relationship.get_to_columns()),
+            self.quote(relationship.get_from_table()),
+            self.quote_list(relationship.get_from_columns()),
+            self.checked_referential_actions_sql(relationship)?,
+        ))
+    }
+
+    fn drop_relationship_sql(&self, relationship: &Relationship) -> String {
+        format!(
+            "ALTER TABLE {} DROP FOREIGN KEY {};\n",
+            self.quote(relationship.get_to_table()),
+            self.quote(&relationship.get_constraint_name()),
+        )
+    }
+}
+
+impl DatabaseEngine for MySqlEngine {
+    fn name() -> &'static str {
+        "MySQL"
+    }
+
+    fn get_type_mapping(&self) -> &TypeMapping {
+        &self.type_mapping
+    }
+
+    fn get_type_mapping_mut(&mut self) -> &mut TypeMapping {
+        &mut self.type_mapping
+    }
+
+    fn default_db_type(&self, data_type: &DataType) -> Option<String> {
+        let db_type = match data_type {
+            DataType::Integer => "INT".to_string(),
+            DataType::Float => "DOUBLE".to_string(),
+            DataType::String => "TEXT".to_string(),
+            DataType::Boolean => "TINYINT(1)".to_string(),
+            DataType::Date => "DATE".to_string(),
+            DataType::SmallInt => "SMALLINT".to_string(),
+            DataType::BigInt => "BIGINT".to_string(),
+            DataType::Decimal { precision, scale } => {
+                format!("DECIMAL({}, {})", precision, scale)
+            }
+            DataType::Varchar(length) => format!("VARCHAR({})", length),
+            // TIMESTAMP values are converted to UTC, DATETIME values are stored as given
+            DataType::Timestamp {
+                precision,
+                with_time_zone: true,
+            } => time_db_type("TIMESTAMP", precision, false),
+            DataType::Timestamp {
+                precision,
+                with_time_zone: false,
+            } => time_db_type("DATETIME", precision, false),
+            DataType::Time {
+                precision,
+                with_time_zone: false,
+            } => time_db_type("TIME", precision, false),
+            DataType::Time {
+                with_time_zone: true,
+                ..
+            } => return None,
+            DataType::Uuid => "CHAR(36)".to_string(),
+            // MySQL's JSON is stored in a binary format already
+            DataType::Json | DataType::Jsonb => "JSON".to_string(),
+            DataType::Binary => "LONGBLOB".to_string(),
+            DataType::Array(_) => return None,
+            DataType::Enum(_) => "VARCHAR(255)".to_string(),
+        };
+        Some(db_type)
+    }
+
+    fn default_data_type(&self, db_type: &str) -> Option<DataType> {
+        if db_type == "TINYINT(1)" || db_type == "BOOLEAN" || db_type == "BOOL" {
+            return Some(DataType::Boolean);
+        }
+        if db_type == "CHAR(36)" {
+            return Some(DataType::Uuid);
+        }
+        let parameters = db_type_parameters(db_type);
+        let time_precision = match parameters.first() {
+            Some(precision) => Some(u8::try_from(*precision).ok()?),
+            None => None,
+        };
+        match base_db_type(db_type).as_str() {
+            "INT" | "INTEGER" | "MEDIUMINT" => Some(DataType::Integer),
+            "SMALLINT" => Some(DataType::SmallInt),
+            "BIGINT" => Some(DataType::BigInt),
+            "DOUBLE" | "DOUBLE PRECISION" | "FLOAT" | "REAL" => Some(DataType::Float),
+            "DECIMAL" | "NUMERIC" => match parameters[..] {
+                [precision] => Some(DataType::Decimal {
+                    precision: u16::try_from(precision).ok()?,
+                    scale: 0,
+                }),
+                [precision, scale] => Some(DataType::Decimal {
+                    precision: u16::try_from(precision).ok()?,
+                    scale: u16::try_from(scale).ok()?,
+                }),
+                _ => None,
+            },
+            "VARCHAR" => match parameters.first() {
+                Some(length) => Some(DataType::Varchar(*length)),
+                None => Some(DataType::String),
+            },
+            "TEXT" | "CHAR" | "TINYTEXT" | "MEDIUMTEXT" | "LONGTEXT" => Some(DataType::String),
+            "DATE" => Some(DataType::Date),
+            "DATETIME" => Some(DataType::Timestamp {
+                precision: time_precision,
+                with_time_zone: false,
+            }),
+            "TIMESTAMP" => Some(DataType::Timestamp {
+                precision: time_precision,
+                with_time_zone: true,
+            }),
+            "TIME" => Some(DataType::Time {
+                precision: time_precision,
+                with_time_zone: false,
+            }),
+            "JSON" => Some(DataType::Json),
+            "BLOB" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BINARY" | "VARBINARY" => {
+                Some(DataType::Binary)
+            }
+            _ => None,
+        }
+    }
+
+    fn generate_migration_plan(&self, schema: &mut Schema) -> Result<MigrationPlan, String> {
+        self.generate_diff_migration_plan(&Schema::new(), schema)
+    }
+
+    fn generate_diff_migration_plan(
+        &self,
+        current: &Schema,
+        desired: &Schema,
+    ) -> Result<MigrationPlan, String> {
+        desired.validate()?;
+        let mut migration_steps = Vec::<MigrationStep>::default();
+
+        for change in SchemaDiff::new(current, desired).get_changes() {
+            let step = match change {
+                SchemaChange::CreateTable(table) => MigrationStep::CreateTable {
+                    name: table.get_name().to_string(),
+                    sql_script: self.create_table_sql(table)?,
+                    down_sql_script: Some(self.drop_table_sql(table)),
+                },
+                SchemaChange::DropTable(table) => MigrationStep::DropTable {
+                    name: table.get_name().to_string(),
+                    sql_script: self.drop_table_sql(table),
+                    down_sql_script: Some(self.create_table_sql(table)?),
+                },
+                SchemaChange::AddColumn { table, column } => MigrationStep::AddColumn {
+                    table: table.clone(),
+                    column: column.clone(),
+                    sql_script: self.add_column_sql(desired.get_table(table)?, column)?,
+                    down_sql_script: Some(self.remove_column_sql(table, column)),
+                },
+                SchemaChange::RemoveColumn { table, column } => MigrationStep::RemoveColumn {
+                    table: table.clone(),
+                    column: column.clone(),
+                    sql_script: self.remove_column_sql(table, column),
+                    down_sql_script: Some(self.add_column_sql(current.get_table(table)?, column)?),
+                },
+                SchemaChange::AlterColumn { table, from, to } => {
+                    let (current_table, desired_table) =
+                        (current.get_table(table)?, desired.get_table(table)?);
+                    let sql_script =
+                        self.alter_column_sql(current_table, desired_table, from, to)?;
+                    if sql_script.is_empty() {
+                        continue;
+                    }
+                    MigrationStep::AlterColumn {
+                        table: table.clone(),
+                        column: to.clone(),
+                        previous_column: from.clone(),
+                        sql_script,
+                        down_sql_script: Some(self.alter_column_sql(
+                            desired_table,
+                            current_table,
+                            to,
+                            from,
+                        )?),
+                    }
+                }
+                SchemaChange::AddPrimaryKey { table, primary_key } => {
+                    MigrationStep::AddPrimaryKey {
+                        table: table.clone(),
+                        primary_key: primary_key.clone(),
+                        sql_script: self.add_primary_key_sql(table, primary_key),
+                        down_sql_script: Some(self.drop_primary_key_sql(table)),
+                    }
+                }
+                SchemaChange::DropPrimaryKey { table, primary_key } => {
+                    MigrationStep::DropPrimaryKey {
+                        table: table.clone(),
+                        primary_key: primary_key.clone(),
+                        sql_script: self.drop_primary_key_sql(table),
+                        down_sql_script: Some(self.add_primary_key_sql(table, primary_key)),
+                    }
+                }
+                SchemaChange::AddIndex { table, index } => {
+                    let (current_table, desired_table) =
+                        (current.get_table(table)?, desired.get_table(table)?);
+                    MigrationStep::AddIndex {
+                        table: table.clone(),
+                        index: index.clone(),
+                        concurrently: false,
+                        sql_script: self.index_columns_sql(current_table, desired_table, index)?
+                            + &self.create_index_sql(desired_table, index)?,
+                        down_sql_script: Some(
+                            self.drop_index_sql(table, index)
+                                + &self.index_columns_sql(desired_table, current_table, index)?,
+                        ),
+                    }
+                }
+                SchemaChange::DropIndex { table, index } => {
+                    let (current_table, desired_table) =
+                        (current.get_table(table)?, desired.get_table(table)?);
+                    MigrationStep::DropIndex {
+                        table: table.clone(),
+                        index: index.clone(),
+                        concurrently: false,
+                        sql_script: self.drop_index_sql(table, index)
+                            + &self.index_columns_sql(current_table, desired_table, index)?,
+                        down_sql_script: Some(
+                            self.index_columns_sql(desired_table, current_table, index)?
+                                + &self.create_index_sql(current_table, index)?,
+                        ),
+                    }
+                }
+                SchemaChange::AddRelationship(relationship)
+                | SchemaChange::DropRelationship(relationship)
+                    if relationship.is_many_to_many() =>
+                {
+                    // modeled by the foreign keys of its junction table
+                    continue;
+                }
+                SchemaChange::AddRelationship(relationship) => MigrationStep::AddRelationship {
+                    relationship: relationship.clone(),
+                    sql_script: self.add_relationship_sql(relationship)?,
+                    down_sql_script: Some(self.drop_relationship_sql(relationship)),
+                },
+                SchemaChange::DropRelationship(relationship) => MigrationStep::DropRelationship {
+                    relationship: relationship.clone(),
+                    sql_script: self.drop_relationship_sql(relationship),
+                    down_sql_script: Some(self.add_relationship_sql(relationship)?),
+                },
+                SchemaChange::CreateEnum(_)
+                | SchemaChange::DropEnum(_)
+                | SchemaChange::AlterEnum { .. } => {
+                    // enum columns are stored as VARCHAR, the labels live in the schema only
+                    continue;
+                }
+            };
+            migration_steps.push(step);
+        }
+
+        Ok(MigrationPlan::new(migration_steps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::constraint::{CheckConstraint, DefaultValue};
+    use crate::schema::relationship::RelationshipType;
+    use crate::test_fixtures::users_and_orders_keyed_by;
+
+    use super::*;
+
+    /// The shared users and orders keyed by strings, with an `active` flag on users and a
+    /// nullable `note` on orders.
+    fn users_and_orders() -> Schema {
+        let mut schema = users_and_orders_keyed_by(DataType::String);
+        schema
+            .get_table_mut("users")
+            .unwrap()
+            .add_column(Column::new(
+                "active",
+                DataType::Boolean,
+                false,
+                false,
+                false,
+            ))
+            .unwrap();
+        schema
+            .get_table_mut("orders")
+            .unwrap()
+            .add_column(Column::new("note", DataType::String, false, false, true))
+            .unwrap();
+        schema
+    }
+
+    #[test]
+    fn test_create_tables() {
+        let mut schema = users_and_orders();
+        schema
+            .add_relation(Relationship::new(
+                "users",
+                "id",
+                "orders",
+                "user_id",
+                RelationshipType::OneToMany,
+            ))
+            .unwrap();
+
+        let plan = MySqlEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "CREATE TABLE `users` (\n    \
+             `id` VARCHAR(255) PRIMARY KEY,\n    \
+             `active` TINYINT(1) NOT NULL\n) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;\n\
+             CREATE TABLE `orders` (\n    \
+             `id` INT PRIMARY KEY,\n    \
+             `user_id` VARCHAR(255) NOT NULL,\n    \
+             `note` TEXT\n) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;\n\
+             ALTER TABLE `orders` ADD CONSTRAINT `orders_user_id_fk` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`);\n"
+        );
+        assert_eq!(
+            plan.get_down_sql().unwrap(),
+            "ALTER TABLE `orders` DROP FOREIGN KEY `orders_user_id_fk`;\n\
+             DROP TABLE `orders`;\n\
+             DROP TABLE `users`;\n"
+        );
+    }
+
+    #[test]
+    fn test_modify_column() {
+        let current = users_and_orders();
+        let mut desired = users_and_orders();
+        let orders_table = desired.get_table_mut("orders").unwrap();
+        *orders_table.get_columns_mut()[0].get_data_type_mut() = DataType::Float;
+        *orders_table.get_columns_mut()[2].get_data_type_mut() = DataType::Date;
+
+        let plan = MySqlEngine::default()
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "ALTER TABLE `orders` MODIFY COLUMN `id` DOUBLE;\n\
+             ALTER TABLE `orders` MODIFY COLUMN `note` DATE;\n"
+        );
+    }
+
+    #[test]
+    fn test_type_mapping() {
+        let mut engine = MySqlEngine::default();
+        assert_eq!(engine.get_data_type("tinyint(1)"), Some(DataType::Boolean));
+        assert_eq!(engine.get_data_type("int(11)"), Some(DataType::Integer));
+        assert_eq!(
+            engine.get_data_type("varchar(255)"),
+            Some(DataType::Varchar(255))
+        );
+        assert_eq!(
+            engine.get_db_type(&DataType::Timestamp {
+                precision: Some(6),
+                with_time_zone: false
+            }),
+            Ok("DATETIME(6)".to_string())
+        );
+        assert!(engine
+            .get_db_type(&DataType::Time {
+                precision: None,
+                with_time_zone: true
+            })
+            .is_err());
+        assert_eq!(engine.get_data_type("char(36)"), Some(DataType::Uuid));
+
+        engine
+            .get_type_mapping_mut()
+            .add_override(DataType::String, "VARCHAR(320)");
+        let key = Column::new("email", DataType::String, true, false, false);
+        assert_eq!(
+            engine.column_type(&Table::new("users"), &key).unwrap(),
+            "VARCHAR(320)"
+        );
+    }
+
+    #[test]
+    fn test_array_columns_are_rejected() {
+        let mut schema = users_and_orders();
+        schema
+            .get_table_mut("users")
+            .unwrap()
+            .add_column(Column::new(
+                "tags",
+                DataType::Array(Box::new(DataType::String)),
+                false,
+                false,
+                false,
+            ))
+            .unwrap();
+        let error = MySqlEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap_err();
+        assert!(error.contains("not supported"));
+    }
+
+    #[test]
+    fn test_column_constraints() {
+        let mut schema = Schema::new();
+        schema.add_table("accounts").unwrap();
+        let mut email = Column::new("email", DataType::Varchar(320), false, false, false);
+        *email.is_unique_mut() = true;
+        let mut active = Column::new("active", DataType::Boolean, false, false, false);
+        *active.get_default_mut() = Some(DefaultValue::Boolean(true));
+        active
+            .add_check(CheckConstraint::new(
+                "accounts_active_check",
+                "active IN (0, 1)",
+            ))
+            .unwrap();
+        let accounts_table = schema.get_table_mut("accounts").unwrap();
+        accounts_table.add_column(email).unwrap();
+        accounts_table.add_column(active).unwrap();
+
+        let engine = MySqlEngine::default();
+        let plan = engine.generate_migration_plan(&mut schema.clone()).unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "CREATE TABLE `accounts` (\n    \
+             `email` VARCHAR(320) NOT NULL,\n    \
+             `active` TINYINT(1) DEFAULT TRUE NOT NULL CONSTRAINT `accounts_active_check` CHECK (active IN (0, 1)),\n    \
+             CONSTRAINT `accounts_email_key` UNIQUE (`email`)\n\
+             ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;\n"
+        );
+
+        let mut desired = schema.clone();
+        let columns = desired.get_table_mut("accounts").unwrap().get_columns_mut();
+        *columns[0].is_unique_mut() = false;
+        columns[1].get_checks_mut().clear();
+        let plan = engine
+            .generate_diff_migration_plan(&schema, &desired)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "ALTER TABLE `accounts` DROP INDEX `accounts_email_key`;\n\
+             ALTER TABLE `accounts` DROP CHECK `accounts_active_check`;\n"
+        );
+    }
+
+    #[test]
+    fn test_unique_and_indexed_string_columns() {
+        let mut schema = Schema::new();
+        schema.add_table("accounts").unwrap();
+        let mut email = Column::new("email", DataType::String, false, false, false);
+        *email.is_unique_mut() = true;
+        let accounts_table = schema.get_table_mut("accounts").unwrap();
+        accounts_table.add_column(email).unwrap();
+        accounts_table
+            .add_column(Column::new("name", DataType::String, false, false, false))
+            .unwrap();
+        accounts_table
+            .add_column(Column::new("bio", DataType::String, false, false, true))
+            .unwrap();
+        accounts_table
+            .add_index(Index::new("accounts_name_idx", &["name"]))
+            .unwrap();
+
+        let engine = MySqlEngine::default();
+        let plan = engine.generate_migration_plan(&mut schema.clone()).unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "CREATE TABLE `accounts` (\n    \
+             `email` VARCHAR(255) NOT NULL,\n    \
+             `name` VARCHAR(255) NOT NULL,\n    \
+             `bio` TEXT,\n    \
+             CONSTRAINT `accounts_email_key` UNIQUE (`email`)\n\
+             ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;\n\
+             CREATE INDEX `accounts_name_idx` ON `accounts` (`name`);\n"
+        );
+
+        let mut desired = schema.clone();
+        *desired.get_table_mut("accounts").unwrap().get_columns_mut()[2].is_unique_mut() = true;
+        let plan = engine
+            .generate_diff_migration_plan(&schema, &desired)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "ALTER TABLE `accounts` MODIFY COLUMN `bio` VARCHAR(255);\n\
+             ALTER TABLE `accounts` ADD CONSTRAINT `accounts_bio_key` UNIQUE (`bio`);\n"
+        );
+        assert_eq!(
+            plan.get_down_sql().unwrap(),
+            "ALTER TABLE `accounts` DROP INDEX `accounts_bio_key`;\n\
+             ALTER TABLE `accounts` MODIFY COLUMN `bio` TEXT;\n"
+        );
+    }
+
+    #[test]
+    fn test_composite_primary_key() {
+        let mut schema = Schema::new();
+        schema.add_table("order_items").unwrap();
+        let table = schema.get_table_mut("order_items").unwrap();
+        for name in ["order_id", "product_id"] {
+            table
+                .add_column(Column::new(name, DataType::Integer, true, false, false))
+                .unwrap();
+        }
+        let plan = MySqlEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "CREATE TABLE `order_items` (\n    \
+             `order_id` INT,\n    \
+             `product_id` INT,\n    \
+             PRIMARY KEY (`order_id`, `product_id`)\n\
+             ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;\n"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_referential_actions() {
+        let mut schema = users_and_orders();
+        let mut relationship = Relationship::new(
+            "users",
+            "id",
+            "orders",
+            "user_id",
+            RelationshipType::OneToMany,
+        );
+        relationship.set_on_update(ReferentialAction::SetDefault);
+        schema.add_relation(relationship).unwrap();
+        assert!(MySqlEngine::default()
+            .generate_migration_plan(&mut schema)
+            .is_err());
+    }
+
+    #[test]
+    fn test_indexes() {
+        let current = users_and_orders();
+        let mut desired = users_and_orders();
+        let mut user_id = Index::new("orders_user_id", &["user_id", "id"]);
+        user_id.get_columns_mut()[1].set_order(SortOrder::Desc);
+        user_id.set_method(IndexMethod::Hash);
+        desired
+            .get_table_mut("orders")
+            .unwrap()
+            .add_index(user_id)
+            .unwrap();
+
+        let plan = MySqlEngine::default()
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "CREATE INDEX `orders_user_id` ON `orders` (`user_id`, `id` DESC) USING HASH;\n"
+        );
+        assert_eq!(
+            plan.get_steps()[0].get_down_sql_script(),
+            Some("DROP INDEX `orders_user_id` ON `orders`;\n")
+        );
+
+        // the TEXT column becomes a VARCHAR(255) while it is indexed
+        let mut indexed = users_and_orders();
+        indexed
+            .get_table_mut("orders")
+            .unwrap()
+            .add_index(Index::new("orders_note_idx", &["note"]))
+            .unwrap();
+        let plan = MySqlEngine::default()
+            .generate_diff_migration_plan(&current, &indexed)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "ALTER TABLE `orders` MODIFY COLUMN `note` VARCHAR(255);\n\
+             CREATE INDEX `orders_note_idx` ON `orders` (`note`);\n"
+        );
+        assert_eq!(
+            plan.get_down_sql().unwrap(),
+            "DROP INDEX `orders_note_idx` ON `orders`;\n\
+             ALTER TABLE `orders` MODIFY COLUMN `note` TEXT;\n"
+        );
+        let plan = MySqlEngine::default()
+            .generate_diff_migration_plan(&indexed, &current)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "DROP INDEX `orders_note_idx` ON `orders`;\n\
+             ALTER TABLE `orders` MODIFY COLUMN `note` TEXT;\n"
+        );
+
+        // an overridden String type stays TEXT, which can't be indexed
+        let mut engine = MySqlEngine::default();
+        engine
+            .get_type_mapping_mut()
+            .add_override(DataType::String, "TEXT");
+        let error = engine
+            .generate_diff_migration_plan(&current, &indexed)
+            .unwrap_err();
+        assert_eq!(
+            error,
+            "Index orders_note_idx: column note has the type TEXT, which MySQL can't index without a prefix length"
+        );
+
+        let mut partial = Index::new("orders_note", &["note"]);
+        partial.set_predicate(Some("note IS NOT NULL"));
+        desired
+            .get_table_mut("orders")
+            .unwrap()
+            .add_index(partial)
+            .unwrap();
+        assert!(MySqlEngine::default()
+            .generate_diff_migration_plan(&current, &desired)
+            .is_err());
+    }
+}
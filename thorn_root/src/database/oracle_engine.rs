@@ -0,0 +1,907 @@
+use crate::migration::migration_plan::{MigrationPlan, MigrationStep};
+use crate::migration::schema_diff::{SchemaChange, SchemaDiff};
+use crate::schema::column::Column;
+use crate::schema::constraint::{DefaultValue, PrimaryKey};
+use crate::schema::data_type::DataType;
+use crate::schema::index::{Index, IndexMethod, SortOrder};
+use crate::schema::relationship::{ReferentialAction, Relationship};
+use crate::schema::table::Table;
+use crate::schema::Schema;
+
+use super::engine::{quote_identifier, referential_actions_sql, DatabaseEngine};
+use super::type_mapping::{base_db_type, db_type_parameters, time_db_type, TypeMapping};
+
+/// Maximum identifier length of Oracle 12.2 and later.
+pub const ORACLE_MAX_IDENTIFIER_LENGTH: usize = 128;
+
+/// Maximum identifier length of Oracle 12.1 and earlier.
+pub const ORACLE_LEGACY_MAX_IDENTIFIER_LENGTH: usize = 30;
+
+/// Words Oracle reserves, which are quoted when used as names.
+const RESERVED_WORDS: [&str; 108] = [
+    "ACCESS",
+    "ADD",
+    "ALL",
+    "ALTER",
+    "AND",
+    "ANY",
+    "AS",
+    "ASC",
+    "AUDIT",
+    "BETWEEN",
+    "BY",
+    "CHAR",
+    "CHECK",
+    "CLUSTER",
+    "COLUMN",
+    "COMMENT",
+    "COMPRESS",
+    "CONNECT",
+    "CREATE",
+    "CURRENT",
+    "DATE",
+    "DECIMAL",
+    "DEFAULT",
+    "DELETE",
+    "DESC",
+    "DISTINCT",
+    "DROP",
+    "ELSE",
+    "EXCLUSIVE",
+    "EXISTS",
+    "FILE",
+    "FLOAT",
+    "FOR",
+    "FROM",
+    "GRANT",
+    "GROUP",
+    "HAVING",
+    "IDENTIFIED",
+    "IMMEDIATE",
+    "IN",
+    "INCREMENT",
+    "INDEX",
+    "INITIAL",
+    "INSERT",
+    "INTEGER",
+    "INTERSECT",
+    "INTO",
+    "IS",
+    "LEVEL",
+    "LIKE",
+    "LOCK",
+    "LONG",
+    "MAXEXTENTS",
+    "MINUS",
+    "MLSLABEL",
+    "MODE",
+    "MODIFY",
+    "NOAUDIT",
+    "NOCOMPRESS",
+    "NOT",
+    "NOWAIT",
+    "NULL",
+    "NUMBER",
+    "OF",
+    "OFFLINE",
+    "ON",
+    "ONLINE",
+    "OPTION",
+    "OR",
+    "ORDER",
+    "PCTFREE",
+    "PRIOR",
+    "PUBLIC",
+    "RAW",
+    "RENAME",
+    "RESOURCE",
+    "REVOKE",
+    "ROW",
+    "ROWID",
+    "ROWNUM",
+    "ROWS",
+    "SELECT",
+    "SESSION",
+    "SET",
+    "SHARE",
+    "SIZE",
+    "SMALLINT",
+    "START",
+    "SUCCESSFUL",
+    "SYNONYM",
+    "SYSDATE",
+    "TABLE",
+    "THEN",
+    "TO",
+    "TRIGGER",
+    "UID",
+    "UNION",
+    "UNIQUE",
+    "UPDATE",
+    "USER",
+    "VALIDATE",
+    "VALUES",
+    "VARCHAR",
+    "VARCHAR2",
+    "VIEW",
+    "WHENEVER",
+    "WHERE",
+    "WITH",
+];
+
+pub struct OracleEngine {
+    max_identifier_length: usize,
+    type_mapping: TypeMapping,
+}
+
+impl Default for OracleEngine {
+    fn default() -> Self {
+        Self::new(ORACLE_MAX_IDENTIFIER_LENGTH)
+    }
+}
+
+impl OracleEngine {
+    /// Creates an engine which rejects table and column names longer than
+    /// `max_identifier_length` and shortens generated constraint names to fit it.
+    pub fn new(max_identifier_length: usize) -> Self {
+        Self {
+            max_identifier_length,
+            type_mapping: TypeMapping::default(),
+        }
+    }
+
+    pub fn get_max_identifier_length(&self) -> usize {
+        self.max_identifier_length
+    }
+
+    /// Quotes a reserved or mixed-case identifier, so it keeps its case. Lower case names
+    /// stay unquoted and are stored in upper case, as Oracle does for unquoted names.
+    pub fn quote(&self, identifier: &str) -> String {
+        quote_identifier(identifier, &RESERVED_WORDS)
+    }
+
+    fn quote_list(&self, identifiers: &[String]) -> String {
+        identifiers
+            .iter()
+            .map(|identifier| self.quote(identifier))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    fn check_identifier(&self, identifier: &str) -> Result<(), String> {
+        if identifier.chars().count() > self.max_identifier_length {
+            return Err(format!(
+                "Identifier {} is longer than {} characters allowed by {}",
+                identifier,
+                self.max_identifier_length,
+                OracleEngine::name()
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_table(&self, table: &Table) -> Result<(), String> {
+        self.check_identifier(table.get_name())?;
+        for column in table.get_columns() {
+            self.check_identifier(column.get_name())?;
+        }
+        Ok(())
+    }
+
+    /// Shortens a generated constraint name to the identifier limit. The name is cut and
+    /// suffixed with a hash of the full name, so distinct names stay distinct.
+    fn constraint_name(&self, name: &str) -> String {
+        if name.chars().count() <= self.max_identifier_length {
+            return name.to_string();
+        }
+        // FNV-1a, stable across runs and platforms
+        let hash = name.bytes().fold(0x811c9dc5u32, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x01000193)
+        });
+        let suffix = format!("_{:08x}", hash);
+        let prefix = name
+            .chars()
+            .take(self.max_identifier_length.saturating_sub(suffix.len()))
+            .collect::<String>();
+        format!("{}{}", prefix, suffix)
+    }
+
+    fn column_type(&self, column: &Column) -> Result<String, String> {
+        self.get_db_type(&column.get_data_type())
+    }
+
+    fn default_sql(&self, default: &DefaultValue) -> String {
+        // booleans are stored as NUMBER(1)
+        match default {
+            DefaultValue::Boolean(value) => (*value as u8).to_string(),
+            _ => default.to_sql(),
+        }
+    }
+
+    fn column_definition(
+        &self,
+        table: &str,
+        column: &Column,
+        inline_primary_key: bool,
+    ) -> Result<String, String> {
+        let mut definition = format!(
+            "{} {}",
+            self.quote(column.get_name()),
+            self.column_type(column)?
+        );
+        match column.get_default() {
+            Some(default) => {
+                definition.push_str(&format!(" DEFAULT {}", self.default_sql(default)));
+            }
+            None if inline_primary_key
+                && matches!(
+                    column.get_data_type(),
+                    DataType::Integer | DataType::SmallInt | DataType::BigInt
+                ) =>
+            {
+                definition.push_str(" GENERATED BY DEFAULT AS IDENTITY");
+            }
+            None => {}
+        }
+        if inline_primary_key {
+            definition.push_str(" PRIMARY KEY");
+        } else if !column.is_primary_key() && !column.is_nullable() {
+            definition.push_str(" NOT NULL");
+        }
+        if column.is_unique() {
+            definition.push_str(&format!(
+                " CONSTRAINT {} UNIQUE",
+                self.quote(&self.constraint_name(&column.get_unique_constraint_name(table)))
+            ));
+        }
+        for check in column.get_checks() {
+            self.check_identifier(check.get_name())?;
+            definition.push_str(&format!(
+                " CONSTRAINT {} CHECK ({})",
+                self.quote(check.get_name()),
+                check.get_expression()
+            ));
+        }
+        Ok(definition)
+    }
+
+    fn primary_key_definition(&self, primary_key: &PrimaryKey) -> Result<String, String> {
+        let constraint = match primary_key.get_name() {
+            Some(name) => {
+                self.check_identifier(name)?;
+                format!("CONSTRAINT {} ", self.quote(name))
+            }
+            None => String::default(),
+        };
+        Ok(format!(
+            "{}PRIMARY KEY ({})",
+            constraint,
+            self.quote_list(primary_key.get_columns())
+        ))
+    }
+
+    fn create_table_sql(&self, table: &Table) -> Result<String, String> {
+        self.check_table(table)?;
+        let primary_key = table.get_primary_key();
+        let inline_primary_key = primary_key.is_some_and(|p| p.is_inline());
+        let mut definitions = table
+            .get_columns()
+            .iter()
+            .map(|column| {
+                let inline = inline_primary_key && column.is_primary_key();
+                self.column_definition(table.get_name(), column, inline)
+            })
+            .collect::<Result<Vec<String>, String>>()?;
+        if let (false, Some(primary_key)) = (inline_primary_key, primary_key) {
+            definitions.push(self.primary_key_definition(primary_key)?);
+        }
+        let mut sql = format!(
+            "CREATE TABLE {} (\n    {}\n);\n",
+            self.quote(table.get_name()),
+            definitions.join(",\n    ")
+        );
+        for index in table.get_indexes() {
+            sql.push_str(&self.create_index_sql(table.get_name(), index)?);
+        }
+        Ok(sql)
+    }
+
+    fn drop_table_sql(&self, table: &Table) -> String {
+        format!("DROP TABLE {};\n", self.quote(table.get_name()))
+    }
+
+    fn add_column_sql(&self, table: &str, column: &Column) -> Result<String, String> {
+        self.check_identifier(column.get_name())?;
+        Ok(format!(
+            "ALTER TABLE {} ADD ({});\n",
+            self.quote(table),
+            self.column_definition(table, column, false)?
+        ))
+    }
+
+    fn remove_column_sql(&self, table: &str, column: &Column) -> String {
+        format!(
+            "ALTER TABLE {} DROP COLUMN {};\n",
+            self.quote(table),
+            self.quote(column.get_name())
+        )
+    }
+
+    fn alter_column_sql(&self, table: &str, from: &Column, to: &Column) -> Result<String, String> {
+        let quoted_table = self.quote(table);
+        let column = self.quote(to.get_name());
+        let mut sql = String::default();
+        for check in from.get_checks() {
+            if !to.get_checks().contains(check) {
+                sql.push_str(&format!(
+                    "ALTER TABLE {} DROP CONSTRAINT {};\n",
+                    quoted_table,
+                    self.quote(check.get_name())
+                ));
+            }
+        }
+        if from.is_unique() && !to.is_unique() {
+            sql.push_str(&format!(
+                "ALTER TABLE {} DROP CONSTRAINT {};\n",
+                quoted_table,
+                self.quote(&self.constraint_name(&from.get_unique_constraint_name(table)))
+            ));
+        }
+        if from.get_data_type() != to.get_data_type() {
+            sql.push_str(&format!(
+                "ALTER TABLE {} MODIFY ({} {});\n",
+                quoted_table,
+                column,
+                self.column_type(to)?
+            ));
+        }
+        if from.get_default() != to.get_default() {
+            sql.push_str(&format!(
+                "ALTER TABLE {} MODIFY ({} DEFAULT {});\n",
+                quoted_table,
+                column,
+                to.get_default()
+                    .map(|default| self.default_sql(default))
+                    .unwrap_or("NULL".to_string())
+            ));
+        }
+        if from.is_nullable() != to.is_nullable() {
+            sql.push_str(&format!(
+                "ALTER TABLE {} MODIFY ({} {});\n",
+                quoted_table,
+                column,
+                if to.is_nullable() { "NULL" } else { "NOT NULL" }
+            ));
+        }
+        if !from.is_unique() && to.is_unique() {
+            sql.push_str(&format!(
+                "ALTER TABLE {} ADD CONSTRAINT {} UNIQUE ({});\n",
+                quoted_table,
+                self.quote(&self.constraint_name(&to.get_unique_constraint_name(table))),
+                column
+            ));
+        }
+        for check in to.get_checks() {
+            if !from.get_checks().contains(check) {
+                self.check_identifier(check.get_name())?;
+                sql.push_str(&format!(
+                    "ALTER TABLE {} ADD CONSTRAINT {} CHECK ({});\n",
+                    quoted_table,
+                    self.quote(check.get_name()),
+                    check.get_expression()
+                ));
+            }
+        }
+        Ok(sql)
+    }
+
+    fn add_primary_key_sql(&self, table: &str, primary_key: &PrimaryKey) -> Result<String, String> {
+        let name = match primary_key.get_name() {
+            Some(name) => {
+                self.check_identifier(name)?;
+                name.to_string()
+            }
+            None => self.constraint_name(&format!("{}_pk", table)),
+        };
+        Ok(format!(
+            "ALTER TABLE {} ADD CONSTRAINT {} PRIMARY KEY ({});\n",
+            self.quote(table),
+            self.quote(&name),
+            self.quote_list(primary_key.get_columns())
+        ))
+    }
+
+    fn drop_primary_key_sql(&self, table: &str) -> String {
+        format!("ALTER TABLE {} DROP PRIMARY KEY;\n", self.quote(table))
+    }
+
+    fn create_index_sql(&self, table: &str, index: &Index) -> Result<String, String> {
+        self.check_identifier(index.get_name())?;
+        if index.get_predicate().is_some() || !index.get_include().is_empty() {
+            return Err(format!(
+                "Index {}: partial and covering indexes are not supported by {}",
+                index.get_name(),
+                OracleEngine::name()
+            ));
+        }
+        if index.get_method() != IndexMethod::BTree
+            || index.get_columns().iter().any(|c| c.get_nulls().is_some())
+        {
+            return Err(format!(
+                "Index {}: only btree indexes with the default NULLS order are supported by {}",
+                index.get_name(),
+                OracleEngine::name()
+            ));
+        }
+        let columns = index
+            .get_columns()
+            .iter()
+            .map(|c| match c.get_order() {
+                SortOrder::Asc => self.quote(c.get_name()),
+                SortOrder::Desc => format!("{} DESC", self.quote(c.get_name())),
+            })
+            .collect::<Vec<String>>();
+        Ok(format!(
+            "CREATE {}INDEX {} ON {} ({});\n",
+            if index.is_unique() { "UNIQUE " } else { "" },
+            self.quote(index.get_name()),
+            self.quote(table),
+            columns.join(", ")
+        ))
+    }
+
+    fn drop_index_sql(&self, index: &Index) -> String {
+        format!("DROP INDEX {};\n", self.quote(index.get_name()))
+    }
+
+    /// Renders the referential actions and deferral options following `REFERENCES`.
+    /// Oracle only knows `ON DELETE CASCADE` and `ON DELETE SET NULL`, other actions are errors.
+    fn checked_referential_actions_sql(
+        &self,
+        relationship: &Relationship,
+    ) -> Result<String, String> {
+        let unsupported = |clause: &str, action: ReferentialAction| {
+            format!(
+                "Relationship {} uses {} {}, which {} doesn't support",
+                relationship.get_code(),
+                clause,
+                action.to_sql(),
+                OracleEngine::name()
+            )
+        };
+        match relationship.get_on_delete() {
+            ReferentialAction::NoAction
+            | ReferentialAction::Cascade
+            | ReferentialAction::SetNull => {}
+            action => return Err(unsupported("ON DELETE", action)),
+        }
+        if relationship.get_on_update() != ReferentialAction::NoAction {
+            return Err(unsupported("ON UPDATE", relationship.get_on_update()));
+        }
+        Ok(referential_actions_sql(relationship))
+    }
+
+    fn add_relationship_sql(&self, relationship: &Relationship) -> Result<String, String> {
+        Ok(format!(
+            "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({}){};\n",
+            self.quote(relationship.get_to_table()),
+            self.quote(&self.constraint_name(&relationship.get_constraint_name())),
+            self.quote_list(relationship.get_to_columns()),
+            self.quote(relationship.get_from_table()),
+            self.quote_list(relationship.get_from_columns()),
+            self.checked_referential_actions_sql(relationship)?,
+        ))
+    }
+
+    fn drop_relationship_sql(&self, relationship: &Relationship) -> String {
+        format!(
+            "ALTER TABLE {} DROP CONSTRAINT {};\n",
+            self.quote(relationship.get_to_table()),
+            self.quote(&self.constraint_name(&relationship.get_constraint_name())),
+        )
+    }
+}
+
+impl DatabaseEngine for OracleEngine {
+    fn name() -> &'static str {
+        "Oracle"
+    }
+
+    fn get_type_mapping(&self) -> &TypeMapping {
+        &self.type_mapping
+    }
+
+    fn get_type_mapping_mut(&mut self) -> &mut TypeMapping {
+        &mut self.type_mapping
+    }
+
+    fn default_db_type(&self, data_type: &DataType) -> Option<String> {
+        let db_type = match data_type {
+            DataType::Integer => "NUMBER".to_string(),
+            DataType::Float => "FLOAT".to_string(),
+            DataType::String => "VARCHAR2(4000)".to_string(),
+            DataType::Boolean => "NUMBER(1)".to_string(),
+            DataType::Date => "DATE".to_string(),
+            DataType::SmallInt => "NUMBER(5)".to_string(),
+            DataType::BigInt => "NUMBER(19)".to_string(),
+            DataType::Decimal { precision, scale } => {
+                format!("NUMBER({}, {})", precision, scale)
+            }
+            DataType::Varchar(length) => format!("VARCHAR2({})", length),
+            DataType::Timestamp {
+                precision,
+                with_time_zone,
+            } => time_db_type("TIMESTAMP", precision, *with_time_zone),
+            // Oracle has no type for a time of day
+            DataType::Time { .. } => return None,
+            DataType::Uuid => "RAW(16)".to_string(),
+            DataType::Json | DataType::Jsonb => "JSON".to_string(),
+            DataType::Binary => "BLOB".to_string(),
+            DataType::Array(_) => return None,
+            DataType::Enum(_) => "VARCHAR2(255)".to_string(),
+        };
+        Some(db_type)
+    }
+
+    fn default_data_type(&self, db_type: &str) -> Option<DataType> {
+        let parameters = db_type_parameters(db_type);
+        match (base_db_type(db_type).as_str(), &parameters[..]) {
+            ("NUMBER", [1]) => Some(DataType::Boolean),
+            ("NUMBER", [5]) => Some(DataType::SmallInt),
+            ("NUMBER", [19]) => Some(DataType::BigInt),
+            ("NUMBER", [precision, scale]) => Some(DataType::Decimal {
+                precision: u16::try_from(*precision).ok()?,
+                scale: u16::try_from(*scale).ok()?,
+            }),
+            ("NUMBER" | "INTEGER" | "INT", _) => Some(DataType::Integer),
+            ("FLOAT" | "BINARY_DOUBLE" | "BINARY_FLOAT", _) => Some(DataType::Float),
+            ("VARCHAR2" | "NVARCHAR2" | "VARCHAR", [4000]) => Some(DataType::String),
+            ("VARCHAR2" | "NVARCHAR2" | "VARCHAR", [length]) => Some(DataType::Varchar(*length)),
+            ("VARCHAR2" | "NVARCHAR2" | "VARCHAR" | "CHAR" | "NCHAR" | "CLOB" | "NCLOB", _) => {
+                Some(DataType::String)
+            }
+            ("DATE", _) => Some(DataType::Date),
+            ("TIMESTAMP", _) => Some(DataType::Timestamp {
+                precision: parameters.first().and_then(|p| u8::try_from(*p).ok()),
+                with_time_zone: false,
+            }),
+            ("TIMESTAMP WITH TIME ZONE", _) => Some(DataType::Timestamp {
+                precision: parameters.first().and_then(|p| u8::try_from(*p).ok()),
+                with_time_zone: true,
+            }),
+            ("RAW", [16]) => Some(DataType::Uuid),
+            ("JSON", _) => Some(DataType::Json),
+            ("BLOB" | "RAW", _) => Some(DataType::Binary),
+            _ => None,
+        }
+    }
+
+    fn generate_migration_plan(&self, schema: &mut Schema) -> Result<MigrationPlan, String> {
+        self.generate_diff_migration_plan(&Schema::new(), schema)
+    }
+
+    fn generate_diff_migration_plan(
+        &self,
+        current: &Schema,
+        desired: &Schema,
+    ) -> Result<MigrationPlan, String> {
+        desired.validate()?;
+        let mut migration_steps = Vec::<MigrationStep>::default();
+
+        for change in SchemaDiff::new(current, desired).get_changes() {
+            let step = match change {
+                SchemaChange::CreateTable(table) => MigrationStep::CreateTable {
+                    name: table.get_name().to_string(),
+                    sql_script: self.create_table_sql(table)?,
+                    down_sql_script: Some(self.drop_table_sql(table)),
+                },
+                SchemaChange::DropTable(table) => MigrationStep::DropTable {
+                    name: table.get_name().to_string(),
+                    sql_script: self.drop_table_sql(table),
+                    down_sql_script: Some(self.create_table_sql(table)?),
+                },
+                SchemaChange::AddColumn { table, column } => MigrationStep::AddColumn {
+                    table: table.clone(),
+                    column: column.clone(),
+                    sql_script: self.add_column_sql(table, column)?,
+                    down_sql_script: Some(self.remove_column_sql(table, column)),
+                },
+                SchemaChange::RemoveColumn { table, column } => MigrationStep::RemoveColumn {
+                    table: table.clone(),
+                    column: column.clone(),
+                    sql_script: self.remove_column_sql(table, column),
+                    down_sql_script: Some(self.add_column_sql(table, column)?),
+                },
+                SchemaChange::AlterColumn { table, from, to } => {
+                    let sql_script = self.alter_column_sql(table, from, to)?;
+                    if sql_script.is_empty() {
+                        continue;
+                    }
+                    MigrationStep::AlterColumn {
+                        table: table.clone(),
+                        column: to.clone(),
+                        previous_column: from.clone(),
+                        sql_script,
+                        down_sql_script: Some(self.alter_column_sql(table, to, from)?),
+                    }
+                }
+                SchemaChange::AddPrimaryKey { table, primary_key } => {
+                    MigrationStep::AddPrimaryKey {
+                        table: table.clone(),
+                        primary_key: primary_key.clone(),
+                        sql_script: self.add_primary_key_sql(table, primary_key)?,
+                        down_sql_script: Some(self.drop_primary_key_sql(table)),
+                    }
+                }
+                SchemaChange::DropPrimaryKey { table, primary_key } => {
+                    MigrationStep::DropPrimaryKey {
+                        table: table.clone(),
+                        primary_key: primary_key.clone(),
+                        sql_script: self.drop_primary_key_sql(table),
+                        down_sql_script: Some(self.add_primary_key_sql(table, primary_key)?),
+                    }
+                }
+                SchemaChange::AddIndex { table, index } => MigrationStep::AddIndex {
+                    table: table.clone(),
+                    index: index.clone(),
+                    concurrently: false,
+                    sql_script: self.create_index_sql(table, index)?,
+                    down_sql_script: Some(self.drop_index_sql(index)),
+                },
+                SchemaChange::DropIndex { table, index } => MigrationStep::DropIndex {
+                    table: table.clone(),
+                    index: index.clone(),
+                    concurrently: false,
+                    sql_script: self.drop_index_sql(index),
+                    down_sql_script: Some(self.create_index_sql(table, index)?),
+                },
+                SchemaChange::AddRelationship(relationship)
+                | SchemaChange::DropRelationship(relationship)
+                    if relationship.is_many_to_many() =>
+                {
+                    // modeled by the foreign keys of its junction table
+                    continue;
+                }
+                SchemaChange::AddRelationship(relationship) => MigrationStep::AddRelationship {
+                    relationship: relationship.clone(),
+                    sql_script: self.add_relationship_sql(relationship)?,
+                    down_sql_script: Some(self.drop_relationship_sql(relationship)),
+                },
+                SchemaChange::DropRelationship(relationship) => MigrationStep::DropRelationship {
+                    relationship: relationship.clone(),
+                    sql_script: self.drop_relationship_sql(relationship),
+                    down_sql_script: Some(self.add_relationship_sql(relationship)?),
+                },
+                SchemaChange::CreateEnum(_)
+                | SchemaChange::DropEnum(_)
+                | SchemaChange::AlterEnum { .. } => {
+                    // Oracle has no enum types, their columns are stored as VARCHAR2
+                    continue;
+                }
+            };
+            migration_steps.push(step);
+        }
+
+        Ok(MigrationPlan::new(migration_steps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::constraint::{DefaultValue, PrimaryKey};
+    use crate::schema::relationship::RelationshipType;
+
+    use super::*;
+
+    fn customers_and_invoices() -> Schema {
+        let mut schema = Schema::new();
+        schema.add_table("customers").unwrap();
+        schema.add_table("invoices").unwrap();
+        let customers_table = schema.get_table_mut("customers").unwrap();
+        customers_table
+            .add_column(Column::new("id", DataType::Integer, true, false, false))
+            .unwrap();
+        customers_table
+            .add_column(Column::new("name", DataType::String, false, false, false))
+            .unwrap();
+        let invoices_table = schema.get_table_mut("invoices").unwrap();
+        invoices_table
+            .add_column(Column::new(
+                "customer_identifier",
+                DataType::Integer,
+                false,
+                true,
+                false,
+            ))
+            .unwrap();
+        invoices_table
+            .add_column(Column::new("paid", DataType::Boolean, false, false, false))
+            .unwrap();
+        schema
+            .add_relation(Relationship::new(
+                "customers",
+                "id",
+                "invoices",
+                "customer_identifier",
+                RelationshipType::OneToMany,
+            ))
+            .unwrap();
+        schema
+    }
+
+    #[test]
+    fn test_create_tables() {
+        let mut schema = customers_and_invoices();
+        let plan = OracleEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "CREATE TABLE customers (\n    \
+             id NUMBER GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,\n    \
+             name VARCHAR2(4000) NOT NULL\n);\n\
+             CREATE TABLE invoices (\n    \
+             customer_identifier NUMBER NOT NULL,\n    \
+             paid NUMBER(1) NOT NULL\n);\n\
+             ALTER TABLE invoices ADD CONSTRAINT invoices_customer_identifier_fk \
+             FOREIGN KEY (customer_identifier) REFERENCES customers (id);\n"
+        );
+    }
+
+    #[test]
+    fn test_legacy_identifier_length() {
+        let mut schema = customers_and_invoices();
+        let plan = OracleEngine::new(ORACLE_LEGACY_MAX_IDENTIFIER_LENGTH)
+            .generate_migration_plan(&mut schema)
+            .unwrap();
+        let MigrationStep::AddRelationship { sql_script, .. } = &plan.get_steps()[2] else {
+            panic!("expected a relationship step");
+        };
+        let constraint_name = sql_script.split(' ').nth(5).unwrap();
+        assert_eq!(constraint_name.len(), ORACLE_LEGACY_MAX_IDENTIFIER_LENGTH);
+        assert!(constraint_name.starts_with("invoices_customer_"));
+
+        schema
+            .add_table("a_table_name_longer_than_thirty_chars")
+            .unwrap();
+        assert!(OracleEngine::new(ORACLE_LEGACY_MAX_IDENTIFIER_LENGTH)
+            .generate_migration_plan(&mut schema)
+            .is_err());
+        assert!(OracleEngine::default()
+            .generate_migration_plan(&mut schema)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_alter_column() {
+        let current = customers_and_invoices();
+        let mut desired = customers_and_invoices();
+        let invoices_table = desired.get_table_mut("invoices").unwrap();
+        *invoices_table.get_columns_mut()[1].get_data_type_mut() = DataType::Date;
+        invoices_table
+            .add_column(Column::new("total", DataType::Float, false, false, true))
+            .unwrap();
+
+        let plan = OracleEngine::default()
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "ALTER TABLE invoices MODIFY (paid DATE);\n\
+             ALTER TABLE invoices ADD (total FLOAT);\n"
+        );
+    }
+
+    #[test]
+    fn test_type_mapping() {
+        let engine = OracleEngine::default();
+        assert_eq!(engine.get_db_type(&DataType::Boolean).unwrap(), "NUMBER(1)");
+        assert_eq!(engine.get_data_type("number(1)"), Some(DataType::Boolean));
+        assert_eq!(engine.get_data_type("NUMBER(10)"), Some(DataType::Integer));
+        assert_eq!(
+            engine.get_data_type("varchar2(100)"),
+            Some(DataType::Varchar(100))
+        );
+        assert_eq!(
+            engine.get_data_type("varchar2(4000)"),
+            Some(DataType::String)
+        );
+        assert_eq!(
+            engine.get_data_type("number(12, 2)"),
+            Some(DataType::Decimal {
+                precision: 12,
+                scale: 2
+            })
+        );
+        assert!(engine
+            .get_db_type(&DataType::Time {
+                precision: None,
+                with_time_zone: false
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_array_columns_are_rejected() {
+        let mut schema = customers_and_invoices();
+        schema
+            .get_table_mut("customers")
+            .unwrap()
+            .add_column(Column::new(
+                "tags",
+                DataType::Array(Box::new(DataType::String)),
+                false,
+                false,
+                false,
+            ))
+            .unwrap();
+        let error = OracleEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap_err();
+        assert!(error.contains("not supported"));
+    }
+
+    #[test]
+    fn test_column_constraints() {
+        let mut schema = Schema::new();
+        schema.add_table("accounts").unwrap();
+        let mut id = Column::new("id", DataType::Integer, true, false, false);
+        *id.get_default_mut() = Some(DefaultValue::Expression("accounts_seq.NEXTVAL".to_string()));
+        let mut active = Column::new("active", DataType::Boolean, false, false, false);
+        *active.get_default_mut() = Some(DefaultValue::Boolean(false));
+        *active.is_unique_mut() = true;
+        let accounts_table = schema.get_table_mut("accounts").unwrap();
+        accounts_table.add_column(id).unwrap();
+        accounts_table.add_column(active).unwrap();
+
+        let plan = OracleEngine::new(ORACLE_LEGACY_MAX_IDENTIFIER_LENGTH)
+            .generate_migration_plan(&mut schema.clone())
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "CREATE TABLE accounts (\n    \
+             id NUMBER DEFAULT accounts_seq.NEXTVAL PRIMARY KEY,\n    \
+             active NUMBER(1) DEFAULT 0 NOT NULL CONSTRAINT accounts_active_key UNIQUE\n);\n"
+        );
+
+        let mut desired = schema.clone();
+        *desired.get_table_mut("accounts").unwrap().get_columns_mut()[1].is_nullable_mut() = true;
+        let plan = OracleEngine::default()
+            .generate_diff_migration_plan(&schema, &desired)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "ALTER TABLE accounts MODIFY (active NULL);\n"
+        );
+    }
+
+    #[test]
+    fn test_composite_primary_key() {
+        let current = customers_and_invoices();
+        let mut desired = current.clone();
+        desired
+            .get_table_mut("invoices")
+            .unwrap()
+            .set_primary_key(Some(PrimaryKey::new(&["customer_identifier", "paid"])))
+            .unwrap();
+        let plan = OracleEngine::new(ORACLE_LEGACY_MAX_IDENTIFIER_LENGTH)
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "ALTER TABLE invoices ADD CONSTRAINT invoices_pk PRIMARY KEY (customer_identifier, paid);\n"
+        );
+        assert_eq!(
+            plan.get_down_sql().unwrap(),
+            "ALTER TABLE invoices DROP PRIMARY KEY;\n"
+        );
+        let plan = OracleEngine::default()
+            .generate_migration_plan(&mut desired)
+            .unwrap();
+        assert!(plan.get_sql().contains(
+            "customer_identifier NUMBER,\n    \
+             paid NUMBER(1),\n    \
+             PRIMARY KEY (customer_identifier, paid)\n);\n"
+        ));
+    }
+}
@@ -1,89 +1,747 @@
 use crate::migration::migration_plan::{MigrationPlan, MigrationStep};
+use crate::migration::schema_diff::{SchemaChange, SchemaDiff};
+use crate::schema::column::Column;
+use crate::schema::constraint::PrimaryKey;
+use crate::schema::data_type::DataType;
+use crate::schema::enum_type::EnumType;
+use crate::schema::index::{Index, IndexColumn, IndexMethod, NullsOrder, SortOrder};
+use crate::schema::relationship::Relationship;
+use crate::schema::table::Table;
 use crate::schema::Schema;
 
-use super::engine::DatabaseEngine;
+use super::engine::{quote_identifier, referential_actions_sql, DatabaseEngine};
+use super::type_mapping::{base_db_type, db_type_parameters, time_db_type, TypeMapping};
+
+/// Keywords PostgreSQL doesn't accept as table or column names without quotes.
+const RESERVED_KEYWORDS: [&str; 98] = [
+    "ALL",
+    "ANALYSE",
+    "ANALYZE",
+    "AND",
+    "ANY",
+    "ARRAY",
+    "AS",
+    "ASC",
+    "ASYMMETRIC",
+    "AUTHORIZATION",
+    "BINARY",
+    "BOTH",
+    "CASE",
+    "CAST",
+    "CHECK",
+    "COLLATE",
+    "COLLATION",
+    "COLUMN",
+    "CONCURRENTLY",
+    "CONSTRAINT",
+    "CREATE",
+    "CROSS",
+    "CURRENT_CATALOG",
+    "CURRENT_DATE",
+    "CURRENT_ROLE",
+    "CURRENT_SCHEMA",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "CURRENT_USER",
+    "DEFAULT",
+    "DEFERRABLE",
+    "DESC",
+    "DISTINCT",
+    "DO",
+    "ELSE",
+    "END",
+    "EXCEPT",
+    "FALSE",
+    "FETCH",
+    "FOR",
+    "FOREIGN",
+    "FREEZE",
+    "FROM",
+    "FULL",
+    "GRANT",
+    "GROUP",
+    "HAVING",
+    "ILIKE",
+    "IN",
+    "INITIALLY",
+    "INNER",
+    "INTERSECT",
+    "INTO",
+    "IS",
+    "ISNULL",
+    "JOIN",
+    "LATERAL",
+    "LEADING",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "LOCALTIME",
+    "LOCALTIMESTAMP",
+    "NATURAL",
+    "NOT",
+    "NOTNULL",
+    "NULL",
+    "OFFSET",
+    "ON",
+    "ONLY",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "OVERLAPS",
+    "PLACING",
+    "PRIMARY",
+    "REFERENCES",
+    "RETURNING",
+    "RIGHT",
+    "SELECT",
+    "SESSION_USER",
+    "SIMILAR",
+    "SOME",
+    "SYMMETRIC",
+    "SYSTEM_USER",
+    "TABLE",
+    "TABLESAMPLE",
+    "THEN",
+    "TO",
+    "TRAILING",
+    "TRUE",
+    "UNION",
+    "UNIQUE",
+    "USER",
+    "USING",
+    "VARIADIC",
+    "VERBOSE",
+    "WHEN",
+];
 
 #[derive(Default)]
-pub struct PostgresEngine;
+pub struct PostgresEngine {
+    type_mapping: TypeMapping,
+    concurrent: bool,
+}
+
+impl PostgresEngine {
+    /// Whether migrations of existing tables avoid locks which block writes, see `set_concurrent`.
+    pub fn is_concurrent(&self) -> bool {
+        self.concurrent
+    }
+
+    /// Makes migrations of existing tables create and drop indexes `CONCURRENTLY` and add
+    /// foreign keys `NOT VALID`, validating them in a separate step. These steps can't run
+    /// inside the transaction of the other steps, see `MigrationPlan::get_phases`.
+    pub fn set_concurrent(&mut self, concurrent: bool) {
+        self.concurrent = concurrent;
+    }
+
+    /// Quotes an identifier which PostgreSQL would otherwise fold to lower case or reject.
+    pub fn quote(&self, identifier: &str) -> String {
+        quote_identifier(identifier, &RESERVED_KEYWORDS)
+    }
+
+    fn quote_list(&self, identifiers: &[String]) -> String {
+        identifiers
+            .iter()
+            .map(|identifier| self.quote(identifier))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    fn column_type(&self, column: &Column) -> Result<String, String> {
+        self.get_db_type(&column.get_data_type())
+    }
+
+    fn column_definition(
+        &self,
+        table: &str,
+        column: &Column,
+        inline_primary_key: bool,
+    ) -> Result<String, String> {
+        let mut definition = format!(
+            "{} {}",
+            self.quote(column.get_name()),
+            self.column_type(column)?
+        );
+        if let Some(default) = column.get_default() {
+            definition.push_str(&format!(" DEFAULT {}", default.to_sql()));
+        }
+        if inline_primary_key {
+            definition.push_str(" PRIMARY KEY");
+        } else if !column.is_primary_key() && !column.is_nullable() {
+            definition.push_str(" NOT NULL");
+        }
+        if column.is_unique() {
+            definition.push_str(&format!(
+                " CONSTRAINT {} UNIQUE",
+                self.quote(&column.get_unique_constraint_name(table))
+            ));
+        }
+        for check in column.get_checks() {
+            definition.push_str(&format!(
+                " CONSTRAINT {} CHECK ({})",
+                self.quote(check.get_name()),
+                check.get_expression()
+            ));
+        }
+        Ok(definition)
+    }
+
+    fn primary_key_definition(&self, primary_key: &PrimaryKey) -> String {
+        let constraint = match primary_key.get_name() {
+            Some(name) => format!("CONSTRAINT {} ", self.quote(name)),
+            None => String::default(),
+        };
+        format!(
+            "{}PRIMARY KEY ({})",
+            constraint,
+            self.quote_list(primary_key.get_columns())
+        )
+    }
+
+    fn create_table_sql(&self, table: &Table) -> Result<String, String> {
+        let primary_key = table.get_primary_key();
+        let inline_primary_key = primary_key.is_some_and(|p| p.is_inline());
+        let mut definitions = table
+            .get_columns()
+            .iter()
+            .map(|column| {
+                let inline = inline_primary_key && column.is_primary_key();
+                self.column_definition(table.get_name(), column, inline)
+            })
+            .collect::<Result<Vec<String>, String>>()?;
+        if let (false, Some(primary_key)) = (inline_primary_key, primary_key) {
+            definitions.push(self.primary_key_definition(primary_key));
+        }
+        let mut sql = format!(
+            "CREATE TABLE {} (\n    {}\n);\n",
+            self.quote(table.get_name()),
+            definitions.join(",\n    ")
+        );
+        for index in table.get_indexes() {
+            sql.push_str(&self.create_index_sql(table.get_name(), index, false));
+        }
+        Ok(sql)
+    }
+
+    fn drop_table_sql(&self, table: &Table) -> String {
+        format!("DROP TABLE {};\n", self.quote(table.get_name()))
+    }
+
+    fn add_column_sql(&self, table: &str, column: &Column) -> Result<String, String> {
+        Ok(format!(
+            "ALTER TABLE {} ADD COLUMN {};\n",
+            self.quote(table),
+            self.column_definition(table, column, false)?
+        ))
+    }
+
+    fn remove_column_sql(&self, table: &str, column: &Column) -> String {
+        format!(
+            "ALTER TABLE {} DROP COLUMN {};\n",
+            self.quote(table),
+            self.quote(column.get_name())
+        )
+    }
+
+    fn alter_column_sql(&self, table: &str, from: &Column, to: &Column) -> Result<String, String> {
+        let quoted_table = self.quote(table);
+        let column = self.quote(to.get_name());
+        let mut sql = String::default();
+        for check in from.get_checks() {
+            if !to.get_checks().contains(check) {
+                sql.push_str(&format!(
+                    "ALTER TABLE {} DROP CONSTRAINT {};\n",
+                    quoted_table,
+                    self.quote(check.get_name())
+                ));
+            }
+        }
+        if from.is_unique() && !to.is_unique() {
+            sql.push_str(&format!(
+                "ALTER TABLE {} DROP CONSTRAINT {};\n",
+                quoted_table,
+                self.quote(&from.get_unique_constraint_name(table))
+            ));
+        }
+        if from.get_data_type() != to.get_data_type() {
+            sql.push_str(&format!(
+                "ALTER TABLE {} ALTER COLUMN {} TYPE {};\n",
+                quoted_table,
+                column,
+                self.column_type(to)?
+            ));
+        }
+        if from.get_default() != to.get_default() {
+            sql.push_str(&match to.get_default() {
+                Some(default) => format!(
+                    "ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {};\n",
+                    quoted_table,
+                    column,
+                    default.to_sql()
+                ),
+                None => format!(
+                    "ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT;\n",
+                    quoted_table, column
+                ),
+            });
+        }
+        if from.is_nullable() != to.is_nullable() {
+            sql.push_str(&format!(
+                "ALTER TABLE {} ALTER COLUMN {} {} NOT NULL;\n",
+                quoted_table,
+                column,
+                if to.is_nullable() { "DROP" } else { "SET" }
+            ));
+        }
+        if !from.is_unique() && to.is_unique() {
+            sql.push_str(&format!(
+                "ALTER TABLE {} ADD CONSTRAINT {} UNIQUE ({});\n",
+                quoted_table,
+                self.quote(&to.get_unique_constraint_name(table)),
+                column
+            ));
+        }
+        for check in to.get_checks() {
+            if !from.get_checks().contains(check) {
+                sql.push_str(&format!(
+                    "ALTER TABLE {} ADD CONSTRAINT {} CHECK ({});\n",
+                    quoted_table,
+                    self.quote(check.get_name()),
+                    check.get_expression()
+                ));
+            }
+        }
+        Ok(sql)
+    }
+
+    fn add_primary_key_sql(&self, table: &str, primary_key: &PrimaryKey) -> String {
+        format!(
+            "ALTER TABLE {} ADD {};\n",
+            self.quote(table),
+            self.primary_key_definition(primary_key)
+        )
+    }
+
+    fn drop_primary_key_sql(&self, table: &str, primary_key: &PrimaryKey) -> String {
+        format!(
+            "ALTER TABLE {} DROP CONSTRAINT {};\n",
+            self.quote(table),
+            self.quote(
+                &primary_key
+                    .get_name()
+                    .map(|name| name.to_string())
+                    .unwrap_or(format!("{}_pkey", table))
+            )
+        )
+    }
+
+    /// Generates the SQL adding the foreign key; a `not_valid` one isn't checked against existing rows.
+    fn add_relationship_sql(&self, relationship: &Relationship, not_valid: bool) -> String {
+        format!(
+            "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({}){}{};\n",
+            self.quote(relationship.get_to_table()),
+            self.quote(&relationship.get_constraint_name()),
+            self.quote_list(relationship.get_to_columns()),
+            self.quote(relationship.get_from_table()),
+            self.quote_list(relationship.get_from_columns()),
+            referential_actions_sql(relationship),
+            if not_valid { " NOT VALID" } else { "" },
+        )
+    }
+
+    fn validate_relationship_sql(&self, relationship: &Relationship) -> String {
+        format!(
+            "ALTER TABLE {} VALIDATE CONSTRAINT {};\n",
+            self.quote(relationship.get_to_table()),
+            self.quote(&relationship.get_constraint_name()),
+        )
+    }
+
+    fn drop_relationship_sql(&self, relationship: &Relationship) -> String {
+        format!(
+            "ALTER TABLE {} DROP CONSTRAINT {};\n",
+            self.quote(relationship.get_to_table()),
+            self.quote(&relationship.get_constraint_name()),
+        )
+    }
+
+    fn index_column_sql(&self, column: &IndexColumn) -> String {
+        let mut sql = self.quote(column.get_name());
+        if column.get_order() == SortOrder::Desc {
+            sql.push_str(" DESC");
+        }
+        match column.get_nulls() {
+            Some(NullsOrder::First) => sql.push_str(" NULLS FIRST"),
+            Some(NullsOrder::Last) => sql.push_str(" NULLS LAST"),
+            None => {}
+        }
+        sql
+    }
+
+    fn create_index_sql(&self, table: &str, index: &Index, concurrently: bool) -> String {
+        let mut sql = format!(
+            "CREATE {}INDEX {}{} ON {}",
+            if index.is_unique() { "UNIQUE " } else { "" },
+            if concurrently { "CONCURRENTLY " } else { "" },
+            self.quote(index.get_name()),
+            self.quote(table)
+        );
+        if index.get_method() != IndexMethod::BTree {
+            sql.push_str(&format!(" USING {}", index.get_method().to_sql()));
+        }
+        let columns = index
+            .get_columns()
+            .iter()
+            .map(|column| self.index_column_sql(column))
+            .collect::<Vec<String>>();
+        sql.push_str(&format!(" ({})", columns.join(", ")));
+        if !index.get_include().is_empty() {
+            sql.push_str(&format!(
+                " INCLUDE ({})",
+                self.quote_list(index.get_include())
+            ));
+        }
+        if let Some(predicate) = index.get_predicate() {
+            sql.push_str(&format!(" WHERE {}", predicate));
+        }
+        sql.push_str(";\n");
+        sql
+    }
+
+    fn drop_index_sql(&self, index: &Index, concurrently: bool) -> String {
+        format!(
+            "DROP INDEX {}{};\n",
+            if concurrently { "CONCURRENTLY " } else { "" },
+            self.quote(index.get_name())
+        )
+    }
+
+    fn enum_label(label: &str) -> String {
+        format!("'{}'", label.replace('\'', "''"))
+    }
+
+    fn create_enum_sql(&self, enum_type: &EnumType) -> String {
+        let labels = enum_type
+            .get_labels()
+            .iter()
+            .map(|label| Self::enum_label(label))
+            .collect::<Vec<String>>();
+        format!(
+            "CREATE TYPE {} AS ENUM ({});\n",
+            self.quote(enum_type.get_name()),
+            labels.join(", ")
+        )
+    }
+
+    fn drop_enum_sql(&self, enum_type: &EnumType) -> String {
+        format!("DROP TYPE {};\n", self.quote(enum_type.get_name()))
+    }
+
+    /// Labels can only be added in place; any other change recreates the type
+    /// and converts the `columns` (table and column names) using it.
+    fn alter_enum_sql(
+        &self,
+        from: &EnumType,
+        to: &EnumType,
+        columns: &[(String, String)],
+    ) -> String {
+        let name = self.quote(to.get_name());
+        if from.is_extended_by(to) {
+            let labels = to.get_labels();
+            return labels
+                .iter()
+                .enumerate()
+                .filter(|(_, label)| !from.get_labels().contains(label))
+                .map(|(index, label)| {
+                    // labels are added in order, so only existing or already added labels
+                    // serve as anchors
+                    let position = match (index, from.get_labels().first()) {
+                        (0, Some(first)) => format!(" BEFORE {}", Self::enum_label(first)),
+                        (0, None) => String::default(),
+                        _ => format!(" AFTER {}", Self::enum_label(&labels[index - 1])),
+                    };
+                    // ADD VALUE runs outside transactions, so an interrupted step is run again
+                    format!(
+                        "ALTER TYPE {} ADD VALUE IF NOT EXISTS {}{};\n",
+                        name,
+                        Self::enum_label(label),
+                        position
+                    )
+                })
+                .collect();
+        }
+
+        let old_name = self.quote(&format!("{}_old", to.get_name()));
+        let mut sql = format!("ALTER TYPE {} RENAME TO {};\n", name, old_name);
+        sql.push_str(&self.create_enum_sql(to));
+        for (table, column) in columns {
+            let column = self.quote(column);
+            sql.push_str(&format!(
+                "ALTER TABLE {} ALTER COLUMN {} TYPE {} USING {}::text::{};\n",
+                self.quote(table),
+                column,
+                name,
+                column,
+                name
+            ));
+        }
+        sql.push_str(&format!("DROP TYPE {};\n", old_name));
+        sql
+    }
+
+    /// Returns the columns of `current` using the enum type which are kept in `desired`.
+    fn enum_columns(current: &Schema, desired: &Schema, name: &str) -> Vec<(String, String)> {
+        let data_type = DataType::Enum(name.to_string());
+        current
+            .get_tables()
+            .iter()
+            .filter(|table| desired.get_table(table.get_name()).is_ok())
+            .flat_map(|table| {
+                table
+                    .get_columns()
+                    .iter()
+                    .filter(|column| column.get_data_type() == data_type)
+                    .map(|column| (table.get_name().to_string(), column.get_name().to_string()))
+            })
+            .collect()
+    }
+}
 
 impl DatabaseEngine for PostgresEngine {
     fn name() -> &'static str {
         "PostgreSQL"
     }
 
-    fn generate_migration_plan(&self, schema: &mut Schema) -> Result<MigrationPlan, String> {
-        let mut migration_steps = Vec::<MigrationStep>::default();
+    fn get_type_mapping(&self) -> &TypeMapping {
+        &self.type_mapping
+    }
 
-        let tables = schema.get_tables();
-        for table in tables {
-            // let mut migration_step = MigrationStep::CreateTable
-            let mut create_table_tmp = format!(
-                r#"
-                CREATE TABLE {} (
-                    {{}}
-                );
-            "#,
-                table.get_name()
-            );
-            for (index, column) in table.get_columns().iter().enumerate() {
-                let table_to_place = if index == table.get_columns().len() - 1 {
-                    // PRIMARY KEY
-                    format!(
-                        "{} {}{}",
-                        column.get_name(),
-                        column
-                            .get_data_type()
-                            .to_db_type(PostgresEngine::name())
-                            .unwrap(),
-                        if column.is_primary_key() {
-                            " PRIMARY KEY"
-                        } else {
-                            ""
-                        }
-                    )
-                    // can update here other constraints
-                } else {
-                    format!(
-                        "{} {}{},\n\t\t{{}}",
-                        column.get_name(),
-                        column
-                            .get_data_type()
-                            .to_db_type(PostgresEngine::name())
-                            .unwrap(),
-                        if column.is_primary_key() {
-                            " PRIMARY KEY"
-                        } else {
-                            ""
-                        }
-                    )
-                    // can update here other constraints
-                };
-                create_table_tmp = create_table_tmp.replace("{}", &table_to_place);
+    fn get_type_mapping_mut(&mut self) -> &mut TypeMapping {
+        &mut self.type_mapping
+    }
+
+    fn default_db_type(&self, data_type: &DataType) -> Option<String> {
+        let db_type = match data_type {
+            DataType::Integer => "INTEGER".to_string(),
+            DataType::Float => "DOUBLE PRECISION".to_string(),
+            DataType::String => "TEXT".to_string(),
+            DataType::Boolean => "BOOLEAN".to_string(),
+            DataType::Date => "DATE".to_string(),
+            DataType::SmallInt => "SMALLINT".to_string(),
+            DataType::BigInt => "BIGINT".to_string(),
+            DataType::Decimal { precision, scale } => {
+                format!("NUMERIC({}, {})", precision, scale)
             }
-            migration_steps.push(MigrationStep::CreateTable {
-                name: table.get_name().to_string(),
-                sql_script: create_table_tmp,
-            });
+            DataType::Varchar(length) => format!("VARCHAR({})", length),
+            DataType::Timestamp {
+                precision,
+                with_time_zone,
+            } => time_db_type("TIMESTAMP", precision, *with_time_zone),
+            DataType::Time {
+                precision,
+                with_time_zone,
+            } => time_db_type("TIME", precision, *with_time_zone),
+            DataType::Uuid => "UUID".to_string(),
+            DataType::Json => "JSON".to_string(),
+            DataType::Jsonb => "JSONB".to_string(),
+            DataType::Binary => "BYTEA".to_string(),
+            DataType::Enum(name) => self.quote(name),
+            DataType::Array(element_type) => format!("{}[]", self.get_db_type(element_type).ok()?),
+        };
+        Some(db_type)
+    }
+
+    fn default_data_type(&self, db_type: &str) -> Option<DataType> {
+        if let Some(element_type) = db_type.strip_suffix("[]") {
+            return Some(DataType::Array(Box::new(self.get_data_type(element_type)?)));
+        }
+        let parameters = db_type_parameters(db_type);
+        let time_precision = match parameters.first() {
+            Some(precision) => Some(u8::try_from(*precision).ok()?),
+            None => None,
+        };
+        match base_db_type(db_type).as_str() {
+            "INTEGER" | "INT" | "INT4" | "SERIAL" => Some(DataType::Integer),
+            "SMALLINT" | "INT2" | "SMALLSERIAL" => Some(DataType::SmallInt),
+            "BIGINT" | "INT8" | "BIGSERIAL" => Some(DataType::BigInt),
+            "DOUBLE PRECISION" | "FLOAT8" | "REAL" | "FLOAT4" => Some(DataType::Float),
+            "NUMERIC" | "DECIMAL" => match parameters[..] {
+                [precision] => Some(DataType::Decimal {
+                    precision: u16::try_from(precision).ok()?,
+                    scale: 0,
+                }),
+                [precision, scale] => Some(DataType::Decimal {
+                    precision: u16::try_from(precision).ok()?,
+                    scale: u16::try_from(scale).ok()?,
+                }),
+                _ => None,
+            },
+            "VARCHAR" | "CHARACTER VARYING" => match parameters.first() {
+                Some(length) => Some(DataType::Varchar(*length)),
+                None => Some(DataType::String),
+            },
+            "TEXT" | "CHAR" | "CHARACTER" | "BPCHAR" => Some(DataType::String),
+            "BOOLEAN" | "BOOL" => Some(DataType::Boolean),
+            "DATE" => Some(DataType::Date),
+            "TIMESTAMP" | "TIMESTAMP WITHOUT TIME ZONE" => Some(DataType::Timestamp {
+                precision: time_precision,
+                with_time_zone: false,
+            }),
+            "TIMESTAMPTZ" | "TIMESTAMP WITH TIME ZONE" => Some(DataType::Timestamp {
+                precision: time_precision,
+                with_time_zone: true,
+            }),
+            "TIME" | "TIME WITHOUT TIME ZONE" => Some(DataType::Time {
+                precision: time_precision,
+                with_time_zone: false,
+            }),
+            "TIMETZ" | "TIME WITH TIME ZONE" => Some(DataType::Time {
+                precision: time_precision,
+                with_time_zone: true,
+            }),
+            "UUID" => Some(DataType::Uuid),
+            "JSON" => Some(DataType::Json),
+            "JSONB" => Some(DataType::Jsonb),
+            "BYTEA" => Some(DataType::Binary),
+            _ => None,
         }
+    }
 
-        let relations = schema.get_relationships();
-        for relation in relations {
-            let create_relation_tmp = format!(
-                r#"
-                    ALTER TABLE {} -- table name
-                    ADD CONSTRAINT {}_fk FOREIGN KEY ({}) REFERENCES {} ({});
-                "#,
-                relation.get_to_table(),
-                relation.get_to_column(),
-                relation.get_to_column(),
-                relation.get_from_table(),
-                relation.get_from_column(),
-            );
-            migration_steps.push(MigrationStep::AddRelationship {
-                relationship: relation.clone(),
-                sql_script: create_relation_tmp,
-            });
+    fn generate_migration_plan(&self, schema: &mut Schema) -> Result<MigrationPlan, String> {
+        self.generate_diff_migration_plan(&Schema::new(), schema)
+    }
+
+    fn generate_diff_migration_plan(
+        &self,
+        current: &Schema,
+        desired: &Schema,
+    ) -> Result<MigrationPlan, String> {
+        desired.validate()?;
+        let mut migration_steps = Vec::<MigrationStep>::default();
+
+        for change in SchemaDiff::new(current, desired).get_changes() {
+            let step = match change {
+                SchemaChange::CreateTable(table) => MigrationStep::CreateTable {
+                    name: table.get_name().to_string(),
+                    sql_script: self.create_table_sql(table)?,
+                    down_sql_script: Some(self.drop_table_sql(table)),
+                },
+                SchemaChange::DropTable(table) => MigrationStep::DropTable {
+                    name: table.get_name().to_string(),
+                    sql_script: self.drop_table_sql(table),
+                    down_sql_script: Some(self.create_table_sql(table)?),
+                },
+                SchemaChange::AddColumn { table, column } => MigrationStep::AddColumn {
+                    table: table.clone(),
+                    column: column.clone(),
+                    sql_script: self.add_column_sql(table, column)?,
+                    down_sql_script: Some(self.remove_column_sql(table, column)),
+                },
+                SchemaChange::RemoveColumn { table, column } => MigrationStep::RemoveColumn {
+                    table: table.clone(),
+                    column: column.clone(),
+                    sql_script: self.remove_column_sql(table, column),
+                    down_sql_script: Some(self.add_column_sql(table, column)?),
+                },
+                SchemaChange::AlterColumn { table, from, to } => {
+                    let sql_script = self.alter_column_sql(table, from, to)?;
+                    if sql_script.is_empty() {
+                        // nothing the database needs to know about (e.g. the foreign key flag)
+                        continue;
+                    }
+                    MigrationStep::AlterColumn {
+                        table: table.clone(),
+                        column: to.clone(),
+                        previous_column: from.clone(),
+                        sql_script,
+                        down_sql_script: Some(self.alter_column_sql(table, to, from)?),
+                    }
+                }
+                SchemaChange::AddPrimaryKey { table, primary_key } => {
+                    MigrationStep::AddPrimaryKey {
+                        table: table.clone(),
+                        primary_key: primary_key.clone(),
+                        sql_script: self.add_primary_key_sql(table, primary_key),
+                        down_sql_script: Some(self.drop_primary_key_sql(table, primary_key)),
+                    }
+                }
+                SchemaChange::DropPrimaryKey { table, primary_key } => {
+                    MigrationStep::DropPrimaryKey {
+                        table: table.clone(),
+                        primary_key: primary_key.clone(),
+                        sql_script: self.drop_primary_key_sql(table, primary_key),
+                        down_sql_script: Some(self.add_primary_key_sql(table, primary_key)),
+                    }
+                }
+                SchemaChange::AddIndex { table, index } => MigrationStep::AddIndex {
+                    table: table.clone(),
+                    index: index.clone(),
+                    concurrently: self.concurrent,
+                    sql_script: self.create_index_sql(table, index, self.concurrent),
+                    down_sql_script: Some(self.drop_index_sql(index, self.concurrent)),
+                },
+                SchemaChange::DropIndex { table, index } => MigrationStep::DropIndex {
+                    table: table.clone(),
+                    index: index.clone(),
+                    concurrently: self.concurrent,
+                    sql_script: self.drop_index_sql(index, self.concurrent),
+                    down_sql_script: Some(self.create_index_sql(table, index, self.concurrent)),
+                },
+                SchemaChange::AddRelationship(relationship)
+                | SchemaChange::DropRelationship(relationship)
+                    if relationship.is_many_to_many() =>
+                {
+                    // modeled by the foreign keys of its junction table
+                    continue;
+                }
+                SchemaChange::AddRelationship(relationship)
+                    if self.concurrent
+                        && current.get_table(relationship.get_to_table()).is_ok() =>
+                {
+                    migration_steps.push(MigrationStep::AddRelationship {
+                        relationship: relationship.clone(),
+                        sql_script: self.add_relationship_sql(relationship, true),
+                        down_sql_script: Some(self.drop_relationship_sql(relationship)),
+                    });
+                    MigrationStep::ValidateRelationship {
+                        relationship: relationship.clone(),
+                        sql_script: self.validate_relationship_sql(relationship),
+                        // dropping the foreign key undoes the validation as well
+                        down_sql_script: Some(String::default()),
+                    }
+                }
+                SchemaChange::AddRelationship(relationship) => MigrationStep::AddRelationship {
+                    relationship: relationship.clone(),
+                    sql_script: self.add_relationship_sql(relationship, false),
+                    down_sql_script: Some(self.drop_relationship_sql(relationship)),
+                },
+                SchemaChange::DropRelationship(relationship) => MigrationStep::DropRelationship {
+                    relationship: relationship.clone(),
+                    sql_script: self.drop_relationship_sql(relationship),
+                    down_sql_script: Some(self.add_relationship_sql(relationship, false)),
+                },
+                SchemaChange::CreateEnum(enum_type) => MigrationStep::CreateEnum {
+                    enum_type: enum_type.clone(),
+                    sql_script: self.create_enum_sql(enum_type),
+                    down_sql_script: Some(self.drop_enum_sql(enum_type)),
+                },
+                SchemaChange::DropEnum(enum_type) => MigrationStep::DropEnum {
+                    enum_type: enum_type.clone(),
+                    sql_script: self.drop_enum_sql(enum_type),
+                    down_sql_script: Some(self.create_enum_sql(enum_type)),
+                },
+                SchemaChange::AlterEnum { from, to } => {
+                    let columns = Self::enum_columns(current, desired, to.get_name());
+                    MigrationStep::AlterEnum {
+                        enum_type: to.clone(),
+                        previous_enum_type: from.clone(),
+                        sql_script: self.alter_enum_sql(from, to, &columns),
+                        down_sql_script: Some(self.alter_enum_sql(to, from, &columns)),
+                    }
+                }
+            };
+            migration_steps.push(step);
         }
+
         Ok(MigrationPlan::new(migration_steps))
     }
 }
@@ -91,8 +749,10 @@ impl DatabaseEngine for PostgresEngine {
 #[cfg(test)]
 mod tests {
     use crate::schema::column::Column;
+    use crate::schema::constraint::{CheckConstraint, DefaultValue, PrimaryKey};
     use crate::schema::data_type;
-    use crate::schema::relationship::{Relationship, RelationshipType};
+    use crate::schema::data_type::DataType;
+    use crate::schema::relationship::{ReferentialAction, Relationship, RelationshipType};
 
     use super::*;
     #[test]
@@ -123,7 +783,7 @@ mod tests {
             ))
             .unwrap();
 
-        let postgres_engine = PostgresEngine;
+        let postgres_engine = PostgresEngine::default();
         let res = postgres_engine.generate_migration_plan(&mut schema);
         assert!(res.is_ok());
         let plan = res.unwrap();
@@ -168,7 +828,7 @@ mod tests {
         );
         schema.add_relation(relationship).unwrap();
 
-        let postgres_engine = PostgresEngine;
+        let postgres_engine = PostgresEngine::default();
         let res = postgres_engine.generate_migration_plan(&mut schema);
         assert!(res.is_ok());
         let plan = res.unwrap();
@@ -232,11 +892,759 @@ mod tests {
         );
         schema.add_relation(relationship).unwrap();
 
-        let postgres_engine = PostgresEngine;
+        let postgres_engine = PostgresEngine::default();
         let res = postgres_engine.generate_migration_plan(&mut schema);
         assert!(res.is_ok());
         let plan = res.unwrap();
         let sql = plan.get_sql();
         println!("{}", sql);
     }
+
+    #[test]
+    fn test_create_table_sql() {
+        let mut schema = Schema::new();
+        schema.add_table("users").unwrap();
+        let users_table = schema.get_table_mut("users").unwrap();
+        users_table
+            .add_column(Column::new(
+                "id",
+                data_type::DataType::Integer,
+                true,
+                false,
+                false,
+            ))
+            .unwrap();
+        users_table
+            .add_column(Column::new(
+                "username",
+                data_type::DataType::String,
+                false,
+                false,
+                false,
+            ))
+            .unwrap();
+
+        let plan = PostgresEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "CREATE TABLE users (\n    id INTEGER PRIMARY KEY,\n    username TEXT NOT NULL\n);\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_migration_plan() {
+        let mut current = Schema::new();
+        current.add_table("users").unwrap();
+        current.add_table("orders").unwrap();
+        current
+            .get_table_mut("users")
+            .unwrap()
+            .add_column(Column::new(
+                "id",
+                data_type::DataType::Integer,
+                true,
+                false,
+                false,
+            ))
+            .unwrap();
+        current
+            .get_table_mut("orders")
+            .unwrap()
+            .add_column(Column::new(
+                "user_id",
+                data_type::DataType::Integer,
+                false,
+                true,
+                false,
+            ))
+            .unwrap();
+
+        let mut desired = current.clone();
+        desired.remove_table("orders").unwrap();
+        desired.add_table("accounts").unwrap();
+        desired
+            .get_table_mut("accounts")
+            .unwrap()
+            .add_column(Column::new(
+                "user_id",
+                data_type::DataType::Integer,
+                false,
+                true,
+                false,
+            ))
+            .unwrap();
+        let users_table = desired.get_table_mut("users").unwrap();
+        users_table
+            .add_column(Column::new(
+                "email",
+                data_type::DataType::String,
+                false,
+                false,
+                true,
+            ))
+            .unwrap();
+        *users_table.get_columns_mut()[0].get_data_type_mut() = data_type::DataType::Float;
+
+        let plan = PostgresEngine::default()
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        let steps = plan.get_steps();
+        assert_eq!(steps.len(), 4);
+        assert!(matches!(steps[0], MigrationStep::DropTable { .. }));
+        assert!(matches!(steps[1], MigrationStep::AlterColumn { .. }));
+        assert!(matches!(steps[2], MigrationStep::AddColumn { .. }));
+        assert!(matches!(steps[3], MigrationStep::CreateTable { .. }));
+        assert_eq!(
+            plan.get_sql(),
+            "DROP TABLE orders;\n\
+             ALTER TABLE users ALTER COLUMN id TYPE DOUBLE PRECISION;\n\
+             ALTER TABLE users ADD COLUMN email TEXT;\n\
+             CREATE TABLE accounts (\n    user_id INTEGER NOT NULL\n);\n"
+        );
+        assert_eq!(
+            plan.get_down_sql().unwrap(),
+            "DROP TABLE accounts;\n\
+             ALTER TABLE users DROP COLUMN email;\n\
+             ALTER TABLE users ALTER COLUMN id TYPE INTEGER;\n\
+             CREATE TABLE orders (\n    user_id INTEGER NOT NULL\n);\n"
+        );
+        assert!(steps[0].is_destructive());
+        assert!(steps[1].is_destructive());
+        assert!(!steps[2].is_destructive());
+    }
+
+    #[test]
+    fn test_diff_migration_plan_relationships() {
+        let mut current = Schema::new();
+        current.add_table("users").unwrap();
+        current.add_table("orders").unwrap();
+        current
+            .get_table_mut("users")
+            .unwrap()
+            .add_column(Column::new(
+                "id",
+                data_type::DataType::Integer,
+                true,
+                false,
+                false,
+            ))
+            .unwrap();
+        current
+            .get_table_mut("orders")
+            .unwrap()
+            .add_column(Column::new(
+                "user_id",
+                data_type::DataType::Integer,
+                false,
+                true,
+                false,
+            ))
+            .unwrap();
+        let mut desired = current.clone();
+        desired
+            .add_relation(Relationship::new(
+                "users",
+                "id",
+                "orders",
+                "user_id",
+                RelationshipType::OneToMany,
+            ))
+            .unwrap();
+
+        let up = PostgresEngine::default()
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        assert_eq!(
+            up.get_sql(),
+            "ALTER TABLE orders ADD CONSTRAINT orders_user_id_fk FOREIGN KEY (user_id) REFERENCES users (id);\n"
+        );
+
+        let down = PostgresEngine::default()
+            .generate_diff_migration_plan(&desired, &current)
+            .unwrap();
+        assert_eq!(
+            down.get_sql(),
+            "ALTER TABLE orders DROP CONSTRAINT orders_user_id_fk;\n"
+        );
+        assert_eq!(down.get_sql(), up.get_down_sql().unwrap());
+    }
+
+    #[test]
+    fn test_type_mapping() {
+        let mut engine = PostgresEngine::default();
+        assert_eq!(engine.get_db_type(&DataType::String).unwrap(), "TEXT");
+        assert_eq!(engine.get_data_type("int4"), Some(DataType::Integer));
+        assert_eq!(
+            engine.get_data_type("character varying(20)"),
+            Some(DataType::Varchar(20))
+        );
+        assert_eq!(engine.get_data_type("xml"), None);
+
+        engine
+            .get_type_mapping_mut()
+            .add_override(DataType::String, "VARCHAR(320)");
+        assert_eq!(
+            engine.get_db_type(&DataType::String).unwrap(),
+            "VARCHAR(320)"
+        );
+        assert_eq!(engine.get_data_type("varchar(320)"), Some(DataType::String));
+
+        let mut schema = Schema::new();
+        schema.add_table("users").unwrap();
+        schema
+            .get_table_mut("users")
+            .unwrap()
+            .add_column(Column::new("email", DataType::String, false, false, false))
+            .unwrap();
+        let plan = engine.generate_migration_plan(&mut schema).unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "CREATE TABLE users (\n    email VARCHAR(320) NOT NULL\n);\n"
+        );
+    }
+
+    #[test]
+    fn test_parameterized_types() {
+        let engine = PostgresEngine::default();
+        let data_types = [
+            (
+                DataType::Decimal {
+                    precision: 12,
+                    scale: 2,
+                },
+                "NUMERIC(12, 2)",
+            ),
+            (DataType::Varchar(80), "VARCHAR(80)"),
+            (
+                DataType::Timestamp {
+                    precision: Some(3),
+                    with_time_zone: true,
+                },
+                "TIMESTAMP(3) WITH TIME ZONE",
+            ),
+            (
+                DataType::Timestamp {
+                    precision: None,
+                    with_time_zone: false,
+                },
+                "TIMESTAMP",
+            ),
+            (
+                DataType::Time {
+                    precision: Some(0),
+                    with_time_zone: false,
+                },
+                "TIME(0)",
+            ),
+            (DataType::SmallInt, "SMALLINT"),
+            (DataType::BigInt, "BIGINT"),
+            (DataType::Uuid, "UUID"),
+            (DataType::Json, "JSON"),
+            (DataType::Jsonb, "JSONB"),
+            (DataType::Binary, "BYTEA"),
+            (DataType::Array(Box::new(DataType::Integer)), "INTEGER[]"),
+            (DataType::Array(Box::new(DataType::String)), "TEXT[]"),
+            (
+                DataType::Array(Box::new(DataType::Varchar(20))),
+                "VARCHAR(20)[]",
+            ),
+        ];
+        for (data_type, db_type) in data_types {
+            assert_eq!(engine.get_db_type(&data_type).unwrap(), db_type);
+            assert_eq!(engine.get_data_type(db_type), Some(data_type));
+        }
+        assert_eq!(
+            engine.get_data_type("timestamptz"),
+            Some(DataType::Timestamp {
+                precision: None,
+                with_time_zone: true
+            })
+        );
+    }
+
+    #[test]
+    fn test_enums() {
+        let mut current = Schema::new();
+        current
+            .add_enum(EnumType::new("status", &["new", "paid"]))
+            .unwrap();
+        current.add_table("orders").unwrap();
+        current
+            .get_table_mut("orders")
+            .unwrap()
+            .add_column(Column::new(
+                "status",
+                DataType::Enum("status".to_string()),
+                false,
+                false,
+                false,
+            ))
+            .unwrap();
+
+        let plan = PostgresEngine::default()
+            .generate_migration_plan(&mut current.clone())
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "CREATE TYPE status AS ENUM ('new', 'paid');\n\
+             CREATE TABLE orders (\n    status status NOT NULL\n);\n"
+        );
+
+        let mut extended = current.clone();
+        extended
+            .get_enum_mut("status")
+            .unwrap()
+            .add_label("refunded")
+            .unwrap();
+        let plan = PostgresEngine::default()
+            .generate_diff_migration_plan(&current, &extended)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "ALTER TYPE status ADD VALUE IF NOT EXISTS 'refunded' AFTER 'paid';\n"
+        );
+        assert!(!plan.get_steps()[0].is_destructive());
+
+        let mut prepended = current.clone();
+        *prepended.get_enum_mut("status").unwrap() =
+            EnumType::new("status", &["draft", "review", "new", "held", "paid"]);
+        let plan = PostgresEngine::default()
+            .generate_diff_migration_plan(&current, &prepended)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "ALTER TYPE status ADD VALUE IF NOT EXISTS 'draft' BEFORE 'new';\n\
+             ALTER TYPE status ADD VALUE IF NOT EXISTS 'review' AFTER 'draft';\n\
+             ALTER TYPE status ADD VALUE IF NOT EXISTS 'held' AFTER 'new';\n"
+        );
+
+        let plan = PostgresEngine::default()
+            .generate_diff_migration_plan(&extended, &current)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "ALTER TYPE status RENAME TO status_old;\n\
+             CREATE TYPE status AS ENUM ('new', 'paid');\n\
+             ALTER TABLE orders ALTER COLUMN status TYPE status USING status::text::status;\n\
+             DROP TYPE status_old;\n"
+        );
+        assert!(plan.get_steps()[0].is_destructive());
+
+        let mut invalid = current.clone();
+        invalid.remove_table("orders").unwrap();
+        invalid.add_table("orders").unwrap();
+        invalid
+            .get_table_mut("orders")
+            .unwrap()
+            .add_column(Column::new(
+                "priority",
+                DataType::Enum("priority".to_string()),
+                false,
+                false,
+                false,
+            ))
+            .unwrap();
+        assert!(PostgresEngine::default()
+            .generate_diff_migration_plan(&current, &invalid)
+            .is_err());
+    }
+
+    fn accounts_with_constraints() -> Schema {
+        let mut schema = Schema::new();
+        schema.add_table("accounts").unwrap();
+        let mut email = Column::new("email", DataType::String, false, false, false);
+        *email.is_unique_mut() = true;
+        let mut balance = Column::new("balance", DataType::Integer, false, false, false);
+        *balance.get_default_mut() = Some(DefaultValue::Integer(0));
+        balance
+            .add_check(CheckConstraint::new(
+                "accounts_balance_check",
+                "balance >= 0",
+            ))
+            .unwrap();
+        let accounts_table = schema.get_table_mut("accounts").unwrap();
+        accounts_table.add_column(email).unwrap();
+        accounts_table.add_column(balance).unwrap();
+        schema
+    }
+
+    #[test]
+    fn test_column_constraints() {
+        let current = accounts_with_constraints();
+        let plan = PostgresEngine::default()
+            .generate_migration_plan(&mut current.clone())
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "CREATE TABLE accounts (\n    \
+             email TEXT NOT NULL CONSTRAINT accounts_email_key UNIQUE,\n    \
+             balance INTEGER DEFAULT 0 NOT NULL CONSTRAINT accounts_balance_check CHECK (balance >= 0)\n);\n"
+        );
+
+        let mut desired = current.clone();
+        let columns = desired.get_table_mut("accounts").unwrap().get_columns_mut();
+        *columns[0].is_unique_mut() = false;
+        *columns[0].is_nullable_mut() = true;
+        *columns[1].get_default_mut() = None;
+        *columns[1].get_checks_mut() = vec![CheckConstraint::new(
+            "accounts_balance_check",
+            "balance > -100",
+        )];
+        let plan = PostgresEngine::default()
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "ALTER TABLE accounts DROP CONSTRAINT accounts_email_key;\n\
+             ALTER TABLE accounts ALTER COLUMN email DROP NOT NULL;\n\
+             ALTER TABLE accounts DROP CONSTRAINT accounts_balance_check;\n\
+             ALTER TABLE accounts ALTER COLUMN balance DROP DEFAULT;\n\
+             ALTER TABLE accounts ADD CONSTRAINT accounts_balance_check CHECK (balance > -100);\n"
+        );
+        assert_eq!(
+            plan.get_down_sql().unwrap(),
+            "ALTER TABLE accounts DROP CONSTRAINT accounts_balance_check;\n\
+             ALTER TABLE accounts ALTER COLUMN balance SET DEFAULT 0;\n\
+             ALTER TABLE accounts ADD CONSTRAINT accounts_balance_check CHECK (balance >= 0);\n\
+             ALTER TABLE accounts ALTER COLUMN email SET NOT NULL;\n\
+             ALTER TABLE accounts ADD CONSTRAINT accounts_email_key UNIQUE (email);\n"
+        );
+    }
+
+    fn order_items() -> Schema {
+        let mut schema = Schema::new();
+        schema.add_table("order_items").unwrap();
+        let table = schema.get_table_mut("order_items").unwrap();
+        for name in ["order_id", "product_id", "quantity"] {
+            table
+                .add_column(Column::new(name, DataType::Integer, false, false, false))
+                .unwrap();
+        }
+        table
+            .set_primary_key(Some(PrimaryKey::new(&["order_id", "product_id"])))
+            .unwrap();
+        schema
+    }
+
+    #[test]
+    fn test_composite_primary_key() {
+        let current = order_items();
+        let plan = PostgresEngine::default()
+            .generate_migration_plan(&mut current.clone())
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "CREATE TABLE order_items (\n    \
+             order_id INTEGER,\n    \
+             product_id INTEGER,\n    \
+             quantity INTEGER NOT NULL,\n    \
+             PRIMARY KEY (order_id, product_id)\n);\n"
+        );
+
+        let mut desired = current.clone();
+        let table = desired.get_table_mut("order_items").unwrap();
+        table
+            .add_column(Column::new("line", DataType::Integer, false, false, false))
+            .unwrap();
+        table
+            .set_primary_key(Some(PrimaryKey::with_name(
+                "order_items_pk",
+                &["order_id", "line"],
+            )))
+            .unwrap();
+        let plan = PostgresEngine::default()
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "ALTER TABLE order_items DROP CONSTRAINT order_items_pkey;\n\
+             ALTER TABLE order_items ADD COLUMN line INTEGER;\n\
+             ALTER TABLE order_items ADD CONSTRAINT order_items_pk PRIMARY KEY (order_id, line);\n"
+        );
+        assert_eq!(
+            plan.get_down_sql().unwrap(),
+            "ALTER TABLE order_items DROP CONSTRAINT order_items_pk;\n\
+             ALTER TABLE order_items DROP COLUMN line;\n\
+             ALTER TABLE order_items ADD PRIMARY KEY (order_id, product_id);\n"
+        );
+    }
+
+    #[test]
+    fn test_composite_foreign_key() {
+        let mut schema = Schema::new();
+        schema.add_table("users").unwrap();
+        schema.add_table("orders").unwrap();
+        for (table, columns) in [
+            ("users", ["tenant_id", "id"]),
+            ("orders", ["tenant_id", "user_id"]),
+        ] {
+            for column in columns {
+                schema
+                    .get_table_mut(table)
+                    .unwrap()
+                    .add_column(Column::new(
+                        column,
+                        DataType::BigInt,
+                        table == "users",
+                        false,
+                        false,
+                    ))
+                    .unwrap();
+            }
+        }
+        schema
+            .add_relation(Relationship::new_composite(
+                "users",
+                &["tenant_id", "id"],
+                "orders",
+                &["tenant_id", "user_id"],
+                RelationshipType::OneToMany,
+            ))
+            .unwrap();
+
+        let plan = PostgresEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap();
+        assert!(plan.get_sql().ends_with(
+            "PRIMARY KEY (tenant_id, id)\n);\n\
+             CREATE TABLE orders (\n    \
+             tenant_id BIGINT NOT NULL,\n    \
+             user_id BIGINT NOT NULL\n);\n\
+             ALTER TABLE orders ADD CONSTRAINT orders_tenant_id_user_id_fk \
+             FOREIGN KEY (tenant_id, user_id) REFERENCES users (tenant_id, id);\n"
+        ));
+    }
+
+    #[test]
+    fn test_referential_actions() {
+        let mut schema = Schema::new();
+        schema.add_table("users").unwrap();
+        schema.add_table("orders").unwrap();
+        schema
+            .get_table_mut("users")
+            .unwrap()
+            .add_column(Column::new("id", DataType::Integer, true, false, false))
+            .unwrap();
+        schema
+            .get_table_mut("orders")
+            .unwrap()
+            .add_column(Column::new("user_id", DataType::Integer, false, true, true))
+            .unwrap();
+        let mut relationship = Relationship::new(
+            "users",
+            "id",
+            "orders",
+            "user_id",
+            RelationshipType::OneToMany,
+        );
+        relationship.set_on_delete(ReferentialAction::SetNull);
+        relationship.set_on_update(ReferentialAction::Cascade);
+        relationship.set_deferrable(true);
+        relationship.set_initially_deferred(true);
+        schema.add_relation(relationship).unwrap();
+
+        let plan = PostgresEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap();
+        assert!(plan.get_sql().ends_with(
+            "ALTER TABLE orders ADD CONSTRAINT orders_user_id_fk FOREIGN KEY (user_id) REFERENCES users (id) \
+             ON DELETE SET NULL ON UPDATE CASCADE DEFERRABLE INITIALLY DEFERRED;\n"
+        ));
+    }
+
+    #[test]
+    fn test_many_to_many() {
+        let mut schema = Schema::new();
+        schema.add_table("posts").unwrap();
+        schema.add_table("tags").unwrap();
+        for table in ["posts", "tags"] {
+            schema
+                .get_table_mut(table)
+                .unwrap()
+                .add_column(Column::new("id", DataType::Integer, true, false, false))
+                .unwrap();
+        }
+        schema
+            .add_relation(Relationship::new(
+                "posts",
+                "id",
+                "tags",
+                "id",
+                RelationshipType::ManyToMany,
+            ))
+            .unwrap();
+
+        let plan = PostgresEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap();
+        assert!(plan.get_sql().ends_with(
+            "CREATE TABLE posts_tags (\n    \
+             posts_id INTEGER,\n    \
+             tags_id INTEGER,\n    \
+             PRIMARY KEY (posts_id, tags_id)\n);\n\
+             ALTER TABLE posts_tags ADD CONSTRAINT posts_tags_posts_id_fk \
+             FOREIGN KEY (posts_id) REFERENCES posts (id) ON DELETE CASCADE;\n\
+             ALTER TABLE posts_tags ADD CONSTRAINT posts_tags_tags_id_fk \
+             FOREIGN KEY (tags_id) REFERENCES tags (id) ON DELETE CASCADE;\n"
+        ));
+    }
+
+    #[test]
+    fn test_one_to_one() {
+        let mut schema = Schema::new();
+        schema.add_table("users").unwrap();
+        schema.add_table("profiles").unwrap();
+        schema
+            .get_table_mut("users")
+            .unwrap()
+            .add_column(Column::new("id", DataType::Integer, true, false, false))
+            .unwrap();
+        schema
+            .get_table_mut("profiles")
+            .unwrap()
+            .add_column(Column::new("user_id", DataType::Integer, false, true, true))
+            .unwrap();
+        schema
+            .add_relation(Relationship::new(
+                "users",
+                "id",
+                "profiles",
+                "user_id",
+                RelationshipType::OneToOne,
+            ))
+            .unwrap();
+
+        let plan = PostgresEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap();
+        assert!(plan.get_sql().ends_with(
+            "CREATE TABLE profiles (\n    \
+             user_id INTEGER CONSTRAINT profiles_user_id_key UNIQUE\n);\n\
+             ALTER TABLE profiles ADD CONSTRAINT profiles_user_id_fk \
+             FOREIGN KEY (user_id) REFERENCES users (id);\n"
+        ));
+    }
+
+    #[test]
+    fn test_indexes() {
+        let mut current = Schema::new();
+        current.add_table("events").unwrap();
+        let events_table = current.get_table_mut("events").unwrap();
+        for (name, data_type) in [
+            ("id", DataType::Integer),
+            ("created_at", DataType::Date),
+            ("payload", DataType::Jsonb),
+        ] {
+            events_table
+                .add_column(Column::new(name, data_type, name == "id", false, true))
+                .unwrap();
+        }
+        let mut recent = Index::new("events_recent", &["created_at", "id"]);
+        recent.get_columns_mut()[0].set_order(SortOrder::Desc);
+        recent.get_columns_mut()[0].set_nulls(Some(NullsOrder::Last));
+        recent.set_unique(true);
+        recent.set_include(&["payload"]);
+        recent.set_predicate(Some("payload IS NOT NULL"));
+        events_table.add_index(recent).unwrap();
+
+        let plan = PostgresEngine::default()
+            .generate_migration_plan(&mut current.clone())
+            .unwrap();
+        assert!(plan.get_sql().ends_with(
+            ");\nCREATE UNIQUE INDEX events_recent ON events \
+             (created_at DESC NULLS LAST, id) INCLUDE (payload) WHERE payload IS NOT NULL;\n"
+        ));
+
+        let mut desired = current.clone();
+        let mut payload = Index::new("events_payload", &["payload"]);
+        payload.set_method(IndexMethod::Gin);
+        let events_table = desired.get_table_mut("events").unwrap();
+        events_table.add_index(payload).unwrap();
+        events_table.remove_index("events_recent").unwrap();
+
+        let plan = PostgresEngine::default()
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "DROP INDEX events_recent;\n\
+             CREATE INDEX events_payload ON events USING gin (payload);\n"
+        );
+        assert!(plan.get_steps()[0]
+            .get_down_sql_script()
+            .unwrap()
+            .starts_with("CREATE UNIQUE INDEX events_recent"));
+    }
+
+    #[test]
+    fn test_concurrent_mode() {
+        let mut current = Schema::new();
+        current.add_table("users").unwrap();
+        current.add_table("orders").unwrap();
+        current
+            .get_table_mut("users")
+            .unwrap()
+            .add_column(Column::new("id", DataType::Integer, true, false, false))
+            .unwrap();
+        current
+            .get_table_mut("orders")
+            .unwrap()
+            .add_column(Column::new(
+                "user_id",
+                DataType::Integer,
+                false,
+                true,
+                false,
+            ))
+            .unwrap();
+        let mut desired = current.clone();
+        desired
+            .get_table_mut("orders")
+            .unwrap()
+            .add_index(Index::new("orders_user_id", &["user_id"]))
+            .unwrap();
+        desired
+            .add_relation(Relationship::new(
+                "users",
+                "id",
+                "orders",
+                "user_id",
+                RelationshipType::OneToMany,
+            ))
+            .unwrap();
+
+        let mut engine = PostgresEngine::default();
+        engine.set_concurrent(true);
+        let plan = engine
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "CREATE INDEX CONCURRENTLY orders_user_id ON orders (user_id);\n\
+             ALTER TABLE orders ADD CONSTRAINT orders_user_id_fk \
+             FOREIGN KEY (user_id) REFERENCES users (id) NOT VALID;\n\
+             ALTER TABLE orders VALIDATE CONSTRAINT orders_user_id_fk;\n"
+        );
+        let phases = plan.get_phases();
+        assert_eq!(phases.len(), 3);
+        assert!(!phases[0].is_transactional());
+        assert!(phases[1].is_transactional());
+        assert!(matches!(
+            phases[2].get_steps(),
+            [MigrationStep::ValidateRelationship { .. }]
+        ));
+        assert_eq!(
+            plan.get_down_sql().unwrap(),
+            "ALTER TABLE orders DROP CONSTRAINT orders_user_id_fk;\n\
+             DROP INDEX CONCURRENTLY orders_user_id;\n"
+        );
+
+        // new tables can't be in use yet, so they are created as usual
+        let plan = engine.generate_migration_plan(&mut desired).unwrap();
+        assert!(!plan.get_sql().contains("CONCURRENTLY"));
+        assert!(!plan.get_sql().contains("NOT VALID"));
+        assert_eq!(plan.get_phases().len(), 1);
+    }
 }
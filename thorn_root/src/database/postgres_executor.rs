@@ -0,0 +1,431 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use postgres::{Client, GenericClient};
+
+use crate::database::postgres_engine::PostgresEngine;
+use crate::error::CoreError;
+use crate::migration::migration_executor::{AppliedMigration, Migration, MigrationExecutor};
+use crate::migration::migration_plan::MigrationStep;
+
+const CREATE_HISTORY_TABLES: &str = "
+CREATE TABLE IF NOT EXISTS thorn_migrations (
+    id text PRIMARY KEY,
+    checksum text NOT NULL,
+    applied_at timestamp with time zone NOT NULL DEFAULT now(),
+    duration interval NOT NULL
+);
+CREATE TABLE IF NOT EXISTS thorn_migration_steps (
+    id text NOT NULL,
+    checksum text NOT NULL,
+    step integer NOT NULL,
+    PRIMARY KEY (id, step)
+)";
+
+const SELECT_HISTORY: &str = "
+SELECT id, checksum, applied_at, extract(epoch FROM duration)::float8
+FROM thorn_migrations
+ORDER BY applied_at, id";
+
+const INSERT_HISTORY: &str = "
+INSERT INTO thorn_migrations (id, checksum, duration)
+VALUES ($1, $2, make_interval(secs => $3))
+RETURNING applied_at";
+
+const SELECT_STEPS: &str = "SELECT checksum, step FROM thorn_migration_steps WHERE id = $1";
+
+const INSERT_STEP: &str =
+    "INSERT INTO thorn_migration_steps (id, checksum, step) VALUES ($1, $2, $3)";
+
+const DELETE_STEPS: &str = "DELETE FROM thorn_migration_steps WHERE id = $1";
+
+const SELECT_INDEX_VALIDITY: &str =
+    "SELECT indisvalid FROM pg_index WHERE indexrelid = to_regclass($1)";
+
+const LOCK: &str = "SELECT pg_advisory_lock(hashtext('thorn_migrations'))";
+
+const UNLOCK: &str = "SELECT pg_advisory_unlock(hashtext('thorn_migrations'))";
+
+/// Applies migrations to a PostgreSQL database, keeping their history in the
+/// `thorn_migrations` table, which is created on first use.
+///
+/// Each step of a plan runs in its own transaction, which also records the step in the
+/// `thorn_migration_steps` table, so a migration which failed halfway continues after its
+/// last applied step when it is applied again. PostgreSQL can't run concurrent index changes,
+/// and shouldn't run foreign key validations or labels added to enum types, in a transaction
+/// with other statements, so these steps run on their own and are recorded afterwards. They
+/// are safe to run again: an index a failed concurrent build left invalid is dropped and
+/// built again, an index which is already built or dropped is skipped, and labels are added
+/// `IF NOT EXISTS`.
+///
+/// `migrate` holds an advisory lock while it reads the history and applies the missing
+/// migrations, so executors running at the same time apply each migration once.
+pub struct PostgresExecutor<'a> {
+    client: &'a mut Client,
+}
+
+impl<'a> PostgresExecutor<'a> {
+    pub fn new(client: &'a mut Client) -> Self {
+        Self { client }
+    }
+
+    fn create_history_tables(&mut self) -> Result<(), CoreError> {
+        self.client
+            .batch_execute(CREATE_HISTORY_TABLES)
+            .map_err(database_error)
+    }
+
+    /// Returns the steps of a migration which were applied before it failed, counted from 1.
+    fn get_applied_steps(&mut self, migration: &Migration) -> Result<Vec<i32>, CoreError> {
+        let mut steps = vec![];
+        for row in self
+            .client
+            .query(SELECT_STEPS, &[&migration.get_id()])
+            .map_err(database_error)?
+        {
+            let checksum: &str = row.get(0);
+            if checksum != migration.get_checksum() {
+                return Err(CoreError::MigrationError(format!(
+                    "Migration {} was partially applied with checksum {}, but its plan now has checksum {}",
+                    migration.get_id(),
+                    checksum,
+                    migration.get_checksum()
+                )));
+            }
+            steps.push(row.get(1));
+        }
+        Ok(steps)
+    }
+
+    /// Whether an index exists and is valid, or `None` if it doesn't exist.
+    fn is_index_valid(&mut self, name: &str) -> Result<Option<bool>, postgres::Error> {
+        self.client
+            .query_opt(SELECT_INDEX_VALIDITY, &[&name])
+            .map(|row| row.map(|row| row.get(0)))
+    }
+
+    /// Runs a step which can't run in a transaction, skipping or redoing what an earlier
+    /// attempt left behind.
+    fn run_on_its_own(&mut self, step: &MigrationStep) -> Result<(), postgres::Error> {
+        match step {
+            MigrationStep::AddIndex { index, .. } => match self.is_index_valid(index.get_name())? {
+                Some(true) => return Ok(()),
+                Some(false) => self.client.batch_execute(&format!(
+                    "DROP INDEX CONCURRENTLY {};",
+                    PostgresEngine::default().quote(index.get_name())
+                ))?,
+                None => {}
+            },
+            MigrationStep::DropIndex { index, .. }
+                if self.is_index_valid(index.get_name())?.is_none() =>
+            {
+                return Ok(());
+            }
+            _ => {}
+        }
+        self.client.batch_execute(step.get_sql_script())
+    }
+}
+
+impl MigrationExecutor for PostgresExecutor<'_> {
+    fn acquire_lock(&mut self) -> Result<(), CoreError> {
+        self.client.batch_execute(LOCK).map_err(database_error)
+    }
+
+    fn release_lock(&mut self) -> Result<(), CoreError> {
+        self.client.batch_execute(UNLOCK).map_err(database_error)
+    }
+
+    fn get_applied_migrations(&mut self) -> Result<Vec<AppliedMigration>, CoreError> {
+        self.create_history_tables()?;
+        let rows = self
+            .client
+            .query(SELECT_HISTORY, &[])
+            .map_err(database_error)?;
+        Ok(rows
+            .iter()
+            .map(|row| {
+                AppliedMigration::new(
+                    row.get(0),
+                    row.get(1),
+                    row.get(2),
+                    Duration::from_secs_f64(row.get(3)),
+                )
+            })
+            .collect())
+    }
+
+    fn apply_migration(&mut self, migration: &Migration) -> Result<AppliedMigration, CoreError> {
+        self.create_history_tables()?;
+        let start = Instant::now();
+        let applied_steps = self.get_applied_steps(migration)?;
+        let steps = migration.get_plan().get_steps();
+
+        for (index, step) in steps.iter().enumerate() {
+            let number = index as i32 + 1;
+            if applied_steps.contains(&number) {
+                continue;
+            }
+            let step_error = |error: postgres::Error| {
+                CoreError::MigrationError(format!(
+                    "Migration {} failed at step {} of {}: {}",
+                    migration.get_id(),
+                    number,
+                    steps.len(),
+                    error_message(&error)
+                ))
+            };
+
+            if step.is_transactional() {
+                let mut transaction = self.client.transaction().map_err(database_error)?;
+                transaction
+                    .batch_execute(step.get_sql_script())
+                    .map_err(step_error)?;
+                record_step(&mut transaction, migration, number)?;
+                transaction.commit().map_err(database_error)?;
+            } else {
+                self.run_on_its_own(step).map_err(step_error)?;
+                record_step(self.client, migration, number)?;
+            }
+        }
+
+        let mut transaction = self.client.transaction().map_err(database_error)?;
+        let applied = record(&mut transaction, migration, start)?;
+        transaction
+            .execute(DELETE_STEPS, &[&migration.get_id()])
+            .map_err(database_error)?;
+        transaction.commit().map_err(database_error)?;
+        Ok(applied)
+    }
+}
+
+/// Records a step of a migration as applied, until the whole migration is.
+fn record_step(
+    client: &mut impl GenericClient,
+    migration: &Migration,
+    step: i32,
+) -> Result<(), CoreError> {
+    client
+        .execute(
+            INSERT_STEP,
+            &[&migration.get_id(), &migration.get_checksum(), &step],
+        )
+        .map(|_| ())
+        .map_err(database_error)
+}
+
+/// Inserts a migration which started to be applied at `start` into the history.
+fn record(
+    client: &mut impl GenericClient,
+    migration: &Migration,
+    start: Instant,
+) -> Result<AppliedMigration, CoreError> {
+    let duration = start.elapsed();
+    let applied_at: SystemTime = client
+        .query_one(
+            INSERT_HISTORY,
+            &[
+                &migration.get_id(),
+                &migration.get_checksum(),
+                &duration.as_secs_f64(),
+            ],
+        )
+        .map(|row| row.get(0))
+        .map_err(database_error)?;
+    Ok(AppliedMigration::new(
+        migration.get_id(),
+        migration.get_checksum(),
+        applied_at,
+        duration,
+    ))
+}
+
+fn database_error(error: postgres::Error) -> CoreError {
+    CoreError::DatabaseError(error_message(&error))
+}
+
+/// The message of the server for errors reported by the database, as `Error` only displays
+/// their kind.
+fn error_message(error: &postgres::Error) -> String {
+    match error.as_db_error() {
+        Some(db_error) => db_error.to_string(),
+        None => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::migration::migration_plan::{MigrationPlan, MigrationStep};
+    use crate::schema::index::Index;
+
+    use super::*;
+
+    fn create_table(id: &str, name: &str) -> Migration {
+        Migration::new(
+            id,
+            MigrationPlan::new(vec![MigrationStep::CreateTable {
+                name: name.to_string(),
+                sql_script: format!("CREATE TABLE {} (id integer PRIMARY KEY);\n", name),
+                down_sql_script: Some(format!("DROP TABLE {};\n", name)),
+            }]),
+        )
+    }
+
+    fn relation_exists(client: &mut Client, name: &str) -> bool {
+        client
+            .query_one("SELECT to_regclass($1) IS NOT NULL", &[&name])
+            .unwrap()
+            .get(0)
+    }
+
+    fn add_index(id: &str, table: &str, index: &str, unique: bool) -> Migration {
+        let mut step_index = Index::new(index, &["name"]);
+        step_index.set_unique(unique);
+        Migration::new(
+            id,
+            MigrationPlan::new(vec![MigrationStep::AddIndex {
+                table: table.to_string(),
+                sql_script: format!(
+                    "CREATE {}INDEX CONCURRENTLY {} ON {} (name);\n",
+                    if unique { "UNIQUE " } else { "" },
+                    index,
+                    table
+                ),
+                index: step_index,
+                concurrently: true,
+                down_sql_script: None,
+            }]),
+        )
+    }
+
+    #[test]
+    #[ignore = "needs a PostgreSQL server at THORN_ROOT_POSTGRES_URL"]
+    fn test_migrate() {
+        let url = std::env::var("THORN_ROOT_POSTGRES_URL")
+            .unwrap_or("host=localhost user=postgres".to_string());
+        let mut client = Client::connect(&url, postgres::NoTls).unwrap();
+        client
+            .batch_execute(
+                "DROP SCHEMA IF EXISTS thorn_root_migrations CASCADE;
+                CREATE SCHEMA thorn_root_migrations;
+                SET search_path TO thorn_root_migrations",
+            )
+            .unwrap();
+
+        let mut executor = PostgresExecutor::new(&mut client);
+        let applied = executor.migrate(&[create_table("1", "users")]).unwrap();
+        assert_eq!(applied.len(), 1);
+        let history = executor.get_applied_migrations().unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].get_id(), "1");
+        assert_eq!(history[0].get_checksum(), applied[0].get_checksum());
+        assert_eq!(history[0].get_applied_at(), applied[0].get_applied_at());
+
+        // each step commits on its own, so the orders table stays when the next step fails
+        let failing = Migration::new(
+            "2",
+            MigrationPlan::new(vec![
+                create_table("2", "orders").get_plan().get_steps()[0].clone(),
+                create_table("2", "users").get_plan().get_steps()[0].clone(),
+            ]),
+        );
+        let error = executor
+            .migrate(&[create_table("1", "users"), failing.clone()])
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Migration 2 failed at step 2 of 2: ERROR: relation \"users\" already exists"
+        );
+        assert_eq!(executor.get_applied_migrations().unwrap().len(), 1);
+        assert!(relation_exists(&mut client, "orders"));
+
+        // a partially applied migration can't change either
+        let error = PostgresExecutor::new(&mut client)
+            .migrate(&[create_table("2", "orders")])
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Migration 2 was partially applied with checksum"));
+
+        // once the cause is fixed, the migration continues after the applied step
+        client.batch_execute("DROP TABLE users").unwrap();
+        let mut executor = PostgresExecutor::new(&mut client);
+        let applied = executor
+            .migrate(&[create_table("1", "users"), failing])
+            .unwrap();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(executor.get_applied_migrations().unwrap().len(), 2);
+
+        let error = executor
+            .migrate(&[create_table("1", "accounts")])
+            .unwrap_err();
+        assert!(matches!(error, CoreError::MigrationError(_)));
+
+        // an index built concurrently can't be built in a transaction
+        let mut steps = create_table("3", "items").get_plan().get_steps().to_vec();
+        steps.push(MigrationStep::AddIndex {
+            table: "items".to_string(),
+            index: Index::new("items_id_idx", &["id"]),
+            concurrently: true,
+            sql_script: "CREATE INDEX CONCURRENTLY items_id_idx ON items (id);\n".to_string(),
+            down_sql_script: None,
+        });
+        executor
+            .migrate(&[Migration::new("3", MigrationPlan::new(steps))])
+            .unwrap();
+        assert_eq!(executor.get_applied_migrations().unwrap().len(), 3);
+
+        // a failed concurrent build leaves an invalid index, which is built again
+        client
+            .batch_execute("CREATE TABLE tags (name text); INSERT INTO tags VALUES ('a'), ('a')")
+            .unwrap();
+        let unique_tags = [add_index("4", "tags", "tags_name_key", true)];
+        let error = PostgresExecutor::new(&mut client)
+            .migrate(&unique_tags)
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Migration 4 failed at step 1 of 1"));
+        assert!(relation_exists(&mut client, "tags_name_key"));
+        client
+            .batch_execute("DELETE FROM tags; INSERT INTO tags VALUES ('a')")
+            .unwrap();
+        PostgresExecutor::new(&mut client)
+            .migrate(&unique_tags)
+            .unwrap();
+        let valid: bool = client
+            .query_one(SELECT_INDEX_VALIDITY, &[&"tags_name_key"])
+            .unwrap()
+            .get(0);
+        assert!(valid);
+
+        // an index built before the step was recorded isn't built again
+        client
+            .batch_execute("CREATE INDEX CONCURRENTLY tags_name_idx ON tags (name)")
+            .unwrap();
+        PostgresExecutor::new(&mut client)
+            .migrate(&[add_index("5", "tags", "tags_name_idx", false)])
+            .unwrap();
+
+        assert!(relation_exists(&mut client, "items_id_idx"));
+        assert!(relation_exists(&mut client, "orders"));
+        assert!(!relation_exists(&mut client, "accounts"));
+        let remaining_steps: i64 = client
+            .query_one("SELECT count(*) FROM thorn_migration_steps", &[])
+            .unwrap()
+            .get(0);
+        assert_eq!(remaining_steps, 0);
+        // the lock is released, also after a failure
+        let locked: bool = client
+            .query_one(
+                "SELECT count(*) > 0 FROM pg_locks WHERE locktype = 'advisory' AND granted",
+                &[],
+            )
+            .unwrap()
+            .get(0);
+        assert!(!locked);
+
+        client
+            .batch_execute("DROP SCHEMA thorn_root_migrations CASCADE")
+            .unwrap();
+    }
+}
@@ -0,0 +1,992 @@
+use crate::migration::migration_plan::{MigrationPlan, MigrationStep, FOREIGN_KEYS_OFF};
+use crate::migration::schema_diff::{SchemaChange, SchemaDiff};
+use crate::schema::column::Column;
+use crate::schema::constraint::{DefaultValue, PrimaryKey};
+use crate::schema::data_type::DataType;
+use crate::schema::index::{Index, IndexMethod, SortOrder};
+use crate::schema::relationship::Relationship;
+use crate::schema::table::Table;
+use crate::schema::Schema;
+
+use super::engine::{quote_identifier, referential_actions_sql, DatabaseEngine};
+use super::type_mapping::{base_db_type, db_type_parameters, TypeMapping};
+
+/// SQLite's keywords, which are quoted when used as names.
+const KEYWORDS: [&str; 147] = [
+    "ABORT",
+    "ACTION",
+    "ADD",
+    "AFTER",
+    "ALL",
+    "ALTER",
+    "ALWAYS",
+    "ANALYZE",
+    "AND",
+    "AS",
+    "ASC",
+    "ATTACH",
+    "AUTOINCREMENT",
+    "BEFORE",
+    "BEGIN",
+    "BETWEEN",
+    "BY",
+    "CASCADE",
+    "CASE",
+    "CAST",
+    "CHECK",
+    "COLLATE",
+    "COLUMN",
+    "COMMIT",
+    "CONFLICT",
+    "CONSTRAINT",
+    "CREATE",
+    "CROSS",
+    "CURRENT",
+    "CURRENT_DATE",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "DATABASE",
+    "DEFAULT",
+    "DEFERRABLE",
+    "DEFERRED",
+    "DELETE",
+    "DESC",
+    "DETACH",
+    "DISTINCT",
+    "DO",
+    "DROP",
+    "EACH",
+    "ELSE",
+    "END",
+    "ESCAPE",
+    "EXCEPT",
+    "EXCLUDE",
+    "EXCLUSIVE",
+    "EXISTS",
+    "EXPLAIN",
+    "FAIL",
+    "FILTER",
+    "FIRST",
+    "FOLLOWING",
+    "FOR",
+    "FOREIGN",
+    "FROM",
+    "FULL",
+    "GENERATED",
+    "GLOB",
+    "GROUP",
+    "GROUPS",
+    "HAVING",
+    "IF",
+    "IGNORE",
+    "IMMEDIATE",
+    "IN",
+    "INDEX",
+    "INDEXED",
+    "INITIALLY",
+    "INNER",
+    "INSERT",
+    "INSTEAD",
+    "INTERSECT",
+    "INTO",
+    "IS",
+    "ISNULL",
+    "JOIN",
+    "KEY",
+    "LAST",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "MATCH",
+    "MATERIALIZED",
+    "NATURAL",
+    "NO",
+    "NOT",
+    "NOTHING",
+    "NOTNULL",
+    "NULL",
+    "NULLS",
+    "OF",
+    "OFFSET",
+    "ON",
+    "OR",
+    "ORDER",
+    "OTHERS",
+    "OUTER",
+    "OVER",
+    "PARTITION",
+    "PLAN",
+    "PRAGMA",
+    "PRECEDING",
+    "PRIMARY",
+    "QUERY",
+    "RAISE",
+    "RANGE",
+    "RECURSIVE",
+    "REFERENCES",
+    "REGEXP",
+    "REINDEX",
+    "RELEASE",
+    "RENAME",
+    "REPLACE",
+    "RESTRICT",
+    "RETURNING",
+    "RIGHT",
+    "ROLLBACK",
+    "ROW",
+    "ROWS",
+    "SAVEPOINT",
+    "SELECT",
+    "SET",
+    "TABLE",
+    "TEMP",
+    "TEMPORARY",
+    "THEN",
+    "TIES",
+    "TO",
+    "TRANSACTION",
+    "TRIGGER",
+    "UNBOUNDED",
+    "UNION",
+    "UNIQUE",
+    "UPDATE",
+    "USING",
+    "VACUUM",
+    "VALUES",
+    "VIEW",
+    "VIRTUAL",
+    "WHEN",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+    "WITHOUT",
+];
+
+/// SQLite can neither add foreign keys to nor alter the columns of an existing table, so
+/// such changes are generated with the table-rebuild pattern: a new table is created with
+/// the desired definition, the rows are copied over and the new table takes the old one's name.
+/// Foreign key enforcement is switched off around a rebuild, which SQLite only honours
+/// outside of a transaction, so rebuild steps are not transactional.
+#[derive(Default)]
+pub struct SqliteEngine {
+    type_mapping: TypeMapping,
+}
+
+impl SqliteEngine {
+    /// Quotes an identifier which is a keyword, isn't a plain name or has upper case letters.
+    pub fn quote(&self, identifier: &str) -> String {
+        quote_identifier(identifier, &KEYWORDS)
+    }
+
+    fn quote_list(&self, identifiers: &[String]) -> String {
+        identifiers
+            .iter()
+            .map(|identifier| self.quote(identifier))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    fn column_definition(
+        &self,
+        table: &str,
+        column: &Column,
+        inline_primary_key: bool,
+    ) -> Result<String, String> {
+        let db_type = self.get_db_type(&column.get_data_type())?;
+        let mut definition = format!("{} {}", self.quote(column.get_name()), db_type);
+        if let Some(default) = column.get_default() {
+            definition.push_str(&format!(" DEFAULT {}", default.to_sql()));
+        }
+        // only an INTEGER PRIMARY KEY, which aliases the rowid, can't hold NULL by itself
+        let rowid_alias = inline_primary_key && db_type.eq_ignore_ascii_case("INTEGER");
+        if !rowid_alias && (column.is_primary_key() || !column.is_nullable()) {
+            definition.push_str(" NOT NULL");
+        }
+        if inline_primary_key {
+            definition.push_str(" PRIMARY KEY");
+        }
+        if column.is_unique() {
+            definition.push_str(&format!(
+                " CONSTRAINT {} UNIQUE",
+                self.quote(&column.get_unique_constraint_name(table))
+            ));
+        }
+        for check in column.get_checks() {
+            definition.push_str(&format!(
+                " CONSTRAINT {} CHECK ({})",
+                self.quote(check.get_name()),
+                check.get_expression()
+            ));
+        }
+        Ok(definition)
+    }
+
+    fn primary_key_definition(&self, primary_key: &PrimaryKey) -> String {
+        let constraint = match primary_key.get_name() {
+            Some(name) => format!("CONSTRAINT {} ", self.quote(name)),
+            None => String::default(),
+        };
+        format!(
+            "{}PRIMARY KEY ({})",
+            constraint,
+            self.quote_list(primary_key.get_columns())
+        )
+    }
+
+    fn foreign_key_definition(&self, relationship: &Relationship) -> String {
+        format!(
+            "CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({}){}",
+            self.quote(&relationship.get_constraint_name()),
+            self.quote_list(relationship.get_to_columns()),
+            self.quote(relationship.get_from_table()),
+            self.quote_list(relationship.get_from_columns()),
+            referential_actions_sql(relationship),
+        )
+    }
+
+    fn create_table_sql(
+        &self,
+        table_name: &str,
+        table: &Table,
+        schema: &Schema,
+    ) -> Result<String, String> {
+        let primary_key = table.get_primary_key();
+        let inline_primary_key = primary_key.is_some_and(|p| p.is_inline());
+        let mut definitions = table
+            .get_columns()
+            .iter()
+            .map(|column| {
+                let inline = inline_primary_key && column.is_primary_key();
+                self.column_definition(table.get_name(), column, inline)
+            })
+            .collect::<Result<Vec<String>, String>>()?;
+        if let (false, Some(primary_key)) = (inline_primary_key, primary_key) {
+            definitions.push(self.primary_key_definition(primary_key));
+        }
+        definitions.extend(
+            schema
+                .get_relationships()
+                .iter()
+                .filter(|r| r.get_to_table() == table.get_name() && !r.is_many_to_many())
+                .map(|r| self.foreign_key_definition(r)),
+        );
+        Ok(format!(
+            "CREATE TABLE {} (\n    {}\n);\n",
+            self.quote(table_name),
+            definitions.join(",\n    ")
+        ))
+    }
+
+    fn create_index_sql(&self, table: &str, index: &Index) -> Result<String, String> {
+        if index.get_method() != IndexMethod::BTree || !index.get_include().is_empty() {
+            return Err(format!(
+                "Index {}: only btree indexes without included columns are supported by SQLite",
+                index.get_name()
+            ));
+        }
+        if index.get_columns().iter().any(|c| c.get_nulls().is_some()) {
+            return Err(format!(
+                "Index {}: NULLS FIRST and NULLS LAST are not supported by SQLite",
+                index.get_name()
+            ));
+        }
+        let columns = index
+            .get_columns()
+            .iter()
+            .map(|c| {
+                let mut sql = self.quote(c.get_name());
+                if c.get_order() == SortOrder::Desc {
+                    sql.push_str(" DESC");
+                }
+                sql
+            })
+            .collect::<Vec<String>>();
+        let predicate = match index.get_predicate() {
+            Some(predicate) => format!(" WHERE {}", predicate),
+            None => String::default(),
+        };
+        Ok(format!(
+            "CREATE {}INDEX {} ON {} ({}){};\n",
+            if index.is_unique() { "UNIQUE " } else { "" },
+            self.quote(index.get_name()),
+            self.quote(table),
+            columns.join(", "),
+            predicate
+        ))
+    }
+
+    fn drop_index_sql(&self, index: &Index) -> String {
+        format!("DROP INDEX {};\n", self.quote(index.get_name()))
+    }
+
+    /// Generates the SQL creating all indexes of a table.
+    fn create_indexes_sql(&self, table: &Table) -> Result<String, String> {
+        table
+            .get_indexes()
+            .iter()
+            .map(|index| self.create_index_sql(table.get_name(), index))
+            .collect()
+    }
+
+    fn drop_table_sql(&self, table: &Table) -> String {
+        format!("DROP TABLE {};\n", self.quote(table.get_name()))
+    }
+
+    /// Rebuilds `table_name` from its definition in `before` to the one in `after`,
+    /// keeping the rows of every column present in both.
+    fn rebuild_table_sql(
+        &self,
+        before: &Schema,
+        after: &Schema,
+        table_name: &str,
+    ) -> Result<String, String> {
+        let before_table = before.get_table(table_name)?;
+        let after_table = after.get_table(table_name)?;
+        let new_table_name = format!("new_{}", table_name);
+
+        let mut sql = String::from(FOREIGN_KEYS_OFF);
+        sql.push_str(&self.create_table_sql(&new_table_name, after_table, after)?);
+
+        let kept_columns = after_table
+            .get_columns()
+            .iter()
+            .filter(|c| before_table.get_column(c.get_name()).is_ok())
+            .map(|c| self.quote(c.get_name()))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let (new_table_name, table_name) = (self.quote(&new_table_name), self.quote(table_name));
+        if !kept_columns.is_empty() {
+            sql.push_str(&format!(
+                "INSERT INTO {} ({}) SELECT {} FROM {};\n",
+                new_table_name, kept_columns, kept_columns, table_name
+            ));
+        }
+
+        sql.push_str(&format!("DROP TABLE {};\n", table_name));
+        sql.push_str(&format!(
+            "ALTER TABLE {} RENAME TO {};\n",
+            new_table_name, table_name
+        ));
+        // the indexes were dropped with the old table
+        sql.push_str(&self.create_indexes_sql(after_table)?);
+        sql.push_str("PRAGMA foreign_key_check;\nPRAGMA foreign_keys = ON;\n");
+        Ok(sql)
+    }
+
+    /// Generates the SQL adding `column`, unless SQLite requires a rebuild for it.
+    fn add_column_sql(
+        &self,
+        before: &Schema,
+        after: &Schema,
+        table: &str,
+        column: &Column,
+    ) -> Result<String, String> {
+        // ADD COLUMN can't add unique columns, nor NOT NULL columns without a constant
+        // default; key columns become NOT NULL with the rebuild adding the primary key
+        let needs_rebuild = column.is_unique()
+            || matches!(column.get_default(), Some(DefaultValue::Expression(_)))
+            || (!column.is_primary_key()
+                && !column.is_nullable()
+                && matches!(column.get_default(), None | Some(DefaultValue::Null)));
+        if needs_rebuild {
+            return self.rebuild_table_sql(before, after, table);
+        }
+        Ok(format!(
+            "ALTER TABLE {} ADD COLUMN {};\n",
+            self.quote(table),
+            self.column_definition(table, column, false)?
+        ))
+    }
+
+    /// Generates the SQL removing `column`, unless SQLite requires a rebuild for it.
+    fn remove_column_sql(
+        &self,
+        before: &Schema,
+        after: &Schema,
+        table: &str,
+        column: &Column,
+    ) -> Result<String, String> {
+        // DROP COLUMN fails on key columns
+        let in_primary_key = before
+            .get_table(table)?
+            .get_primary_key()
+            .is_some_and(|p| p.contains(column.get_name()));
+        if in_primary_key || column.is_unique() {
+            return self.rebuild_table_sql(before, after, table);
+        }
+        Ok(format!(
+            "ALTER TABLE {} DROP COLUMN {};\n",
+            self.quote(table),
+            self.quote(column.get_name())
+        ))
+    }
+}
+
+impl DatabaseEngine for SqliteEngine {
+    fn name() -> &'static str {
+        "SQLite"
+    }
+
+    fn get_type_mapping(&self) -> &TypeMapping {
+        &self.type_mapping
+    }
+
+    fn get_type_mapping_mut(&mut self) -> &mut TypeMapping {
+        &mut self.type_mapping
+    }
+
+    fn default_db_type(&self, data_type: &DataType) -> Option<String> {
+        let db_type = match data_type {
+            DataType::Integer | DataType::SmallInt | DataType::BigInt | DataType::Boolean => {
+                "INTEGER"
+            }
+            DataType::Float => "REAL",
+            DataType::Decimal { .. } => "NUMERIC",
+            DataType::String
+            | DataType::Varchar(_)
+            | DataType::Date
+            | DataType::Timestamp { .. }
+            | DataType::Time { .. }
+            | DataType::Uuid
+            | DataType::Json
+            | DataType::Jsonb
+            | DataType::Enum(_) => "TEXT",
+            DataType::Array(_) => return None,
+            DataType::Binary => "BLOB",
+        };
+        Some(db_type.to_string())
+    }
+
+    /// Follows SQLite's rules for determining the affinity of a declared type, but recognises
+    /// the common declared type names first so that e.g. `BOOLEAN` doesn't become a `Float`.
+    fn default_data_type(&self, db_type: &str) -> Option<DataType> {
+        let parameters = db_type_parameters(db_type);
+        match (base_db_type(db_type).as_str(), &parameters[..]) {
+            ("SMALLINT", _) => return Some(DataType::SmallInt),
+            ("BIGINT", _) => return Some(DataType::BigInt),
+            ("VARCHAR" | "CHARACTER VARYING", [length]) => return Some(DataType::Varchar(*length)),
+            ("NUMERIC" | "DECIMAL", [precision, scale]) => {
+                return Some(DataType::Decimal {
+                    precision: u16::try_from(*precision).ok()?,
+                    scale: u16::try_from(*scale).ok()?,
+                })
+            }
+            ("TIME", _) => {
+                return Some(DataType::Time {
+                    precision: None,
+                    with_time_zone: false,
+                })
+            }
+            ("UUID", _) => return Some(DataType::Uuid),
+            ("JSON", _) => return Some(DataType::Json),
+            _ => {}
+        }
+
+        if db_type.contains("BOOL") {
+            Some(DataType::Boolean)
+        } else if db_type.contains("DATETIME") || db_type.contains("TIMESTAMP") {
+            Some(DataType::Timestamp {
+                precision: None,
+                with_time_zone: false,
+            })
+        } else if db_type.contains("DATE") {
+            Some(DataType::Date)
+        } else if db_type.contains("INT") {
+            Some(DataType::Integer)
+        } else if db_type.contains("CHAR") || db_type.contains("CLOB") || db_type.contains("TEXT") {
+            Some(DataType::String)
+        } else if db_type.contains("BLOB") {
+            Some(DataType::Binary)
+        } else if db_type.contains("REAL") || db_type.contains("FLOA") || db_type.contains("DOUB") {
+            Some(DataType::Float)
+        } else {
+            None
+        }
+    }
+
+    fn generate_migration_plan(&self, schema: &mut Schema) -> Result<MigrationPlan, String> {
+        self.generate_diff_migration_plan(&Schema::new(), schema)
+    }
+
+    fn generate_diff_migration_plan(
+        &self,
+        current: &Schema,
+        desired: &Schema,
+    ) -> Result<MigrationPlan, String> {
+        desired.validate()?;
+        let mut migration_steps = Vec::<MigrationStep>::default();
+        let mut working = current.clone();
+
+        for change in SchemaDiff::new(current, desired).get_changes() {
+            let before = working.clone();
+            change.apply_to(&mut working)?;
+
+            let step = match change {
+                SchemaChange::CreateTable(table) => MigrationStep::CreateTable {
+                    name: table.get_name().to_string(),
+                    // foreign keys of new tables are created inline
+                    sql_script: self.create_table_sql(table.get_name(), table, desired)?
+                        + &self.create_indexes_sql(table)?,
+                    down_sql_script: Some(self.drop_table_sql(table)),
+                },
+                SchemaChange::DropTable(table) => MigrationStep::DropTable {
+                    name: table.get_name().to_string(),
+                    sql_script: self.drop_table_sql(table),
+                    down_sql_script: Some(
+                        self.create_table_sql(table.get_name(), table, current)?
+                            + &self.create_indexes_sql(table)?,
+                    ),
+                },
+                SchemaChange::AddColumn { table, column } => MigrationStep::AddColumn {
+                    table: table.clone(),
+                    column: column.clone(),
+                    sql_script: self.add_column_sql(&before, &working, table, column)?,
+                    down_sql_script: Some(
+                        self.remove_column_sql(&working, &before, table, column)?,
+                    ),
+                },
+                SchemaChange::RemoveColumn { table, column } => MigrationStep::RemoveColumn {
+                    table: table.clone(),
+                    column: column.clone(),
+                    sql_script: self.remove_column_sql(&before, &working, table, column)?,
+                    down_sql_script: Some(self.add_column_sql(&working, &before, table, column)?),
+                },
+                SchemaChange::AlterColumn { table, from, to } => {
+                    let mut unchanged = from.clone();
+                    *unchanged.is_primary_key_mut() = to.is_primary_key();
+                    if unchanged == *to {
+                        // the primary key is rebuilt by its own step
+                        continue;
+                    }
+                    MigrationStep::AlterColumn {
+                        table: table.clone(),
+                        column: to.clone(),
+                        previous_column: from.clone(),
+                        sql_script: self.rebuild_table_sql(&before, &working, table)?,
+                        down_sql_script: Some(self.rebuild_table_sql(&working, &before, table)?),
+                    }
+                }
+                SchemaChange::AddPrimaryKey { table, primary_key } => {
+                    MigrationStep::AddPrimaryKey {
+                        table: table.clone(),
+                        primary_key: primary_key.clone(),
+                        sql_script: self.rebuild_table_sql(&before, &working, table)?,
+                        down_sql_script: Some(self.rebuild_table_sql(&working, &before, table)?),
+                    }
+                }
+                SchemaChange::DropPrimaryKey { table, primary_key } => {
+                    MigrationStep::DropPrimaryKey {
+                        table: table.clone(),
+                        primary_key: primary_key.clone(),
+                        sql_script: self.rebuild_table_sql(&before, &working, table)?,
+                        down_sql_script: Some(self.rebuild_table_sql(&working, &before, table)?),
+                    }
+                }
+                SchemaChange::AddIndex { table, index } => MigrationStep::AddIndex {
+                    table: table.clone(),
+                    index: index.clone(),
+                    concurrently: false,
+                    sql_script: self.create_index_sql(table, index)?,
+                    down_sql_script: Some(self.drop_index_sql(index)),
+                },
+                SchemaChange::DropIndex { table, index } => MigrationStep::DropIndex {
+                    table: table.clone(),
+                    index: index.clone(),
+                    concurrently: false,
+                    sql_script: self.drop_index_sql(index),
+                    down_sql_script: Some(self.create_index_sql(table, index)?),
+                },
+                SchemaChange::AddRelationship(relationship)
+                | SchemaChange::DropRelationship(relationship)
+                    if relationship.is_many_to_many() =>
+                {
+                    // modeled by the foreign keys of its junction table
+                    continue;
+                }
+                SchemaChange::AddRelationship(relationship) => {
+                    if current.get_table(relationship.get_to_table()).is_err() {
+                        // already part of the CREATE TABLE statement
+                        continue;
+                    }
+                    let table = relationship.get_to_table();
+                    MigrationStep::AddRelationship {
+                        relationship: relationship.clone(),
+                        sql_script: self.rebuild_table_sql(&before, &working, table)?,
+                        down_sql_script: Some(self.rebuild_table_sql(&working, &before, table)?),
+                    }
+                }
+                SchemaChange::DropRelationship(relationship) => {
+                    if desired.get_table(relationship.get_to_table()).is_err() {
+                        // dropped together with its table
+                        continue;
+                    }
+                    let table = relationship.get_to_table();
+                    MigrationStep::DropRelationship {
+                        relationship: relationship.clone(),
+                        sql_script: self.rebuild_table_sql(&before, &working, table)?,
+                        down_sql_script: Some(self.rebuild_table_sql(&working, &before, table)?),
+                    }
+                }
+                SchemaChange::CreateEnum(_)
+                | SchemaChange::DropEnum(_)
+                | SchemaChange::AlterEnum { .. } => {
+                    // SQLite has no enum types, their columns are stored as TEXT
+                    continue;
+                }
+            };
+            migration_steps.push(step);
+        }
+
+        Ok(MigrationPlan::new(migration_steps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::constraint::{DefaultValue, PrimaryKey};
+    use crate::schema::index::NullsOrder;
+    use crate::schema::relationship::RelationshipType;
+    use crate::test_fixtures::users_and_orders;
+
+    use super::*;
+
+    #[test]
+    fn test_create_tables_with_inline_foreign_key() {
+        let mut schema = users_and_orders();
+        schema
+            .add_relation(Relationship::new(
+                "users",
+                "id",
+                "orders",
+                "user_id",
+                RelationshipType::OneToMany,
+            ))
+            .unwrap();
+
+        let plan = SqliteEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap();
+        assert_eq!(plan.get_steps().len(), 2);
+        assert_eq!(
+            plan.get_sql(),
+            "CREATE TABLE users (\n    id INTEGER PRIMARY KEY\n);\n\
+             CREATE TABLE orders (\n    \
+             id INTEGER PRIMARY KEY,\n    \
+             user_id INTEGER NOT NULL,\n    \
+             CONSTRAINT orders_user_id_fk FOREIGN KEY (user_id) REFERENCES users (id)\n);\n"
+        );
+    }
+
+    #[test]
+    fn test_add_relationship_rebuilds_table() {
+        let current = users_and_orders();
+        let mut desired = users_and_orders();
+        desired
+            .add_relation(Relationship::new(
+                "users",
+                "id",
+                "orders",
+                "user_id",
+                RelationshipType::OneToMany,
+            ))
+            .unwrap();
+
+        let plan = SqliteEngine::default()
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        assert_eq!(
+            plan.get_sql(),
+            "PRAGMA foreign_keys = OFF;\n\
+             CREATE TABLE new_orders (\n    \
+             id INTEGER PRIMARY KEY,\n    \
+             user_id INTEGER NOT NULL,\n    \
+             CONSTRAINT orders_user_id_fk FOREIGN KEY (user_id) REFERENCES users (id)\n);\n\
+             INSERT INTO new_orders (id, user_id) SELECT id, user_id FROM orders;\n\
+             DROP TABLE orders;\n\
+             ALTER TABLE new_orders RENAME TO orders;\n\
+             PRAGMA foreign_key_check;\n\
+             PRAGMA foreign_keys = ON;\n"
+        );
+        assert!(plan.get_down_sql().unwrap().contains(
+            "CREATE TABLE new_orders (\n    id INTEGER PRIMARY KEY,\n    user_id INTEGER NOT NULL\n);\n"
+        ));
+    }
+
+    #[test]
+    fn test_alter_and_add_columns() {
+        let current = users_and_orders();
+        let mut desired = users_and_orders();
+        let orders_table = desired.get_table_mut("orders").unwrap();
+        *orders_table.get_columns_mut()[1].get_data_type_mut() = DataType::String;
+        orders_table
+            .add_column(Column::new("total", DataType::Float, false, false, true))
+            .unwrap();
+
+        let plan = SqliteEngine::default()
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        let steps = plan.get_steps();
+        assert_eq!(steps.len(), 2);
+        assert!(matches!(steps[0], MigrationStep::AlterColumn { .. }));
+        assert!(steps[0].get_sql_script().contains(
+            "CREATE TABLE new_orders (\n    id INTEGER PRIMARY KEY,\n    user_id TEXT NOT NULL\n);\n"
+        ));
+        assert_eq!(
+            steps[1].get_sql_script(),
+            "ALTER TABLE orders ADD COLUMN total REAL;\n"
+        );
+        assert_eq!(
+            steps[1].get_down_sql_script(),
+            Some("ALTER TABLE orders DROP COLUMN total;\n")
+        );
+    }
+
+    #[test]
+    fn test_type_mapping() {
+        let engine = SqliteEngine::default();
+        assert_eq!(engine.get_db_type(&DataType::Boolean).unwrap(), "INTEGER");
+        assert_eq!(engine.get_data_type("BIGINT"), Some(DataType::BigInt));
+        assert_eq!(engine.get_data_type("MEDIUMINT"), Some(DataType::Integer));
+        assert_eq!(
+            engine.get_data_type("varchar(30)"),
+            Some(DataType::Varchar(30))
+        );
+        assert_eq!(engine.get_data_type("nvarchar"), Some(DataType::String));
+        assert_eq!(engine.get_data_type("double"), Some(DataType::Float));
+        assert_eq!(engine.get_data_type("boolean"), Some(DataType::Boolean));
+        assert_eq!(engine.get_data_type("BLOB"), Some(DataType::Binary));
+        assert_eq!(
+            engine.get_data_type("decimal(10,2)"),
+            Some(DataType::Decimal {
+                precision: 10,
+                scale: 2
+            })
+        );
+        assert_eq!(
+            engine.get_db_type(&DataType::Decimal {
+                precision: 10,
+                scale: 2
+            }),
+            Ok("NUMERIC".to_string())
+        );
+        assert_eq!(engine.get_data_type("NUMERIC"), None);
+    }
+
+    #[test]
+    fn test_array_columns_are_rejected() {
+        let mut schema = users_and_orders();
+        schema
+            .get_table_mut("users")
+            .unwrap()
+            .add_column(Column::new(
+                "tags",
+                DataType::Array(Box::new(DataType::String)),
+                false,
+                false,
+                false,
+            ))
+            .unwrap();
+        let error = SqliteEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap_err();
+        assert!(error.contains("not supported"));
+    }
+
+    #[test]
+    fn test_add_constrained_columns() {
+        let current = users_and_orders();
+        let mut desired = users_and_orders();
+        let mut email = Column::new("email", DataType::String, false, false, true);
+        *email.is_unique_mut() = true;
+        let mut active = Column::new("active", DataType::Boolean, false, false, false);
+        *active.get_default_mut() = Some(DefaultValue::Boolean(true));
+        let users_table = desired.get_table_mut("users").unwrap();
+        users_table.add_column(email).unwrap();
+        users_table.add_column(active).unwrap();
+
+        let plan = SqliteEngine::default()
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        let steps = plan.get_steps();
+        assert_eq!(steps.len(), 2);
+        // UNIQUE columns can only be added by rebuilding the table
+        assert!(steps[0].get_sql_script().contains(
+            "CREATE TABLE new_users (\n    \
+             id INTEGER PRIMARY KEY,\n    \
+             email TEXT CONSTRAINT users_email_key UNIQUE\n);\n"
+        ));
+        assert_eq!(
+            steps[1].get_sql_script(),
+            "ALTER TABLE users ADD COLUMN active INTEGER DEFAULT TRUE NOT NULL;\n"
+        );
+    }
+
+    #[test]
+    fn test_composite_primary_key() {
+        let current = users_and_orders();
+        let mut desired = users_and_orders();
+        let orders_table = desired.get_table_mut("orders").unwrap();
+        orders_table
+            .set_primary_key(Some(PrimaryKey::new(&["id", "user_id"])))
+            .unwrap();
+
+        let plan = SqliteEngine::default()
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        let steps = plan.get_steps();
+        assert_eq!(steps.len(), 2);
+        assert!(matches!(steps[0], MigrationStep::DropPrimaryKey { .. }));
+        assert!(matches!(steps[1], MigrationStep::AddPrimaryKey { .. }));
+        assert!(steps[1].get_sql_script().contains(
+            "CREATE TABLE new_orders (\n    \
+             id INTEGER NOT NULL,\n    \
+             user_id INTEGER NOT NULL,\n    \
+             PRIMARY KEY (id, user_id)\n);\n"
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite")]
+    fn test_primary_keys_reject_null() {
+        let mut schema = Schema::new();
+        schema.add_table("tags").unwrap();
+        schema.add_table("order_items").unwrap();
+        schema
+            .get_table_mut("tags")
+            .unwrap()
+            .add_column(Column::new("name", DataType::String, true, false, true))
+            .unwrap();
+        let order_items = schema.get_table_mut("order_items").unwrap();
+        for name in ["order_id", "position"] {
+            order_items
+                .add_column(Column::new(name, DataType::Integer, true, false, true))
+                .unwrap();
+        }
+        let plan = SqliteEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap();
+
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection.execute_batch(&plan.get_sql()).unwrap();
+        assert!(connection
+            .execute("INSERT INTO tags VALUES (NULL)", [])
+            .is_err());
+        assert!(connection
+            .execute("INSERT INTO order_items VALUES (NULL, NULL)", [])
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite")]
+    fn test_rebuild_keeps_cascading_children() {
+        let mut relationship = Relationship::new(
+            "users",
+            "id",
+            "orders",
+            "user_id",
+            RelationshipType::OneToMany,
+        );
+        relationship.set_on_delete(crate::schema::relationship::ReferentialAction::Cascade);
+        let mut current = users_and_orders();
+        current.add_relation(relationship).unwrap();
+        let mut desired = current.clone();
+        let mut email = Column::new("email", DataType::String, false, false, true);
+        *email.is_unique_mut() = true;
+        desired
+            .get_table_mut("users")
+            .unwrap()
+            .add_column(email)
+            .unwrap();
+
+        let engine = SqliteEngine::default();
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                &engine
+                    .generate_migration_plan(&mut current)
+                    .unwrap()
+                    .get_sql(),
+            )
+            .unwrap();
+        connection
+            .execute_batch(
+                "PRAGMA foreign_keys = ON;\n\
+                 INSERT INTO users VALUES (1);\n\
+                 INSERT INTO orders VALUES (1, 1);\n",
+            )
+            .unwrap();
+
+        let plan = engine
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        assert!(!plan.get_steps()[0].is_transactional());
+        for phase in plan.get_phases() {
+            if phase.is_transactional() {
+                connection
+                    .execute_batch(&format!("BEGIN;\n{}COMMIT;\n", phase.get_sql()))
+                    .unwrap();
+            } else {
+                for step in phase.get_steps() {
+                    connection.execute_batch(step.get_sql_script()).unwrap();
+                }
+            }
+        }
+        let orders: i64 = connection
+            .query_row("SELECT COUNT(*) FROM orders", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(orders, 1);
+    }
+
+    #[test]
+    fn test_rebuild_recreates_indexes() {
+        let mut current = users_and_orders();
+        let mut user_id = Index::new("orders_user_id", &["user_id"]);
+        user_id.set_predicate(Some("user_id > 0"));
+        current
+            .get_table_mut("orders")
+            .unwrap()
+            .add_index(user_id)
+            .unwrap();
+        let mut desired = current.clone();
+        desired
+            .add_relation(Relationship::new(
+                "users",
+                "id",
+                "orders",
+                "user_id",
+                RelationshipType::OneToMany,
+            ))
+            .unwrap();
+
+        let plan = SqliteEngine::default()
+            .generate_diff_migration_plan(&current, &desired)
+            .unwrap();
+        assert!(plan.get_sql().ends_with(
+            "ALTER TABLE new_orders RENAME TO orders;\n\
+             CREATE INDEX orders_user_id ON orders (user_id) WHERE user_id > 0;\n\
+             PRAGMA foreign_key_check;\n\
+             PRAGMA foreign_keys = ON;\n"
+        ));
+
+        let plan = SqliteEngine::default()
+            .generate_migration_plan(&mut current)
+            .unwrap();
+        assert!(plan
+            .get_sql()
+            .ends_with(");\nCREATE INDEX orders_user_id ON orders (user_id) WHERE user_id > 0;\n"));
+
+        let mut nulls_last = Index::new("orders_id_nulls_last", &["id"]);
+        nulls_last.get_columns_mut()[0].set_nulls(Some(NullsOrder::Last));
+        desired
+            .get_table_mut("orders")
+            .unwrap()
+            .add_index(nulls_last)
+            .unwrap();
+        assert_eq!(
+            SqliteEngine::default()
+                .generate_diff_migration_plan(&current, &desired)
+                .unwrap_err(),
+            "Index orders_id_nulls_last: NULLS FIRST and NULLS LAST are not supported by SQLite"
+        );
+    }
+}
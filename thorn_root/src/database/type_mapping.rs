@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use crate::schema::data_type::DataType;
+
+/// Per-project overrides of the native types a database engine uses for data types,
+/// e.g. mapping `DataType::String` to `VARCHAR(320)` for a schema full of e-mail addresses.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TypeMapping {
+    overrides: HashMap<DataType, String>,
+}
+
+impl TypeMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes `data_type` map to `db_type`, returning the previous override if any.
+    pub fn add_override(&mut self, data_type: DataType, db_type: &str) -> Option<String> {
+        self.overrides.insert(data_type, db_type.to_string())
+    }
+
+    pub fn remove_override(&mut self, data_type: &DataType) -> Option<String> {
+        self.overrides.remove(data_type)
+    }
+
+    pub fn get_override(&self, data_type: &DataType) -> Option<&str> {
+        self.overrides
+            .get(data_type)
+            .map(|db_type| db_type.as_str())
+    }
+
+    pub fn get_overrides(&self) -> &HashMap<DataType, String> {
+        &self.overrides
+    }
+
+    /// Finds the data type whose override produces `db_type`.
+    pub fn find_data_type(&self, db_type: &str) -> Option<DataType> {
+        let db_type = normalize_db_type(db_type);
+        self.overrides
+            .iter()
+            .find(|(_, native)| normalize_db_type(native) == db_type)
+            .map(|(data_type, _)| data_type.clone())
+    }
+}
+
+/// Brings a native type name into a canonical form for comparisons:
+/// upper case, single spaces and no spaces around parentheses or commas.
+pub fn normalize_db_type(db_type: &str) -> String {
+    db_type
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_uppercase()
+        .replace(" (", "(")
+        .replace("( ", "(")
+        .replace(" )", ")")
+        .replace(", ", ",")
+        .replace(" ,", ",")
+}
+
+/// Returns the name of a native type without its parameters, e.g. `VARCHAR` for `varchar(20)`
+/// or `TIMESTAMP WITH TIME ZONE` for `timestamp(3) with time zone`.
+pub fn base_db_type(db_type: &str) -> String {
+    let db_type = normalize_db_type(db_type);
+    match (db_type.find('('), db_type.find(')')) {
+        (Some(start), Some(end)) if start < end => {
+            normalize_db_type(&format!("{} {}", &db_type[..start], &db_type[end + 1..]))
+        }
+        _ => db_type,
+    }
+}
+
+/// Returns the numeric parameters of a native type, e.g. `[10, 2]` for `NUMERIC(10, 2)`.
+/// Parameters which aren't numbers are skipped.
+pub fn db_type_parameters(db_type: &str) -> Vec<u32> {
+    let db_type = normalize_db_type(db_type);
+    match (db_type.find('('), db_type.find(')')) {
+        (Some(start), Some(end)) if start < end => db_type[start + 1..end]
+            .split(',')
+            .filter_map(|parameter| parameter.trim().parse().ok())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Renders a `TIMESTAMP` or `TIME` type with its optional precision and time zone suffix.
+pub fn time_db_type(name: &str, precision: &Option<u8>, with_time_zone: bool) -> String {
+    format!(
+        "{}{}{}",
+        name,
+        precision.map(|p| format!("({})", p)).unwrap_or_default(),
+        if with_time_zone {
+            " WITH TIME ZONE"
+        } else {
+            ""
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides() {
+        let mut type_mapping = TypeMapping::new();
+        assert_eq!(
+            type_mapping.add_override(DataType::String, "VARCHAR(320)"),
+            None
+        );
+        assert_eq!(
+            type_mapping.get_override(&DataType::String),
+            Some("VARCHAR(320)")
+        );
+        assert_eq!(
+            type_mapping.find_data_type("varchar( 320 )"),
+            Some(DataType::String)
+        );
+        assert_eq!(type_mapping.find_data_type("VARCHAR(20)"), None);
+        assert_eq!(
+            type_mapping.remove_override(&DataType::String),
+            Some("VARCHAR(320)".to_string())
+        );
+        assert!(type_mapping.get_overrides().is_empty());
+    }
+
+    #[test]
+    fn test_normalize_db_type() {
+        assert_eq!(
+            normalize_db_type("  double   precision "),
+            "DOUBLE PRECISION"
+        );
+        assert_eq!(normalize_db_type("numeric ( 10 , 2 )"), "NUMERIC(10,2)");
+        assert_eq!(base_db_type("character varying(20)"), "CHARACTER VARYING");
+        assert_eq!(
+            base_db_type("timestamp(3) with time zone"),
+            "TIMESTAMP WITH TIME ZONE"
+        );
+        assert_eq!(db_type_parameters("numeric(10, 2)"), vec![10, 2]);
+        assert_eq!(db_type_parameters("TEXT"), Vec::<u32>::new());
+    }
+}
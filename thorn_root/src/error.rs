@@ -1,6 +1,33 @@
+use std::fmt;
+
 #[derive(Debug, Clone)]
 pub enum CoreError {
     DatabaseError(String),
     SchemaValidationError(String),
     MigrationError(String),
+    /// A schema document which can't be parsed or doesn't have the expected structure.
+    FormatError(String),
+    /// A schema document written by a newer version of the library.
+    UnsupportedFormatVersion {
+        version: u64,
+        supported: u32,
+    },
 }
+
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoreError::DatabaseError(message)
+            | CoreError::SchemaValidationError(message)
+            | CoreError::MigrationError(message)
+            | CoreError::FormatError(message) => write!(f, "{}", message),
+            CoreError::UnsupportedFormatVersion { version, supported } => write!(
+                f,
+                "Schema document has format version {}, but this version of ThornRoot only reads up to {}",
+                version, supported
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CoreError {}
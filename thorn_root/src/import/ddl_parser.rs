@@ -0,0 +1,1345 @@
+use crate::database::engine::DatabaseEngine;
+use crate::database::postgres_engine::PostgresEngine;
+use crate::schema::column::Column;
+use crate::schema::constraint::{CheckConstraint, DefaultValue};
+use crate::schema::data_type::DataType;
+use crate::schema::enum_type::EnumType;
+use crate::schema::index::{Index, IndexColumn, IndexMethod, NullsOrder, SortOrder};
+use crate::schema::relationship::ReferentialAction;
+use crate::schema::Schema;
+
+use super::lexer::{tokenize, Token, TokenKind};
+use super::pg_dump::{is_dump_boilerplate, without_meta_commands};
+use super::{
+    add_foreign_key, add_unique_constraint, as_strs, primary_key, ForeignKey, ImportIssue,
+    ImportResult,
+};
+
+/// Keywords which end the data type or the default of a column definition.
+const COLUMN_CONSTRAINT_KEYWORDS: [&str; 10] = [
+    "CONSTRAINT",
+    "NOT",
+    "NULL",
+    "DEFAULT",
+    "PRIMARY",
+    "UNIQUE",
+    "CHECK",
+    "REFERENCES",
+    "COLLATE",
+    "GENERATED",
+];
+
+/// Types whose literals PostgreSQL may write as a string cast to the type.
+const NUMERIC_TYPES: [&str; 12] = [
+    "smallint", "integer", "int", "int2", "int4", "int8", "bigint", "numeric", "decimal", "real",
+    "double", "float8",
+];
+
+/// Keywords which start a table constraint instead of a column definition.
+const TABLE_CONSTRAINT_KEYWORDS: [&str; 7] = [
+    "CONSTRAINT",
+    "PRIMARY",
+    "UNIQUE",
+    "FOREIGN",
+    "CHECK",
+    "EXCLUDE",
+    "LIKE",
+];
+
+/// Imports PostgreSQL DDL scripts into a schema.
+#[derive(Default)]
+pub struct DdlParser {
+    engine: PostgresEngine,
+}
+
+impl DdlParser {
+    /// Creates a parser which maps column types through the type mapping of the engine.
+    pub fn new(engine: PostgresEngine) -> Self {
+        Self { engine }
+    }
+
+    /// Builds a schema from the `CREATE TABLE`, `CREATE INDEX`, `CREATE TYPE ... AS ENUM` and
+    /// `ALTER TABLE ... ADD` statements of a script.
+    ///
+    /// Statements the parser doesn't support are skipped and reported with their position, as
+    /// are single column definitions and constraints it can't import. Foreign keys are added
+    /// after all statements were read, so a table may reference a table created further down.
+    pub fn parse(&self, sql: &str) -> ImportResult {
+        self.import(sql, false)
+    }
+
+    /// Builds a schema from the output of `pg_dump --schema-only`, like `parse`.
+    ///
+    /// Statements of the dump which don't change the structure of the schema, such as `SET`,
+    /// `COMMENT ON`, `GRANT` and changes of owners, are skipped without being reported.
+    pub fn parse_pg_dump(&self, dump: &str) -> ImportResult {
+        self.import(&without_meta_commands(dump), true)
+    }
+
+    fn import(&self, sql: &str, dump: bool) -> ImportResult {
+        let mut import = Import {
+            engine: &self.engine,
+            sql,
+            dump,
+            schema: Schema::new(),
+            issues: vec![],
+            foreign_keys: vec![],
+            sequences: vec![],
+        };
+        let (tokens, issue) = tokenize(sql);
+        let mut statements = tokens
+            .split(|t| t.is_symbol(";"))
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<&[Token]>>();
+        if let Some(issue) = issue {
+            // the statement the lexer stopped in is incomplete
+            if !tokens.last().is_some_and(|t| t.is_symbol(";")) {
+                statements.pop();
+            }
+            import.issues.push(issue);
+        }
+
+        for statement in statements {
+            if let Err(issue) = import.import_statement(statement) {
+                import.issues.push(issue);
+            }
+        }
+        import.add_foreign_keys();
+        import.add_sequences();
+
+        import
+            .issues
+            .sort_by_key(|i| (i.get_line(), i.get_column()));
+        ImportResult::new(import.schema, import.issues)
+    }
+}
+
+/// A sequence, which is imported as part of the column owning it.
+struct Sequence {
+    name: String,
+    /// Table and column owning the sequence.
+    owner: Option<(String, String)>,
+    position: (usize, usize),
+}
+
+enum ConstraintKind<'a> {
+    PrimaryKey(Vec<String>),
+    Unique(Vec<String>),
+    ForeignKey(ForeignKey),
+    Check(&'a [Token]),
+}
+
+/// A constraint declared apart from the columns, in `CREATE TABLE` or `ALTER TABLE ... ADD`.
+struct TableConstraint<'a> {
+    name: Option<String>,
+    kind: ConstraintKind<'a>,
+    position: (usize, usize),
+}
+
+/// State of a single import.
+struct Import<'a> {
+    engine: &'a PostgresEngine,
+    sql: &'a str,
+    /// Whether the script is a dump, whose statements not affecting the schema are skipped.
+    dump: bool,
+    schema: Schema,
+    issues: Vec<ImportIssue>,
+    foreign_keys: Vec<(ForeignKey, (usize, usize))>,
+    sequences: Vec<Sequence>,
+}
+
+impl<'a> Import<'a> {
+    fn import_statement(&mut self, tokens: &'a [Token]) -> Result<(), ImportIssue> {
+        if self.dump && is_dump_boilerplate(tokens) {
+            return Ok(());
+        }
+        let mut cursor = Cursor::new(self.sql, tokens);
+        if cursor.accept_keyword("CREATE") {
+            let unique = cursor.accept_keyword("UNIQUE");
+            if cursor.accept_keyword("INDEX") {
+                return self.create_index(&mut cursor, unique);
+            }
+            if !unique {
+                cursor.accept_keyword("UNLOGGED");
+                if cursor.accept_keyword("TABLE") {
+                    return self.create_table(&mut cursor);
+                }
+                if cursor.accept_keyword("TYPE") {
+                    return self.create_type(&mut cursor);
+                }
+                if cursor.accept_keyword("SEQUENCE") {
+                    return self.create_sequence(&mut cursor);
+                }
+            }
+        } else if cursor.accept_keywords(&["ALTER", "TABLE"]) {
+            return self.alter_table(&mut cursor);
+        } else if cursor.accept_keywords(&["ALTER", "SEQUENCE"]) {
+            return self.alter_sequence(&mut cursor);
+        }
+        Err(unsupported_statement(tokens))
+    }
+
+    fn create_table(&mut self, cursor: &mut Cursor<'a>) -> Result<(), ImportIssue> {
+        cursor.accept_keywords(&["IF", "NOT", "EXISTS"]);
+        let position = cursor.position();
+        let table_name = cursor.object_name()?;
+        let elements = cursor.list()?;
+        if !cursor.is_at_end() {
+            self.issues
+                .push(cursor.error("Table options are not imported"));
+        }
+        self.schema
+            .add_table(&table_name)
+            .map_err(|e| issue(position, &e))?;
+
+        let mut constraints = vec![];
+        for element in elements {
+            let mut cursor = Cursor::new(self.sql, element);
+            let result = if cursor.is_any_keyword(&TABLE_CONSTRAINT_KEYWORDS) {
+                self.table_constraint(&mut cursor, &table_name)
+                    .map(|c| constraints.push(c))
+            } else {
+                self.column(&mut cursor, &table_name)
+            };
+            if let Err(issue) = result {
+                self.issues.push(issue);
+            }
+        }
+        // table constraints may name columns defined after them
+        for constraint in constraints {
+            if let Err(issue) = self.add_constraint(&table_name, constraint) {
+                self.issues.push(issue);
+            }
+        }
+        Ok(())
+    }
+
+    fn alter_table(&mut self, cursor: &mut Cursor<'a>) -> Result<(), ImportIssue> {
+        cursor.accept_keywords(&["IF", "EXISTS"]);
+        cursor.accept_keyword("ONLY");
+        let position = cursor.position();
+        let table_name = cursor.object_name()?;
+        self.schema
+            .get_table(&table_name)
+            .map_err(|e| issue(position, &e))?;
+
+        for action in cursor.split_rest()? {
+            let mut cursor = Cursor::new(self.sql, action);
+            let result = if cursor.accept_keyword("ADD") {
+                if cursor.is_any_keyword(&TABLE_CONSTRAINT_KEYWORDS) {
+                    self.table_constraint(&mut cursor, &table_name)
+                        .and_then(|c| self.add_constraint(&table_name, c))
+                } else {
+                    cursor.accept_keyword("COLUMN");
+                    cursor.accept_keywords(&["IF", "NOT", "EXISTS"]);
+                    self.column(&mut cursor, &table_name)
+                }
+            } else if cursor.accept_keyword("ALTER") {
+                cursor.accept_keyword("COLUMN");
+                self.alter_column(&mut cursor, &table_name)
+            } else {
+                Err(cursor.error(&format!(
+                    "Unsupported ALTER TABLE action {}",
+                    action[0].get_text().to_uppercase()
+                )))
+            };
+            if let Err(issue) = result {
+                self.issues.push(issue);
+            }
+        }
+        Ok(())
+    }
+
+    fn alter_column(
+        &mut self,
+        cursor: &mut Cursor<'a>,
+        table_name: &str,
+    ) -> Result<(), ImportIssue> {
+        let position = cursor.position();
+        let column_name = cursor.identifier()?;
+        let mut default = None;
+        let mut nullable = None;
+        if cursor.accept_keywords(&["SET", "DEFAULT"]) {
+            default = Some(Some(self.default_value(cursor)?));
+        } else if cursor.accept_keywords(&["DROP", "DEFAULT"]) {
+            default = Some(None);
+        } else if cursor.accept_keywords(&["SET", "NOT", "NULL"]) {
+            nullable = Some(false);
+        } else if cursor.accept_keywords(&["DROP", "NOT", "NULL"]) {
+            nullable = Some(true);
+        } else if cursor.accept_keywords(&["ADD", "GENERATED"]) {
+            identity(cursor, position)?;
+        } else {
+            return Err(cursor.error("Unsupported ALTER COLUMN action"));
+        }
+        cursor.expect_end()?;
+
+        let column = self
+            .schema
+            .get_table_mut(table_name)
+            .map_err(|e| issue(position, &e))?
+            .get_columns_mut()
+            .iter_mut()
+            .find(|c| c.get_name() == column_name)
+            .ok_or(issue(
+                position,
+                &format!(
+                    "Column {} doesn't exist in table {}",
+                    column_name, table_name
+                ),
+            ))?;
+        if let Some(default) = default {
+            *column.get_default_mut() = default;
+        }
+        if let Some(nullable) = nullable {
+            *column.is_nullable_mut() = nullable;
+        }
+        Ok(())
+    }
+
+    fn create_sequence(&mut self, cursor: &mut Cursor<'a>) -> Result<(), ImportIssue> {
+        cursor.accept_keywords(&["IF", "NOT", "EXISTS"]);
+        let position = cursor.start_position();
+        let name = cursor.object_name()?;
+        if self.sequences.iter().any(|s| s.name == name) {
+            return Err(issue(
+                position,
+                &format!("Sequence {} already exists", name),
+            ));
+        }
+        // options such as START WITH don't matter for the schema, except for the owner
+        let mut owner = None;
+        while !cursor.is_at_end() {
+            if cursor.accept_keywords(&["OWNED", "BY"]) {
+                owner = sequence_owner(cursor)?;
+            } else {
+                cursor.next();
+            }
+        }
+        self.sequences.push(Sequence {
+            name,
+            owner,
+            position,
+        });
+        Ok(())
+    }
+
+    fn alter_sequence(&mut self, cursor: &mut Cursor<'a>) -> Result<(), ImportIssue> {
+        cursor.accept_keywords(&["IF", "EXISTS"]);
+        let position = cursor.position();
+        let name = cursor.object_name()?;
+        cursor.expect_keywords(&["OWNED", "BY"])?;
+        let owner = sequence_owner(cursor)?;
+        cursor.expect_end()?;
+        let sequence = self
+            .sequences
+            .iter_mut()
+            .find(|s| s.name == name)
+            .ok_or(issue(position, &format!("Sequence {} doesn't exist", name)))?;
+        sequence.owner = owner;
+        Ok(())
+    }
+
+    fn create_index(&mut self, cursor: &mut Cursor<'a>, unique: bool) -> Result<(), ImportIssue> {
+        let position = cursor.start_position();
+        cursor.accept_keyword("CONCURRENTLY");
+        cursor.accept_keywords(&["IF", "NOT", "EXISTS"]);
+        if cursor.is_keyword("ON") {
+            return Err(cursor.error("Indexes without a name are not supported"));
+        }
+        let index_name = cursor.identifier()?;
+        cursor.expect_keywords(&["ON"])?;
+        cursor.accept_keyword("ONLY");
+        let table_position = cursor.position();
+        let table_name = cursor.object_name()?;
+
+        let mut index = Index::new(&index_name, &[]);
+        index.set_unique(unique);
+        if cursor.accept_keyword("USING") {
+            let method_position = cursor.position();
+            index.set_method(match cursor.identifier()?.as_str() {
+                "btree" => IndexMethod::BTree,
+                "hash" => IndexMethod::Hash,
+                "gin" => IndexMethod::Gin,
+                "gist" => IndexMethod::Gist,
+                "brin" => IndexMethod::Brin,
+                method => {
+                    return Err(issue(
+                        method_position,
+                        &format!("Unsupported index method {}", method),
+                    ))
+                }
+            });
+        }
+        for element in cursor.list()? {
+            let column = self.index_column(element)?;
+            index.get_columns_mut().push(column);
+        }
+        if cursor.accept_keyword("INCLUDE") {
+            index.set_include(&as_strs(&cursor.identifier_list()?));
+        }
+        if cursor.is_keyword("NULLS") {
+            return Err(cursor.error("NULLS [NOT] DISTINCT is not supported"));
+        }
+        if cursor.accept_keyword("WITH") {
+            cursor.group()?;
+        }
+        if cursor.accept_keyword("TABLESPACE") {
+            cursor.identifier()?;
+        }
+        if cursor.accept_keyword("WHERE") {
+            let predicate = cursor.rest();
+            index.set_predicate(Some(source_text(self.sql, predicate)));
+        }
+        cursor.expect_end()?;
+
+        self.schema
+            .get_table_mut(&table_name)
+            .map_err(|e| issue(table_position, &e))?
+            .add_index(index)
+            .map_err(|e| issue(position, &e))?;
+        Ok(())
+    }
+
+    fn index_column(&self, tokens: &'a [Token]) -> Result<IndexColumn, ImportIssue> {
+        let mut cursor = Cursor::new(self.sql, tokens);
+        if tokens.iter().take(2).any(|t| t.is_symbol("(")) {
+            return Err(cursor.error("Indexes on expressions are not supported"));
+        }
+        let mut column = IndexColumn::new(&cursor.identifier()?);
+        if cursor.accept_keyword("COLLATE") {
+            cursor.object_name()?;
+        }
+        if !cursor.is_at_end() && !cursor.is_any_keyword(&["ASC", "DESC", "NULLS"]) {
+            return Err(cursor.error("Operator classes are not supported"));
+        }
+        if cursor.accept_keyword("DESC") {
+            column.set_order(SortOrder::Desc);
+        } else {
+            cursor.accept_keyword("ASC");
+        }
+        if cursor.accept_keywords(&["NULLS", "FIRST"]) {
+            column.set_nulls(Some(NullsOrder::First));
+        } else if cursor.accept_keywords(&["NULLS", "LAST"]) {
+            column.set_nulls(Some(NullsOrder::Last));
+        }
+        cursor.expect_end()?;
+        Ok(column)
+    }
+
+    fn create_type(&mut self, cursor: &mut Cursor<'a>) -> Result<(), ImportIssue> {
+        let position = cursor.start_position();
+        let enum_name = cursor.object_name()?;
+        if !cursor.accept_keywords(&["AS", "ENUM"]) {
+            return Err(issue(position, "Only enum types are supported"));
+        }
+        let mut labels = vec![];
+        for element in cursor.list()? {
+            match element {
+                [label] if label.get_kind() == &TokenKind::String => labels.push(label.get_text()),
+                _ => return Err(Cursor::new(self.sql, element).error("Expected a label")),
+            }
+        }
+        cursor.expect_end()?;
+        self.schema
+            .add_enum(EnumType::new(&enum_name, &labels))
+            .map_err(|e| issue(position, &e))?;
+        Ok(())
+    }
+
+    fn column(&mut self, cursor: &mut Cursor<'a>, table_name: &str) -> Result<(), ImportIssue> {
+        let position = cursor.position();
+        let column_name = cursor.identifier()?;
+        let type_position = cursor.position();
+        let type_tokens = cursor.take_until(&COLUMN_CONSTRAINT_KEYWORDS);
+        let data_type = self.data_type(type_tokens).ok_or_else(|| {
+            issue(
+                type_position,
+                &format!(
+                    "Unsupported data type {} of column {}",
+                    source_text(self.sql, type_tokens),
+                    column_name
+                ),
+            )
+        })?;
+
+        let mut column = Column::new(&column_name, data_type, false, false, true);
+        let mut foreign_keys = vec![];
+        while !cursor.is_at_end() {
+            let constraint_position = cursor.position();
+            let constraint_name = match cursor.accept_keyword("CONSTRAINT") {
+                true => Some(cursor.identifier()?),
+                false => None,
+            };
+            if cursor.accept_keywords(&["NOT", "NULL"]) {
+                *column.is_nullable_mut() = false;
+            } else if cursor.accept_keyword("NULL") {
+                *column.is_nullable_mut() = true;
+            } else if cursor.accept_keyword("DEFAULT") {
+                *column.get_default_mut() = Some(self.default_value(cursor)?);
+            } else if cursor.accept_keywords(&["PRIMARY", "KEY"]) {
+                *column.is_primary_key_mut() = true;
+                *column.is_nullable_mut() = false;
+            } else if cursor.accept_keyword("UNIQUE") {
+                *column.is_unique_mut() = true;
+            } else if cursor.accept_keyword("CHECK") {
+                let expression = cursor.group()?;
+                cursor.accept_keywords(&["NO", "INHERIT"]);
+                let check_name = constraint_name
+                    .unwrap_or_else(|| format!("{}_{}_check", table_name, column_name));
+                column
+                    .add_check(CheckConstraint::new(
+                        &check_name,
+                        source_text(self.sql, expression),
+                    ))
+                    .map_err(|e| issue(constraint_position, &e))?;
+            } else if cursor.accept_keyword("REFERENCES") {
+                let foreign_key = self.references(cursor, table_name, vec![column_name.clone()])?;
+                foreign_keys.push((foreign_key, constraint_position));
+            } else if cursor.accept_keyword("COLLATE") {
+                cursor.object_name()?;
+            } else if cursor.accept_keyword("GENERATED") {
+                identity(cursor, constraint_position)?;
+            } else {
+                return Err(cursor.error("Unsupported column constraint"));
+            }
+        }
+
+        let table = self
+            .schema
+            .get_table_mut(table_name)
+            .map_err(|e| issue(position, &e))?;
+        if table.get_column(&column_name).is_ok() {
+            return Err(issue(
+                position,
+                &format!(
+                    "Column {} already exists in table {}",
+                    column_name, table_name
+                ),
+            ));
+        }
+        table.add_column(column).map_err(|e| issue(position, &e))?;
+        self.foreign_keys.extend(foreign_keys);
+        Ok(())
+    }
+
+    /// Maps the tokens of a column type to a data type, looking up enums without their schema.
+    fn data_type(&self, tokens: &[Token]) -> Option<DataType> {
+        if let [element @ .., open, close] = tokens {
+            if open.is_symbol("[") && close.is_symbol("]") {
+                return Some(DataType::Array(Box::new(self.data_type(element)?)));
+            }
+        }
+        let is_name = tokens.len() % 2 == 1
+            && tokens.iter().enumerate().all(|(i, t)| match i % 2 {
+                0 => t.get_identifier().is_some(),
+                _ => t.is_symbol("."),
+            });
+        if is_name {
+            let name = tokens.last()?.get_identifier()?;
+            if self.schema.get_enum(&name).is_ok() {
+                return Some(DataType::Enum(name));
+            }
+        }
+        if tokens.is_empty() {
+            return None;
+        }
+        self.engine.get_data_type(source_text(self.sql, tokens))
+    }
+
+    fn default_value(&self, cursor: &mut Cursor<'a>) -> Result<DefaultValue, ImportIssue> {
+        let start = cursor.index;
+        if cursor.next().is_none() {
+            return Err(cursor.error("Expected a default value"));
+        }
+        cursor.take_until(&COLUMN_CONSTRAINT_KEYWORDS);
+        Ok(default_from_tokens(
+            self.sql,
+            &cursor.tokens[start..cursor.index],
+        ))
+    }
+
+    /// Reads the part of a foreign key following `REFERENCES`.
+    fn references(
+        &self,
+        cursor: &mut Cursor<'a>,
+        table_name: &str,
+        columns: Vec<String>,
+    ) -> Result<ForeignKey, ImportIssue> {
+        let referenced_table = cursor.object_name()?;
+        let referenced_columns = match cursor.is_symbol("(") {
+            true => cursor.identifier_list()?,
+            false => vec![],
+        };
+        let mut foreign_key = ForeignKey {
+            table: table_name.to_string(),
+            columns,
+            referenced_table,
+            referenced_columns,
+            on_delete: ReferentialAction::default(),
+            on_update: ReferentialAction::default(),
+            deferrable: false,
+            initially_deferred: false,
+        };
+        loop {
+            if cursor.accept_keywords(&["ON", "DELETE"]) {
+                foreign_key.on_delete = referential_action(cursor)?;
+            } else if cursor.accept_keywords(&["ON", "UPDATE"]) {
+                foreign_key.on_update = referential_action(cursor)?;
+            } else if cursor.accept_keyword("MATCH") {
+                cursor.identifier()?;
+            } else if cursor.accept_keywords(&["NOT", "DEFERRABLE"]) {
+                foreign_key.deferrable = false;
+            } else if cursor.accept_keyword("DEFERRABLE") {
+                foreign_key.deferrable = true;
+            } else if cursor.accept_keywords(&["INITIALLY", "DEFERRED"]) {
+                foreign_key.initially_deferred = true;
+            } else if cursor.accept_keywords(&["INITIALLY", "IMMEDIATE"]) {
+                foreign_key.initially_deferred = false;
+            } else if !cursor.accept_keywords(&["NOT", "VALID"]) {
+                return Ok(foreign_key);
+            }
+        }
+    }
+
+    fn table_constraint(
+        &self,
+        cursor: &mut Cursor<'a>,
+        table_name: &str,
+    ) -> Result<TableConstraint<'a>, ImportIssue> {
+        let position = cursor.position();
+        let name = match cursor.accept_keyword("CONSTRAINT") {
+            true => Some(cursor.identifier()?),
+            false => None,
+        };
+        let kind = if cursor.accept_keywords(&["PRIMARY", "KEY"]) {
+            ConstraintKind::PrimaryKey(cursor.identifier_list()?)
+        } else if cursor.accept_keyword("UNIQUE") {
+            ConstraintKind::Unique(cursor.identifier_list()?)
+        } else if cursor.accept_keywords(&["FOREIGN", "KEY"]) {
+            let columns = cursor.identifier_list()?;
+            cursor.expect_keywords(&["REFERENCES"])?;
+            ConstraintKind::ForeignKey(self.references(cursor, table_name, columns)?)
+        } else if cursor.accept_keyword("CHECK") {
+            let expression = cursor.group()?;
+            cursor.accept_keywords(&["NO", "INHERIT"]);
+            ConstraintKind::Check(expression)
+        } else {
+            return Err(cursor.error("Unsupported table constraint"));
+        };
+        cursor.accept_keywords(&["NOT", "VALID"]);
+        cursor.expect_end()?;
+        Ok(TableConstraint {
+            name,
+            kind,
+            position,
+        })
+    }
+
+    fn add_constraint(
+        &mut self,
+        table_name: &str,
+        constraint: TableConstraint<'a>,
+    ) -> Result<(), ImportIssue> {
+        let position = constraint.position;
+        let table = self
+            .schema
+            .get_table_mut(table_name)
+            .map_err(|e| issue(position, &e))?;
+        let result = match constraint.kind {
+            ConstraintKind::PrimaryKey(columns) => table
+                .set_primary_key(Some(primary_key(
+                    table_name,
+                    constraint.name.as_deref(),
+                    &columns,
+                )))
+                .map(|_| {
+                    for column in table.get_columns_mut() {
+                        if columns.iter().any(|c| c == column.get_name()) {
+                            *column.is_nullable_mut() = false;
+                        }
+                    }
+                }),
+            ConstraintKind::Unique(columns) => {
+                add_unique_constraint(table, constraint.name.as_deref(), &columns)
+            }
+            ConstraintKind::ForeignKey(foreign_key) => {
+                self.foreign_keys.push((foreign_key, position));
+                Ok(())
+            }
+            ConstraintKind::Check(expression) => {
+                // a check spanning several columns is kept with the first of them
+                let column_name = expression
+                    .iter()
+                    .filter_map(|t| t.get_identifier())
+                    .find(|i| table.get_column(i).is_ok());
+                match column_name {
+                    Some(column_name) => {
+                        let name = constraint
+                            .name
+                            .unwrap_or(format!("{}_{}_check", table_name, column_name));
+                        let column = table
+                            .get_columns_mut()
+                            .iter_mut()
+                            .find(|c| c.get_name() == column_name)
+                            .unwrap();
+                        column
+                            .add_check(CheckConstraint::new(
+                                &name,
+                                source_text(self.sql, expression),
+                            ))
+                            .map(|_| ())
+                    }
+                    None => Err(format!(
+                        "Check constraint of table {} doesn't refer to any of its columns",
+                        table_name
+                    )),
+                }
+            }
+        };
+        result.map_err(|e| issue(position, &e))
+    }
+
+    fn add_foreign_keys(&mut self) {
+        for (foreign_key, position) in std::mem::take(&mut self.foreign_keys) {
+            if let Err(message) = add_foreign_key(&mut self.schema, &foreign_key) {
+                self.issues.push(issue(position, &message));
+            }
+        }
+    }
+
+    /// Drops the default drawing from a sequence owned by its column, which is how `SERIAL`
+    /// columns are dumped, since the schema has no sequences. Every sequence is reported.
+    fn add_sequences(&mut self) {
+        for sequence in std::mem::take(&mut self.sequences) {
+            let column = sequence.owner.as_ref().and_then(|(table, column)| {
+                self.schema
+                    .get_table_mut(table)
+                    .ok()?
+                    .get_columns_mut()
+                    .iter_mut()
+                    .find(|c| c.get_name() == column)
+            });
+            match column {
+                Some(column)
+                    if column
+                        .get_default()
+                        .and_then(nextval_sequence)
+                        .is_some_and(|s| s == sequence.name) =>
+                {
+                    *column.get_default_mut() = None;
+                    let (table, column) = sequence.owner.as_ref().unwrap();
+                    self.issues.push(issue(
+                        sequence.position,
+                        &format!(
+                            "Sequence {} of column {}.{} is not imported, so the column no longer generates its values",
+                            sequence.name, table, column
+                        ),
+                    ));
+                }
+                _ => self.issues.push(issue(
+                    sequence.position,
+                    &format!(
+                        "Sequence {} is not imported, only sequences of serial columns are",
+                        sequence.name
+                    ),
+                )),
+            }
+        }
+    }
+}
+
+/// Reads the tokens of a statement, or of an element of a list, from left to right.
+struct Cursor<'a> {
+    sql: &'a str,
+    tokens: &'a [Token],
+    index: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(sql: &'a str, tokens: &'a [Token]) -> Self {
+        Self {
+            sql,
+            tokens,
+            index: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.peek()?;
+        self.index += 1;
+        Some(token)
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.index >= self.tokens.len()
+    }
+
+    /// Position of the next token, or of the last one at the end.
+    fn position(&self) -> (usize, usize) {
+        self.peek()
+            .or(self.tokens.last())
+            .map_or((1, 1), |t| (t.get_line(), t.get_column()))
+    }
+
+    fn start_position(&self) -> (usize, usize) {
+        self.tokens
+            .first()
+            .map_or((1, 1), |t| (t.get_line(), t.get_column()))
+    }
+
+    fn error(&self, message: &str) -> ImportIssue {
+        issue(self.position(), message)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        self.peek().is_some_and(|t| t.is_keyword(keyword))
+    }
+
+    fn is_any_keyword(&self, keywords: &[&str]) -> bool {
+        keywords.iter().any(|k| self.is_keyword(k))
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        self.peek().is_some_and(|t| t.is_symbol(symbol))
+    }
+
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        self.accept_keywords(&[keyword])
+    }
+
+    /// Skips a sequence of keywords if all of them follow, in order.
+    fn accept_keywords(&mut self, keywords: &[&str]) -> bool {
+        let found = keywords.iter().enumerate().all(|(i, k)| {
+            self.tokens
+                .get(self.index + i)
+                .is_some_and(|t| t.is_keyword(k))
+        });
+        if found {
+            self.index += keywords.len();
+        }
+        found
+    }
+
+    fn expect_keywords(&mut self, keywords: &[&str]) -> Result<(), ImportIssue> {
+        match self.accept_keywords(keywords) {
+            true => Ok(()),
+            false => Err(self.error(&format!("Expected {}", keywords.join(" ")))),
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), ImportIssue> {
+        match self.peek() {
+            Some(token) => Err(self.error(&format!("Unexpected {}", token.get_text()))),
+            None => Ok(()),
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, ImportIssue> {
+        match self.peek().and_then(|t| t.get_identifier()) {
+            Some(identifier) => {
+                self.index += 1;
+                Ok(identifier)
+            }
+            None => Err(self.error("Expected an identifier")),
+        }
+    }
+
+    /// Reads a name which may be qualified by a schema and returns it without the schema.
+    fn object_name(&mut self) -> Result<String, ImportIssue> {
+        let mut name = self.identifier()?;
+        while self.is_symbol(".") {
+            self.index += 1;
+            name = self.identifier()?;
+        }
+        Ok(name)
+    }
+
+    /// Reads a parenthesized group and returns the tokens inside the parentheses.
+    fn group(&mut self) -> Result<&'a [Token], ImportIssue> {
+        if !self.is_symbol("(") {
+            return Err(self.error("Expected ("));
+        }
+        let start = self.index + 1;
+        let mut depth = 0;
+        while let Some(token) = self.next() {
+            if token.is_symbol("(") {
+                depth += 1;
+            } else if token.is_symbol(")") {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(&self.tokens[start..self.index - 1]);
+                }
+            }
+        }
+        Err(self.error("Expected )"))
+    }
+
+    /// Reads a parenthesized, comma separated list and returns the tokens of its elements.
+    fn list(&mut self) -> Result<Vec<&'a [Token]>, ImportIssue> {
+        match self.group()? {
+            [] => Ok(vec![]),
+            group => Cursor::new(self.sql, group).split_rest(),
+        }
+    }
+
+    fn identifier_list(&mut self) -> Result<Vec<String>, ImportIssue> {
+        self.list()?
+            .into_iter()
+            .map(|element| {
+                let mut cursor = Cursor::new(self.sql, element);
+                let identifier = cursor.identifier()?;
+                cursor.expect_end()?;
+                Ok(identifier)
+            })
+            .collect()
+    }
+
+    /// Splits the remaining tokens at the commas outside parentheses.
+    fn split_rest(&mut self) -> Result<Vec<&'a [Token]>, ImportIssue> {
+        let mut elements = vec![];
+        let mut depth = 0;
+        let mut start = self.index;
+        while let Some(token) = self.peek() {
+            if token.is_symbol("(") {
+                depth += 1;
+            } else if token.is_symbol(")") {
+                depth -= 1;
+            } else if token.is_symbol(",") && depth == 0 {
+                if start == self.index {
+                    return Err(self.error("Expected an element before ,"));
+                }
+                elements.push(&self.tokens[start..self.index]);
+                start = self.index + 1;
+            }
+            self.index += 1;
+        }
+        if start == self.index {
+            return Err(self.error("Expected an element"));
+        }
+        elements.push(&self.tokens[start..]);
+        Ok(elements)
+    }
+
+    /// Reads the tokens up to the end or one of the keywords outside parentheses.
+    fn take_until(&mut self, keywords: &[&str]) -> &'a [Token] {
+        let start = self.index;
+        let mut depth = 0;
+        while let Some(token) = self.peek() {
+            if depth == 0 && keywords.iter().any(|k| token.is_keyword(k)) {
+                break;
+            }
+            if token.is_symbol("(") {
+                depth += 1;
+            } else if token.is_symbol(")") {
+                depth -= 1;
+            }
+            self.index += 1;
+        }
+        &self.tokens[start..self.index]
+    }
+
+    fn rest(&mut self) -> &'a [Token] {
+        let rest = &self.tokens[self.index..];
+        self.index = self.tokens.len();
+        rest
+    }
+}
+
+/// Parses the SQL of a column default, e.g. as a database reports it from its catalog.
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) fn parse_default(expression: &str) -> DefaultValue {
+    let (tokens, _) = tokenize(expression);
+    default_from_tokens(expression, &tokens)
+}
+
+/// Maps literals, also when cast to a type, to their value and anything else to an expression.
+fn default_from_tokens(sql: &str, tokens: &[Token]) -> DefaultValue {
+    // a literal cast to a type, e.g. `'active'::status`
+    let (literal, cast) = match tokens.iter().position(|t| t.is_symbol("::")) {
+        Some(cast) if tokens[cast + 1..].iter().all(is_type_token) => {
+            (&tokens[..cast], tokens.get(cast + 1))
+        }
+        _ => (tokens, None),
+    };
+    let numeric_cast = cast.is_some_and(|t| {
+        NUMERIC_TYPES
+            .iter()
+            .any(|n| t.get_text().eq_ignore_ascii_case(n))
+    });
+    let number = match literal {
+        [number] if number.get_kind() == &TokenKind::Number => number.get_text().to_string(),
+        [sign, number] if sign.is_symbol("-") && number.get_kind() == &TokenKind::Number => {
+            format!("-{}", number.get_text())
+        }
+        // PostgreSQL writes negative numbers as strings, e.g. `'-1'::integer`
+        [number] if numeric_cast && number.get_kind() == &TokenKind::String => {
+            number.get_text().to_string()
+        }
+        _ => String::new(),
+    };
+    if let Ok(value) = number.parse::<i64>() {
+        return DefaultValue::Integer(value);
+    }
+    if let Ok(value) = number.parse::<f64>() {
+        return DefaultValue::Float(value);
+    }
+    match literal {
+        [value] if value.is_keyword("NULL") => DefaultValue::Null,
+        [value] if value.is_keyword("TRUE") => DefaultValue::Boolean(true),
+        [value] if value.is_keyword("FALSE") => DefaultValue::Boolean(false),
+        [value] if value.get_kind() == &TokenKind::String => {
+            DefaultValue::String(value.get_text().to_string())
+        }
+        _ => DefaultValue::Expression(source_text(sql, tokens).to_string()),
+    }
+}
+
+/// Reads the part of an identity column following `GENERATED`. Identity columns are
+/// imported as plain integer columns.
+fn identity(cursor: &mut Cursor, position: (usize, usize)) -> Result<(), ImportIssue> {
+    if !cursor.accept_keyword("ALWAYS") {
+        cursor.expect_keywords(&["BY", "DEFAULT"])?;
+    }
+    if !cursor.accept_keywords(&["AS", "IDENTITY"]) {
+        return Err(issue(position, "Generated columns are not supported"));
+    }
+    if cursor.is_symbol("(") {
+        cursor.group()?;
+    }
+    Ok(())
+}
+
+/// Reads the `table.column` following `OWNED BY`, or `NONE`.
+fn sequence_owner(cursor: &mut Cursor) -> Result<Option<(String, String)>, ImportIssue> {
+    if cursor.accept_keyword("NONE") {
+        return Ok(None);
+    }
+    let mut names = vec![cursor.identifier()?];
+    while cursor.is_symbol(".") {
+        cursor.next();
+        names.push(cursor.identifier()?);
+    }
+    match &names[..] {
+        [.., table, column] => Ok(Some((table.clone(), column.clone()))),
+        _ => Err(cursor.error("Expected a column")),
+    }
+}
+
+/// Name of the sequence a `nextval('sequence'::regclass)` default draws from, without its schema.
+pub(crate) fn nextval_sequence(default: &DefaultValue) -> Option<String> {
+    let DefaultValue::Expression(expression) = default else {
+        return None;
+    };
+    let (tokens, _) = tokenize(expression);
+    let (function, open, name, close) = match &tokens[..] {
+        [function, open, name, cast, class, close]
+            if cast.is_symbol("::") && class.is_keyword("regclass") =>
+        {
+            (function, open, name, close)
+        }
+        [function, open, name, close] => (function, open, name, close),
+        _ => return None,
+    };
+    if !function.is_keyword("nextval")
+        || !open.is_symbol("(")
+        || !close.is_symbol(")")
+        || name.get_kind() != &TokenKind::String
+    {
+        return None;
+    }
+    // the name is text, which quotes mixed case parts itself
+    let (name, _) = tokenize(name.get_text());
+    name.last()?.get_identifier()
+}
+
+fn referential_action(cursor: &mut Cursor) -> Result<ReferentialAction, ImportIssue> {
+    if cursor.accept_keyword("CASCADE") {
+        Ok(ReferentialAction::Cascade)
+    } else if cursor.accept_keywords(&["SET", "NULL"]) {
+        Ok(ReferentialAction::SetNull)
+    } else if cursor.accept_keywords(&["SET", "DEFAULT"]) {
+        Ok(ReferentialAction::SetDefault)
+    } else if cursor.accept_keyword("RESTRICT") {
+        Ok(ReferentialAction::Restrict)
+    } else if cursor.accept_keywords(&["NO", "ACTION"]) {
+        Ok(ReferentialAction::NoAction)
+    } else {
+        Err(cursor.error("Expected a referential action"))
+    }
+}
+
+/// Whether a token can be part of the type name of a cast.
+fn is_type_token(token: &Token) -> bool {
+    matches!(
+        token.get_kind(),
+        TokenKind::Word | TokenKind::QuotedIdentifier | TokenKind::Number
+    ) || [".", "(", ")", "[", "]", ","]
+        .iter()
+        .any(|s| token.is_symbol(s))
+}
+
+/// Reports a statement by its leading keywords, e.g. `CREATE VIEW`.
+fn unsupported_statement(tokens: &[Token]) -> ImportIssue {
+    // scripts usually write keywords in upper case and names in lower case
+    let keywords = tokens
+        .iter()
+        .take(4)
+        .enumerate()
+        .take_while(|(i, t)| {
+            t.get_kind() == &TokenKind::Word
+                && (*i == 0 || t.get_text() == t.get_text().to_uppercase())
+        })
+        .map(|(_, t)| t.get_text().to_uppercase())
+        .collect::<Vec<String>>();
+    ImportIssue::new(
+        tokens[0].get_line(),
+        tokens[0].get_column(),
+        &format!("Unsupported statement {}", keywords.join(" ")),
+    )
+}
+
+/// Source text from the first to the last of the tokens.
+fn source_text<'a>(sql: &'a str, tokens: &[Token]) -> &'a str {
+    match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) => &sql[first.get_start()..last.get_end()],
+        _ => "",
+    }
+}
+
+fn issue(position: (usize, usize), message: &str) -> ImportIssue {
+    ImportIssue::new(position.0, position.1, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::migration::schema_diff::SchemaDiff;
+    use crate::schema::constraint::PrimaryKey;
+    use crate::schema::relationship::RelationshipType;
+
+    use super::*;
+
+    const SCRIPT: &str = r#"
+CREATE TYPE public.order_status AS ENUM ('pending', 'paid', 'shipped');
+
+CREATE TABLE IF NOT EXISTS public.users (
+    id SERIAL PRIMARY KEY,
+    email character varying(255) NOT NULL UNIQUE,
+    "display_name" text,
+    active boolean DEFAULT true NOT NULL,
+    created_at timestamp(3) with time zone DEFAULT now() NOT NULL
+);
+
+CREATE TABLE orders (
+    id bigint GENERATED ALWAYS AS IDENTITY,
+    user_id integer NOT NULL REFERENCES users ON DELETE CASCADE,
+    status order_status DEFAULT 'pending'::order_status NOT NULL,
+    total numeric(10, 2) DEFAULT 0 CONSTRAINT orders_total_positive CHECK (total >= 0),
+    tags text[],
+    CONSTRAINT orders_pkey PRIMARY KEY (id)
+);
+
+CREATE TABLE profiles (
+    user_id integer PRIMARY KEY,
+    bio text DEFAULT NULL
+);
+
+CREATE TABLE order_items (
+    order_id bigint NOT NULL,
+    product_id integer NOT NULL,
+    position integer NOT NULL,
+    UNIQUE (order_id, position),
+    CHECK (position > 0)
+);
+
+ALTER TABLE ONLY order_items
+    ADD CONSTRAINT order_items_order_id_fkey FOREIGN KEY (order_id)
+        REFERENCES orders (id) DEFERRABLE INITIALLY DEFERRED;
+ALTER TABLE profiles ADD FOREIGN KEY (user_id) REFERENCES users(id);
+
+CREATE UNIQUE INDEX orders_user_id_status_idx ON ONLY public.orders USING btree (user_id, status DESC NULLS LAST) INCLUDE (total) WHERE (status <> 'shipped'::order_status);
+CREATE INDEX orders_tags_idx ON orders USING gin (tags);
+"#;
+
+    #[test]
+    fn test_parse() {
+        let result = DdlParser::default().parse(SCRIPT);
+        assert!(result.is_complete(), "{:?}", result.get_issues());
+        let schema = result.get_schema();
+        schema.validate().unwrap();
+
+        assert_eq!(
+            schema.get_enum("order_status").unwrap().get_labels(),
+            ["pending", "paid", "shipped"]
+        );
+
+        let users = schema.get_table("users").unwrap();
+        let id = users.get_column("id").unwrap();
+        assert!(id.is_primary_key() && !id.is_nullable());
+        let email = users.get_column("email").unwrap();
+        assert_eq!(email.get_data_type(), DataType::Varchar(255));
+        assert!(email.is_unique() && !email.is_nullable());
+        assert!(users.get_column("display_name").unwrap().is_nullable());
+        assert_eq!(
+            users.get_column("active").unwrap().get_default(),
+            Some(&DefaultValue::Boolean(true))
+        );
+        let created_at = users.get_column("created_at").unwrap();
+        assert_eq!(
+            created_at.get_data_type(),
+            DataType::Timestamp {
+                precision: Some(3),
+                with_time_zone: true
+            }
+        );
+        assert_eq!(
+            created_at.get_default(),
+            Some(&DefaultValue::Expression("now()".to_string()))
+        );
+
+        let orders = schema.get_table("orders").unwrap();
+        assert_eq!(orders.get_primary_key(), Some(&PrimaryKey::new(&["id"])));
+        assert_eq!(
+            orders.get_column("id").unwrap().get_data_type(),
+            DataType::BigInt
+        );
+        let status = orders.get_column("status").unwrap();
+        assert_eq!(
+            status.get_data_type(),
+            DataType::Enum("order_status".to_string())
+        );
+        assert_eq!(
+            status.get_default(),
+            Some(&DefaultValue::String("pending".to_string()))
+        );
+        let total = orders.get_column("total").unwrap();
+        assert_eq!(total.get_default(), Some(&DefaultValue::Integer(0)));
+        assert_eq!(
+            total.get_checks(),
+            [CheckConstraint::new("orders_total_positive", "total >= 0")]
+        );
+        assert_eq!(
+            orders.get_column("tags").unwrap().get_data_type(),
+            DataType::Array(Box::new(DataType::String))
+        );
+
+        let index = orders.get_index("orders_user_id_status_idx").unwrap();
+        assert!(index.is_unique());
+        assert_eq!(index.get_columns()[1].get_order(), SortOrder::Desc);
+        assert_eq!(index.get_columns()[1].get_nulls(), Some(NullsOrder::Last));
+        assert_eq!(index.get_include(), ["total"]);
+        assert_eq!(
+            index.get_predicate(),
+            Some("(status <> 'shipped'::order_status)")
+        );
+        assert_eq!(
+            orders.get_index("orders_tags_idx").unwrap().get_method(),
+            IndexMethod::Gin
+        );
+
+        let order_items = schema.get_table("order_items").unwrap();
+        assert!(order_items
+            .get_index("order_items_order_id_position_key")
+            .unwrap()
+            .is_unique());
+        assert_eq!(
+            order_items.get_column("position").unwrap().get_checks(),
+            [CheckConstraint::new(
+                "order_items_position_check",
+                "position > 0"
+            )]
+        );
+
+        let orders_user = schema
+            .get_relationship_with_code("users_id_orders_user_id")
+            .unwrap();
+        assert_eq!(
+            orders_user.get_relationship_type(),
+            RelationshipType::OneToMany
+        );
+        assert_eq!(orders_user.get_on_delete(), ReferentialAction::Cascade);
+        assert!(orders.get_column("user_id").unwrap().is_foreign_key());
+        let items_order = schema
+            .get_relationship_with_code("orders_id_order_items_order_id")
+            .unwrap();
+        assert!(items_order.is_deferrable() && items_order.is_initially_deferred());
+        assert_eq!(
+            schema
+                .get_relationship_with_code("users_id_profiles_user_id")
+                .unwrap()
+                .get_relationship_type(),
+            RelationshipType::OneToOne
+        );
+    }
+
+    #[test]
+    fn test_generated_sql_imports_the_same_schema() {
+        let schema = DdlParser::default().parse(SCRIPT).into_schema();
+        let sql = PostgresEngine::default()
+            .generate_migration_plan(&mut schema.clone())
+            .unwrap()
+            .get_sql();
+
+        let result = DdlParser::default().parse(&sql);
+        assert!(result.is_complete(), "{:?}", result.get_issues());
+        assert!(SchemaDiff::new(&schema, result.get_schema())
+            .get_changes()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_issues() {
+        let sql = r#"CREATE TABLE users (id integer PRIMARY KEY, name text);
+CREATE VIEW active_users AS SELECT * FROM users;
+CREATE FUNCTION touch() RETURNS trigger AS $$
+BEGIN NEW.updated_at = now(); RETURN NEW; END;
+$$ LANGUAGE plpgsql;
+CREATE TABLE posts (
+    id integer PRIMARY KEY,
+    author_id integer REFERENCES authors (id),
+    location point,
+    title text NOT NULL
+);
+CREATE INDEX posts_title_idx ON posts (lower(title));
+ALTER TABLE posts OWNER TO admin;
+CREATE TYPE mood AS (happy boolean);
+CREATE TABLE comments (body text DEFAULT 'unterminated);
+"#;
+        let result = DdlParser::default().parse(sql);
+        let issues = result
+            .get_issues()
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            issues,
+            [
+                "line 2, column 1: Unsupported statement CREATE VIEW",
+                "line 3, column 1: Unsupported statement CREATE FUNCTION",
+                "line 8, column 23: Table with name authors doesn't exist",
+                "line 9, column 14: Unsupported data type point of column location",
+                "line 12, column 40: Indexes on expressions are not supported",
+                "line 13, column 19: Unsupported ALTER TABLE action OWNER",
+                "line 14, column 1: Only enum types are supported",
+                "line 15, column 42: Unterminated string",
+            ]
+        );
+
+        let schema = result.get_schema();
+        assert_eq!(schema.get_tables().len(), 2);
+        let posts = schema.get_table("posts").unwrap();
+        assert_eq!(
+            posts
+                .get_columns()
+                .iter()
+                .map(|c| c.get_name())
+                .collect::<Vec<&str>>(),
+            ["id", "author_id", "title"]
+        );
+        assert!(!posts.get_column("author_id").unwrap().is_foreign_key());
+    }
+}
@@ -0,0 +1,394 @@
+use super::ImportIssue;
+
+/// Kind of a lexical token of SQL source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A keyword or an unquoted identifier.
+    Word,
+    /// A `"quoted"` identifier, kept with its case.
+    QuotedIdentifier,
+    /// A `'string'`, `E'string'` or `$$dollar-quoted$$` literal.
+    String,
+    Number,
+    /// Punctuation or an operator; `::` is the only one longer than a character.
+    Symbol,
+}
+
+/// A token together with its position in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    kind: TokenKind,
+    text: String,
+    start: usize,
+    end: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Token {
+    pub fn get_kind(&self) -> &TokenKind {
+        &self.kind
+    }
+
+    /// Getter for `text`: the contents of a string or quoted identifier, the source text otherwise
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    /// Byte offset of the first character of the token
+    pub fn get_start(&self) -> usize {
+        self.start
+    }
+
+    /// Byte offset just past the last character of the token
+    pub fn get_end(&self) -> usize {
+        self.end
+    }
+
+    /// 1-based line of the first character of the token
+    pub fn get_line(&self) -> usize {
+        self.line
+    }
+
+    /// 1-based column of the first character of the token, counted in characters
+    pub fn get_column(&self) -> usize {
+        self.column
+    }
+
+    /// Whether the token is the given keyword, ignoring case.
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(keyword)
+    }
+
+    pub fn is_symbol(&self, symbol: &str) -> bool {
+        self.kind == TokenKind::Symbol && self.text == symbol
+    }
+
+    /// The name the token stands for: unquoted identifiers fold to lower case.
+    pub fn get_identifier(&self) -> Option<String> {
+        match self.kind {
+            TokenKind::Word => Some(self.text.to_lowercase()),
+            TokenKind::QuotedIdentifier => Some(self.text.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Splits SQL source into tokens, skipping whitespace and comments.
+///
+/// # Returns
+/// - The tokens, up to an unterminated string, quoted identifier or comment if there is one,
+///   and an issue at the position where it starts.
+pub fn tokenize(sql: &str) -> (Vec<Token>, Option<ImportIssue>) {
+    let mut tokens = Vec::new();
+    let issue = tokenize_into(sql, &mut tokens).err();
+    (tokens, issue)
+}
+
+fn tokenize_into(sql: &str, tokens: &mut Vec<Token>) -> Result<(), ImportIssue> {
+    let chars = sql.char_indices().collect::<Vec<(usize, char)>>();
+    let offset = |index: usize| chars.get(index).map(|c| c.0).unwrap_or(sql.len());
+    let char_at = |index: usize| chars.get(index).map(|c| c.1);
+    let mut index = 0;
+    let mut line = 1;
+    let mut line_start = 0;
+
+    while let Some(c) = char_at(index) {
+        let start = index;
+        let position = (line, start - line_start + 1);
+        let unterminated = |what: &str| {
+            ImportIssue::new(position.0, position.1, &format!("Unterminated {}", what))
+        };
+        let kind = match c {
+            '\n' => {
+                index += 1;
+                line += 1;
+                line_start = index;
+                continue;
+            }
+            c if c.is_whitespace() => {
+                index += 1;
+                continue;
+            }
+            '-' if char_at(index + 1) == Some('-') => {
+                while char_at(index).is_some_and(|c| c != '\n') {
+                    index += 1;
+                }
+                continue;
+            }
+            '/' if char_at(index + 1) == Some('*') => {
+                let mut depth = 0;
+                loop {
+                    match (char_at(index), char_at(index + 1)) {
+                        (Some('/'), Some('*')) => {
+                            depth += 1;
+                            index += 2;
+                        }
+                        (Some('*'), Some('/')) => {
+                            depth -= 1;
+                            index += 2;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        (Some('\n'), _) => {
+                            index += 1;
+                            line += 1;
+                            line_start = index;
+                        }
+                        (Some(_), _) => index += 1,
+                        (None, _) => return Err(unterminated("comment")),
+                    }
+                }
+                continue;
+            }
+            '\'' => {
+                index = scan_quoted(&chars, index, '\'', false).ok_or(unterminated("string"))?;
+                TokenKind::String
+            }
+            'e' | 'E' if char_at(index + 1) == Some('\'') => {
+                index = scan_quoted(&chars, index + 1, '\'', true).ok_or(unterminated("string"))?;
+                TokenKind::String
+            }
+            '"' => {
+                index = scan_quoted(&chars, index, '"', false)
+                    .ok_or(unterminated("quoted identifier"))?;
+                TokenKind::QuotedIdentifier
+            }
+            '$' if dollar_tag(&chars, index).is_some() => {
+                let tag = dollar_tag(&chars, index).unwrap();
+                index += tag.chars().count();
+                loop {
+                    if char_at(index).is_none() {
+                        return Err(unterminated("dollar-quoted string"));
+                    }
+                    if dollar_tag(&chars, index).as_deref() == Some(tag.as_str()) {
+                        index += tag.chars().count();
+                        break;
+                    }
+                    index += 1;
+                }
+                TokenKind::String
+            }
+            c if c.is_ascii_digit()
+                || (c == '.' && char_at(index + 1).is_some_and(|c| c.is_ascii_digit())) =>
+            {
+                while char_at(index).is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    index += 1;
+                }
+                if char_at(index).is_some_and(|c| c == 'e' || c == 'E')
+                    && char_at(index + 1)
+                        .is_some_and(|c| c.is_ascii_digit() || c == '-' || c == '+')
+                {
+                    index += 2;
+                    while char_at(index).is_some_and(|c| c.is_ascii_digit()) {
+                        index += 1;
+                    }
+                }
+                TokenKind::Number
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                while char_at(index).is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$') {
+                    index += 1;
+                }
+                TokenKind::Word
+            }
+            ':' if char_at(index + 1) == Some(':') => {
+                index += 2;
+                TokenKind::Symbol
+            }
+            _ => {
+                index += 1;
+                TokenKind::Symbol
+            }
+        };
+
+        let source = &sql[offset(start)..offset(index)];
+        let text = match kind {
+            TokenKind::String if source.starts_with('$') => {
+                let tag_length = source[1..].find('$').unwrap() + 2;
+                source[tag_length..source.len() - tag_length].to_string()
+            }
+            TokenKind::String if source.starts_with(['e', 'E']) => {
+                unescape_escape_string(&source[2..source.len() - 1])
+            }
+            TokenKind::String | TokenKind::QuotedIdentifier => {
+                unescape(&source[1..source.len() - 1], source.chars().next().unwrap())
+            }
+            _ => source.to_string(),
+        };
+        // literals may span lines
+        for c in chars[start..index].iter().map(|c| c.1) {
+            if c == '\n' {
+                line += 1;
+            }
+        }
+        if let Some(last_newline) = chars[start..index].iter().rposition(|c| c.1 == '\n') {
+            line_start = start + last_newline + 1;
+        }
+        tokens.push(Token {
+            kind,
+            text,
+            start: offset(start),
+            end: offset(index),
+            line: position.0,
+            column: position.1,
+        });
+    }
+    Ok(())
+}
+
+/// Returns the index past the closing quote of a literal starting at `index`, where a doubled
+/// quote stands for the quote itself. Backslashes only escape characters in `E'...'` strings,
+/// as with `standard_conforming_strings` on.
+fn scan_quoted(
+    chars: &[(usize, char)],
+    mut index: usize,
+    quote: char,
+    escapes: bool,
+) -> Option<usize> {
+    index += 1;
+    loop {
+        match (chars.get(index)?.1, chars.get(index + 1).map(|c| c.1)) {
+            (c, Some(next)) if c == quote && next == quote => index += 2,
+            (c, _) if c == quote => return Some(index + 1),
+            ('\\', Some(_)) if escapes => index += 2,
+            _ => index += 1,
+        }
+    }
+}
+
+/// Returns the `$tag$` opening a dollar-quoted string at `index`, if there is one.
+fn dollar_tag(chars: &[(usize, char)], index: usize) -> Option<String> {
+    if chars.get(index)?.1 != '$' {
+        return None;
+    }
+    let mut tag = String::from("$");
+    for (_, c) in chars.get(index + 1..)? {
+        match c {
+            '$' => {
+                tag.push('$');
+                return Some(tag);
+            }
+            c if c.is_alphanumeric() || *c == '_' => tag.push(*c),
+            _ => return None,
+        }
+    }
+    None
+}
+
+fn unescape(text: &str, quote: char) -> String {
+    text.replace(&format!("{}{}", quote, quote), &quote.to_string())
+}
+
+/// Decodes the contents of an `E'...'` string: C-like backslash escapes, octal `\ooo`,
+/// hexadecimal `\xhh` and Unicode `\uXXXX` or `\UXXXXXXXX` escapes, and doubled quotes.
+/// A backslash before any other character stands for that character.
+fn unescape_escape_string(text: &str) -> String {
+    let mut chars = text.chars().peekable();
+    let mut unescaped = String::new();
+    // takes up to `max` digits of the radix, returning the character they encode
+    let code = |chars: &mut std::iter::Peekable<std::str::Chars>, radix: u32, max: usize| {
+        let mut digits = String::new();
+        while digits.len() < max && chars.peek().is_some_and(|c| c.is_digit(radix)) {
+            digits.push(chars.next().unwrap());
+        }
+        u32::from_str_radix(&digits, radix)
+            .ok()
+            .and_then(char::from_u32)
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                unescaped.push('\'');
+            }
+            '\\' => match chars.next() {
+                Some('b') => unescaped.push('\u{8}'),
+                Some('f') => unescaped.push('\u{c}'),
+                Some('n') => unescaped.push('\n'),
+                Some('r') => unescaped.push('\r'),
+                Some('t') => unescaped.push('\t'),
+                Some(c @ '0'..='7') => {
+                    let mut digits = c.to_string();
+                    while digits.len() < 3 && chars.peek().is_some_and(|c| c.is_digit(8)) {
+                        digits.push(chars.next().unwrap());
+                    }
+                    unescaped.extend(
+                        u32::from_str_radix(&digits, 8)
+                            .ok()
+                            .and_then(char::from_u32),
+                    );
+                }
+                Some('x') if chars.peek().is_some_and(|c| c.is_ascii_hexdigit()) => {
+                    unescaped.extend(code(&mut chars, 16, 2));
+                }
+                Some('u') => unescaped.extend(code(&mut chars, 16, 4)),
+                Some('U') => unescaped.extend(code(&mut chars, 16, 8)),
+                Some(c) => unescaped.push(c),
+                None => unescaped.push('\\'),
+            },
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let sql =
+            "-- users\nCREATE TABLE \"Users\" (\n  /* key */ id int DEFAULT 'it''s'::text\n);";
+        let (tokens, issue) = tokenize(sql);
+        assert_eq!(issue, None);
+        let texts = tokens.iter().map(|t| t.get_text()).collect::<Vec<&str>>();
+        assert_eq!(
+            texts,
+            [
+                "CREATE", "TABLE", "Users", "(", "id", "int", "DEFAULT", "it's", "::", "text", ")",
+                ";"
+            ]
+        );
+        assert!(tokens[0].is_keyword("create"));
+        assert_eq!(tokens[2].get_identifier().unwrap(), "Users");
+        assert_eq!(tokens[5].get_identifier().unwrap(), "int");
+        assert_eq!((tokens[4].get_line(), tokens[4].get_column()), (3, 13));
+        assert_eq!(&sql[tokens[7].get_start()..tokens[7].get_end()], "'it''s'");
+        assert_eq!((tokens[11].get_line(), tokens[11].get_column()), (4, 2));
+    }
+
+    #[test]
+    fn test_tokenize_literals() {
+        let (tokens, _) = tokenize("$body$ a; 'b' $body$ E'c' 1.5e3 $$x$$");
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(tokens[0].get_text(), " a; 'b' ");
+        assert_eq!(tokens[1].get_text(), "c");
+        assert_eq!(tokens[2].get_kind(), &TokenKind::Number);
+        assert_eq!(tokens[3].get_text(), "x");
+
+        // backslashes are plain characters in standard strings and escapes in E-strings
+        let (tokens, issue) =
+            tokenize(r"'C:\dir\' 'a''b' E'it\'s\n' e'\t\\\x41\101\u00e9''' E'\q'");
+        assert_eq!(issue, None);
+        let texts = tokens.iter().map(|t| t.get_text()).collect::<Vec<&str>>();
+        assert_eq!(texts, ["C:\\dir\\", "a'b", "it's\n", "\t\\AAé'", "q"]);
+        assert!(tokens.iter().all(|t| t.get_kind() == &TokenKind::String));
+
+        let (tokens, issue) = tokenize(r"'closed\' E'open\'");
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(
+            issue.unwrap().to_string(),
+            "line 1, column 11: Unterminated string"
+        );
+
+        let (tokens, issue) = tokenize("SELECT\n  'open");
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(
+            issue.unwrap().to_string(),
+            "line 2, column 3: Unterminated string"
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt;
+
+use crate::schema::constraint::PrimaryKey;
+use crate::schema::index::Index;
+use crate::schema::relationship::{ReferentialAction, Relationship, RelationshipType};
+use crate::schema::table::Table;
+use crate::schema::Schema;
+
+pub mod ddl_parser;
+pub mod lexer;
+mod pg_dump;
+#[cfg(feature = "postgres")]
+pub mod postgres_introspector;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_introspector;
+
+/// Part of an imported script or database which was skipped, with the position where it
+/// starts in a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportIssue {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl ImportIssue {
+    pub fn new(line: usize, column: usize, message: &str) -> Self {
+        Self {
+            line,
+            column,
+            message: message.to_string(),
+        }
+    }
+
+    /// An issue which has no position, such as a part of a database an introspector skipped.
+    /// Its line and column are 0.
+    pub fn without_position(message: &str) -> Self {
+        Self::new(0, 0, message)
+    }
+
+    /// Getter for `line`, counted from 1
+    pub fn get_line(&self) -> usize {
+        self.line
+    }
+
+    /// Getter for `column`, counted in characters from 1
+    pub fn get_column(&self) -> usize {
+        self.column
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ImportIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}", self.message);
+        }
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+/// Schema built from a script or a database, together with everything the import had to skip.
+#[derive(Debug, Clone)]
+pub struct ImportResult {
+    schema: Schema,
+    issues: Vec<ImportIssue>,
+}
+
+impl ImportResult {
+    pub fn new(schema: Schema, issues: Vec<ImportIssue>) -> Self {
+        Self { schema, issues }
+    }
+
+    pub fn get_schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    pub fn into_schema(self) -> Schema {
+        self.schema
+    }
+
+    /// Getter for `issues`, in the order of their position in the script
+    pub fn get_issues(&self) -> &[ImportIssue] {
+        &self.issues
+    }
+
+    /// Whether the whole script was imported.
+    pub fn is_complete(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// A foreign key read from a script or a database, added as a relationship once all tables exist.
+pub(crate) struct ForeignKey {
+    pub(crate) table: String,
+    pub(crate) columns: Vec<String>,
+    pub(crate) referenced_table: String,
+    /// Empty if the foreign key references the primary key.
+    pub(crate) referenced_columns: Vec<String>,
+    pub(crate) on_delete: ReferentialAction,
+    pub(crate) on_update: ReferentialAction,
+    pub(crate) deferrable: bool,
+    pub(crate) initially_deferred: bool,
+}
+
+/// Adds a foreign key as a one-to-one relationship if its columns are unique in the
+/// referencing table, or a one-to-many relationship otherwise.
+pub(crate) fn add_foreign_key(schema: &mut Schema, foreign_key: &ForeignKey) -> Result<(), String> {
+    let referenced_table = schema.get_table(&foreign_key.referenced_table)?;
+    let referenced_columns = match foreign_key.referenced_columns.is_empty() {
+        true => referenced_table
+            .get_primary_key()
+            .ok_or(format!(
+                "Table {} has no primary key to reference",
+                foreign_key.referenced_table
+            ))?
+            .get_columns()
+            .to_vec(),
+        false => foreign_key.referenced_columns.clone(),
+    };
+    let table = schema.get_table(&foreign_key.table)?;
+    let relationship_type = match table.is_unique_key(&foreign_key.columns) {
+        true => RelationshipType::OneToOne,
+        false => RelationshipType::OneToMany,
+    };
+
+    let mut relationship = Relationship::new_composite(
+        &foreign_key.referenced_table,
+        &as_strs(&referenced_columns),
+        &foreign_key.table,
+        &as_strs(&foreign_key.columns),
+        relationship_type,
+    );
+    relationship.set_on_delete(foreign_key.on_delete.clone());
+    relationship.set_on_update(foreign_key.on_update.clone());
+    relationship.set_deferrable(foreign_key.deferrable);
+    relationship.set_initially_deferred(foreign_key.initially_deferred);
+    schema.add_relation(relationship)?;
+
+    for column in schema.get_table_mut(&foreign_key.table)?.get_columns_mut() {
+        if foreign_key.columns.iter().any(|c| c == column.get_name()) {
+            *column.is_foreign_key_mut() = true;
+        }
+    }
+    Ok(())
+}
+
+/// Primary key of a table, leaving the name to the database if it is PostgreSQL's default.
+pub(crate) fn primary_key(table_name: &str, name: Option<&str>, columns: &[String]) -> PrimaryKey {
+    match name {
+        Some(name) if name != format!("{}_pkey", table_name) => {
+            PrimaryKey::with_name(name, &as_strs(columns))
+        }
+        _ => PrimaryKey::new(&as_strs(columns)),
+    }
+}
+
+/// Adds a unique constraint: a column marked unique if it is over a single column and has
+/// PostgreSQL's default name, a unique index otherwise.
+pub(crate) fn add_unique_constraint(
+    table: &mut Table,
+    name: Option<&str>,
+    columns: &[String],
+) -> Result<(), String> {
+    let default_name = format!("{}_{}_key", table.get_name(), columns.join("_"));
+    let name = name.unwrap_or(&default_name);
+    match columns {
+        [column_name] if name == default_name => {
+            let table_name = table.get_name().to_string();
+            table
+                .get_columns_mut()
+                .iter_mut()
+                .find(|c| c.get_name() == column_name)
+                .map(|c| *c.is_unique_mut() = true)
+                .ok_or(format!(
+                    "Column {} doesn't exist in table {}",
+                    column_name, table_name
+                ))
+        }
+        _ => {
+            let mut index = Index::new(name, &as_strs(columns));
+            index.set_unique(true);
+            table.add_index(index).map(|_| ())
+        }
+    }
+}
+
+pub(crate) fn as_strs(strings: &[String]) -> Vec<&str> {
+    strings.iter().map(String::as_str).collect()
+}
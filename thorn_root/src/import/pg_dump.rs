@@ -0,0 +1,478 @@
+use super::lexer::{tokenize, Token, TokenKind};
+
+/// Whether a statement of a dump only configures the session or sets properties the schema
+/// doesn't model: `SET`, `COMMENT ON`, privileges, owners, schemas and extensions.
+pub(crate) fn is_dump_boilerplate(tokens: &[Token]) -> bool {
+    let starts_with = |words: &[&str]| {
+        tokens.len() >= words.len()
+            && words.iter().zip(tokens).all(|(w, t)| {
+                matches!(t.get_kind(), TokenKind::Word | TokenKind::Symbol)
+                    && t.get_text().eq_ignore_ascii_case(w)
+            })
+    };
+    let owner_change = tokens.len() >= 3
+        && tokens[tokens.len() - 3].is_keyword("OWNER")
+        && tokens[tokens.len() - 2].is_keyword("TO");
+
+    ["SET", "COMMENT", "GRANT", "REVOKE"]
+        .iter()
+        .any(|k| starts_with(&[k]))
+        || starts_with(&["SELECT", "pg_catalog", ".", "set_config"])
+        || starts_with(&["ALTER", "DEFAULT", "PRIVILEGES"])
+        || starts_with(&["CREATE", "SCHEMA"])
+        || starts_with(&["CREATE", "EXTENSION"])
+        || (starts_with(&["ALTER"]) && owner_change)
+}
+
+/// Blanks the psql meta-commands of a dump, such as `\restrict`, which end at the end of their
+/// line instead of at a semicolon. Backslashes in strings, dollar-quoted bodies and comments
+/// don't start meta-commands. Positions in the script stay the same.
+pub(crate) fn without_meta_commands(dump: &str) -> String {
+    let mut dump = dump.to_string();
+    // the arguments of a meta-command aren't SQL, so the rest of the dump is tokenized again
+    // once they're blanked
+    while let Some(start) = tokenize(&dump)
+        .0
+        .iter()
+        .find(|t| t.is_symbol("\\"))
+        .map(|t| t.get_start())
+    {
+        let end = dump[start..]
+            .find('\n')
+            .map_or(dump.len(), |end| start + end);
+        let blank = " ".repeat(dump[start..end].chars().count());
+        dump.replace_range(start..end, &blank);
+    }
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::engine::DatabaseEngine;
+    use crate::database::oracle_engine::OracleEngine;
+    use crate::database::postgres_engine::PostgresEngine;
+    use crate::database::sqlite_engine::SqliteEngine;
+    use crate::import::ddl_parser::DdlParser;
+    use crate::migration::schema_diff::SchemaDiff;
+    use crate::schema::constraint::DefaultValue;
+    use crate::schema::data_type::DataType;
+
+    use super::*;
+
+    /// Output of `pg_dump --schema-only` for a database created from `DESIGN`.
+    const DUMP: &str = r#"--
+-- PostgreSQL database dump
+--
+
+\restrict 5KsB6CH9lcjl2sL797VqHqSGz6NxeAEmm0avbGuVelsTJvWykapnI9nZS6PKVw6
+
+-- Dumped from database version 15.18 (Debian 15.18-0+deb12u1)
+-- Dumped by pg_dump version 15.18 (Debian 15.18-0+deb12u1)
+
+SET statement_timeout = 0;
+SET lock_timeout = 0;
+SET idle_in_transaction_session_timeout = 0;
+SET client_encoding = 'SQL_ASCII';
+SET standard_conforming_strings = on;
+SELECT pg_catalog.set_config('search_path', '', false);
+SET check_function_bodies = false;
+SET xmloption = content;
+SET client_min_messages = warning;
+SET row_security = off;
+
+--
+-- Name: order_status; Type: TYPE; Schema: public; Owner: postgres
+--
+
+CREATE TYPE public.order_status AS ENUM (
+    'pending',
+    'paid',
+    'shipped'
+);
+
+
+ALTER TYPE public.order_status OWNER TO postgres;
+
+--
+-- Name: touch(); Type: FUNCTION; Schema: public; Owner: postgres
+--
+
+CREATE FUNCTION public.touch() RETURNS trigger
+    LANGUAGE plpgsql
+    AS $$ BEGIN RETURN NEW; END; $$;
+
+
+ALTER FUNCTION public.touch() OWNER TO postgres;
+
+SET default_tablespace = '';
+
+SET default_table_access_method = heap;
+
+--
+-- Name: order_items; Type: TABLE; Schema: public; Owner: postgres
+--
+
+CREATE TABLE public.order_items (
+    order_id bigint NOT NULL,
+    "position" integer NOT NULL,
+    quantity smallint DEFAULT 1 NOT NULL
+);
+
+
+ALTER TABLE public.order_items OWNER TO postgres;
+
+--
+-- Name: orders; Type: TABLE; Schema: public; Owner: postgres
+--
+
+CREATE TABLE public.orders (
+    id bigint NOT NULL,
+    user_id integer NOT NULL,
+    status public.order_status DEFAULT 'pending'::public.order_status NOT NULL,
+    total numeric(10,2) DEFAULT 0,
+    tags text[],
+    CONSTRAINT orders_total_check CHECK ((total >= (0)::numeric))
+);
+
+
+ALTER TABLE public.orders OWNER TO postgres;
+
+--
+-- Name: orders_id_seq; Type: SEQUENCE; Schema: public; Owner: postgres
+--
+
+ALTER TABLE public.orders ALTER COLUMN id ADD GENERATED ALWAYS AS IDENTITY (
+    SEQUENCE NAME public.orders_id_seq
+    START WITH 1
+    INCREMENT BY 1
+    NO MINVALUE
+    NO MAXVALUE
+    CACHE 1
+);
+
+
+--
+-- Name: paid_orders; Type: VIEW; Schema: public; Owner: postgres
+--
+
+CREATE VIEW public.paid_orders AS
+ SELECT orders.id,
+    orders.user_id,
+    orders.status,
+    orders.total,
+    orders.tags
+   FROM public.orders
+  WHERE (orders.status = 'paid'::public.order_status);
+
+
+ALTER TABLE public.paid_orders OWNER TO postgres;
+
+--
+-- Name: users; Type: TABLE; Schema: public; Owner: postgres
+--
+
+CREATE TABLE public.users (
+    id integer NOT NULL,
+    email character varying(255) NOT NULL,
+    "displayName" text,
+    active boolean DEFAULT true NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+ALTER TABLE public.users OWNER TO postgres;
+
+--
+-- Name: TABLE users; Type: COMMENT; Schema: public; Owner: postgres
+--
+
+COMMENT ON TABLE public.users IS 'Registered customers';
+
+
+--
+-- Name: COLUMN users.email; Type: COMMENT; Schema: public; Owner: postgres
+--
+
+COMMENT ON COLUMN public.users.email IS 'Login; must be unique';
+
+
+--
+-- Name: users_id_seq; Type: SEQUENCE; Schema: public; Owner: postgres
+--
+
+CREATE SEQUENCE public.users_id_seq
+    AS integer
+    START WITH 1
+    INCREMENT BY 1
+    NO MINVALUE
+    NO MAXVALUE
+    CACHE 1;
+
+
+ALTER TABLE public.users_id_seq OWNER TO postgres;
+
+--
+-- Name: users_id_seq; Type: SEQUENCE OWNED BY; Schema: public; Owner: postgres
+--
+
+ALTER SEQUENCE public.users_id_seq OWNED BY public.users.id;
+
+
+--
+-- Name: users id; Type: DEFAULT; Schema: public; Owner: postgres
+--
+
+ALTER TABLE ONLY public.users ALTER COLUMN id SET DEFAULT nextval('public.users_id_seq'::regclass);
+
+
+--
+-- Name: order_items order_items_pkey; Type: CONSTRAINT; Schema: public; Owner: postgres
+--
+
+ALTER TABLE ONLY public.order_items
+    ADD CONSTRAINT order_items_pkey PRIMARY KEY (order_id, "position");
+
+
+--
+-- Name: orders orders_pkey; Type: CONSTRAINT; Schema: public; Owner: postgres
+--
+
+ALTER TABLE ONLY public.orders
+    ADD CONSTRAINT orders_pkey PRIMARY KEY (id);
+
+
+--
+-- Name: users users_email_key; Type: CONSTRAINT; Schema: public; Owner: postgres
+--
+
+ALTER TABLE ONLY public.users
+    ADD CONSTRAINT users_email_key UNIQUE (email);
+
+
+--
+-- Name: users users_pkey; Type: CONSTRAINT; Schema: public; Owner: postgres
+--
+
+ALTER TABLE ONLY public.users
+    ADD CONSTRAINT users_pkey PRIMARY KEY (id);
+
+
+--
+-- Name: orders_tags_idx; Type: INDEX; Schema: public; Owner: postgres
+--
+
+CREATE INDEX orders_tags_idx ON public.orders USING gin (tags);
+
+
+--
+-- Name: orders_user_status_idx; Type: INDEX; Schema: public; Owner: postgres
+--
+
+CREATE UNIQUE INDEX orders_user_status_idx ON public.orders USING btree (user_id, status DESC NULLS LAST) WHERE (status <> 'shipped'::public.order_status);
+
+
+--
+-- Name: order_items order_items_order_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: postgres
+--
+
+ALTER TABLE ONLY public.order_items
+    ADD CONSTRAINT order_items_order_id_fkey FOREIGN KEY (order_id) REFERENCES public.orders(id) DEFERRABLE INITIALLY DEFERRED;
+
+
+--
+-- Name: orders orders_user_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: postgres
+--
+
+ALTER TABLE ONLY public.orders
+    ADD CONSTRAINT orders_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id) ON DELETE CASCADE;
+
+
+--
+-- PostgreSQL database dump complete
+--
+
+\unrestrict 5KsB6CH9lcjl2sL797VqHqSGz6NxeAEmm0avbGuVelsTJvWykapnI9nZS6PKVw6
+
+"#;
+
+    /// The design of the dumped database, as the database describes it.
+    const DESIGN: &str = r#"
+CREATE TYPE order_status AS ENUM ('pending', 'paid', 'shipped');
+CREATE TABLE users (
+    id SERIAL PRIMARY KEY,
+    email character varying(255) NOT NULL UNIQUE,
+    "displayName" text,
+    active boolean DEFAULT true NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+CREATE TABLE orders (
+    id bigint GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    user_id integer NOT NULL REFERENCES users ON DELETE CASCADE,
+    status order_status DEFAULT 'pending' NOT NULL,
+    total numeric(10,2) DEFAULT 0 CONSTRAINT orders_total_check CHECK ((total >= (0)::numeric)),
+    tags text[]
+);
+CREATE TABLE order_items (
+    order_id bigint NOT NULL REFERENCES orders DEFERRABLE INITIALLY DEFERRED,
+    "position" integer NOT NULL,
+    quantity smallint DEFAULT 1 NOT NULL,
+    PRIMARY KEY (order_id, "position")
+);
+CREATE UNIQUE INDEX orders_user_status_idx ON orders (user_id, status DESC NULLS LAST)
+    WHERE (status <> 'shipped'::public.order_status);
+CREATE INDEX orders_tags_idx ON orders USING gin (tags);
+"#;
+
+    #[test]
+    fn test_parse_pg_dump() {
+        let result = DdlParser::default().parse_pg_dump(DUMP);
+        let issues = result
+            .get_issues()
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            issues,
+            [
+                "line 38, column 1: Unsupported statement CREATE FUNCTION",
+                "line 96, column 1: Unsupported statement CREATE VIEW",
+                "line 141, column 1: Sequence users_id_seq of column users.id is not imported, so the column no longer generates its values",
+            ]
+        );
+
+        let schema = result.get_schema();
+        schema.validate().unwrap();
+        let users = schema.get_table("users").unwrap();
+        let id = users.get_column("id").unwrap();
+        assert!(id.is_primary_key());
+        assert_eq!(id.get_default(), None);
+        assert!(users.get_column("email").unwrap().is_unique());
+        assert!(users.get_column("displayName").is_ok());
+        assert_eq!(
+            schema
+                .get_table("orders")
+                .unwrap()
+                .get_column("status")
+                .unwrap()
+                .get_default(),
+            Some(&DefaultValue::String("pending".to_string()))
+        );
+
+        let design = DdlParser::default().parse(DESIGN);
+        assert!(design.is_complete(), "{:?}", design.get_issues());
+        assert!(SchemaDiff::new(design.get_schema(), schema)
+            .get_changes()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_quoted_names_round_trip() {
+        let dump = r#"
+CREATE TYPE public."Mood" AS ENUM ('ok', 'sad');
+CREATE TABLE public."User" (
+    id integer NOT NULL,
+    "order" character varying(20),
+    "displayName" text,
+    mood public."Mood"
+);
+CREATE TABLE public.orders (
+    id integer NOT NULL,
+    "userId" integer
+);
+ALTER TABLE ONLY public."User"
+    ADD CONSTRAINT "User_pkey" PRIMARY KEY (id);
+ALTER TABLE ONLY public.orders
+    ADD CONSTRAINT orders_pkey PRIMARY KEY (id);
+CREATE INDEX "User_order_idx" ON public."User" USING btree ("order");
+ALTER TABLE ONLY public.orders
+    ADD CONSTRAINT "orders_userId_fkey" FOREIGN KEY ("userId") REFERENCES public."User"(id);
+"#;
+        let result = DdlParser::default().parse_pg_dump(dump);
+        assert!(result.get_issues().is_empty());
+        let mut schema = result.get_schema().clone();
+
+        let sql = PostgresEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap()
+            .get_sql();
+        assert!(sql.contains(
+            "CREATE TABLE \"User\" (\n    \
+             id INTEGER PRIMARY KEY,\n    \
+             \"order\" VARCHAR(20),\n    \
+             \"displayName\" TEXT,\n    \
+             mood \"Mood\"\n);\n"
+        ));
+        assert!(sql.contains("CREATE INDEX \"User_order_idx\" ON \"User\" (\"order\");\n"));
+        let reimported = DdlParser::default().parse(&sql);
+        assert!(reimported.get_issues().is_empty());
+        assert!(SchemaDiff::new(&schema, reimported.get_schema()).is_empty());
+
+        let sql = SqliteEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap()
+            .get_sql();
+        assert!(sql.contains("REFERENCES \"User\" (id)"));
+        assert!(sql.contains("    \"order\" TEXT,\n"));
+        let sql = OracleEngine::default()
+            .generate_migration_plan(&mut schema)
+            .unwrap()
+            .get_sql();
+        assert!(sql.contains("CREATE TABLE \"User\" (\n"));
+        assert!(sql.contains("    \"userId\" NUMBER"));
+    }
+
+    #[test]
+    fn test_sequences() {
+        let dump = r#"
+CREATE TABLE public.invoices (
+    id integer NOT NULL,
+    number bigint NOT NULL
+);
+CREATE SEQUENCE public.invoice_numbers START WITH 1000;
+CREATE SEQUENCE public."Invoices_id_seq" AS integer;
+ALTER SEQUENCE public."Invoices_id_seq" OWNED BY public.invoices.id;
+ALTER TABLE ONLY public.invoices ALTER COLUMN id SET DEFAULT nextval('public."Invoices_id_seq"'::regclass);
+ALTER TABLE ONLY public.invoices ALTER COLUMN number SET DEFAULT nextval('public.invoice_numbers'::regclass);
+"#;
+        let result = DdlParser::default().parse_pg_dump(dump);
+        assert_eq!(result.get_issues().len(), 2);
+        assert_eq!(
+            result.get_issues()[0].to_string(),
+            "line 6, column 1: Sequence invoice_numbers is not imported, only sequences of serial columns are"
+        );
+        assert_eq!(
+            result.get_issues()[1].to_string(),
+            "line 7, column 1: Sequence Invoices_id_seq of column invoices.id is not imported, so the column no longer generates its values"
+        );
+        let invoices = result.get_schema().get_table("invoices").unwrap();
+        assert_eq!(invoices.get_column("id").unwrap().get_default(), None);
+        assert_eq!(
+            invoices.get_column("number").unwrap().get_default(),
+            Some(&DefaultValue::Expression(
+                "nextval('public.invoice_numbers'::regclass)".to_string()
+            ))
+        );
+        assert_eq!(
+            invoices.get_column("number").unwrap().get_data_type(),
+            DataType::BigInt
+        );
+    }
+
+    #[test]
+    fn test_without_meta_commands() {
+        let dump = "\\restrict abc\nSET x = 1;\n  \\unrestrict abc";
+        assert_eq!(
+            without_meta_commands(dump),
+            "             \nSET x = 1;\n                 "
+        );
+
+        let dump = "CREATE FUNCTION f() RETURNS text AS $$\n\\x\n$$ LANGUAGE sql;\n\
+                    COMMENT ON TABLE t IS '\n\\y';\n\
+                    \\connect - 'it''s\n\
+                    SET x = E'\\'\n\\z';";
+        assert_eq!(
+            without_meta_commands(dump),
+            dump.replace("\\connect - 'it''s", &" ".repeat(17))
+        );
+    }
+}
@@ -0,0 +1,570 @@
+use postgres::Client;
+
+use crate::database::engine::DatabaseEngine;
+use crate::database::postgres_engine::PostgresEngine;
+use crate::error::CoreError;
+use crate::schema::column::Column;
+use crate::schema::constraint::CheckConstraint;
+use crate::schema::data_type::DataType;
+use crate::schema::enum_type::EnumType;
+use crate::schema::index::{Index, IndexColumn, IndexMethod, NullsOrder, SortOrder};
+use crate::schema::relationship::ReferentialAction;
+use crate::schema::Schema;
+
+use super::ddl_parser::{nextval_sequence, parse_default};
+use super::lexer::tokenize;
+use super::{
+    add_foreign_key, add_unique_constraint, as_strs, primary_key, ForeignKey, ImportIssue,
+    ImportResult,
+};
+
+const ENUMS_QUERY: &str = "
+SELECT t.typname::text, array_agg(e.enumlabel::text ORDER BY e.enumsortorder)
+FROM pg_type t
+JOIN pg_enum e ON e.enumtypid = t.oid
+JOIN pg_namespace n ON n.oid = t.typnamespace
+WHERE n.nspname = $1
+GROUP BY t.typname
+ORDER BY t.typname";
+
+const TABLES_QUERY: &str = "
+SELECT c.relname::text
+FROM pg_class c
+JOIN pg_namespace n ON n.oid = c.relnamespace
+WHERE n.nspname = $1 AND c.relkind IN ('r', 'p') AND NOT c.relispartition
+ORDER BY c.relname";
+
+const COLUMNS_QUERY: &str = "
+SELECT c.relname::text, a.attname::text, format_type(a.atttypid, a.atttypmod), a.attnotnull,
+    pg_get_expr(d.adbin, d.adrelid), a.attgenerated::text,
+    t.typtype::text, t.typname::text, et.typtype::text, et.typname::text,
+    pg_get_serial_sequence(format('%I.%I', n.nspname, c.relname), a.attname) IS NOT NULL
+FROM pg_attribute a
+JOIN pg_class c ON c.oid = a.attrelid
+JOIN pg_namespace n ON n.oid = c.relnamespace
+JOIN pg_type t ON t.oid = a.atttypid
+LEFT JOIN pg_type et ON et.oid = t.typelem AND t.typcategory = 'A'
+LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+WHERE n.nspname = $1 AND c.relkind IN ('r', 'p') AND NOT c.relispartition
+    AND a.attnum > 0 AND NOT a.attisdropped
+ORDER BY c.relname, a.attnum";
+
+const CONSTRAINTS_QUERY: &str = "
+SELECT con.conname::text, con.contype::text, c.relname::text,
+    ARRAY(SELECT a.attname::text FROM unnest(con.conkey) WITH ORDINALITY k(attnum, position)
+        JOIN pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+        ORDER BY k.position),
+    fc.relname::text,
+    ARRAY(SELECT a.attname::text FROM unnest(con.confkey) WITH ORDINALITY k(attnum, position)
+        JOIN pg_attribute a ON a.attrelid = con.confrelid AND a.attnum = k.attnum
+        ORDER BY k.position),
+    con.confdeltype::text, con.confupdtype::text, con.condeferrable, con.condeferred,
+    pg_get_constraintdef(con.oid)
+FROM pg_constraint con
+JOIN pg_class c ON c.oid = con.conrelid
+JOIN pg_namespace n ON n.oid = c.relnamespace
+LEFT JOIN pg_class fc ON fc.oid = con.confrelid
+WHERE n.nspname = $1 AND con.contype IN ('p', 'u', 'f', 'c') AND NOT c.relispartition
+ORDER BY c.relname, con.conname";
+
+// indexes of primary keys and unique constraints are part of the constraints
+const INDEXES_QUERY: &str = "
+SELECT i.relname::text, t.relname::text, ix.indisunique, am.amname::text,
+    pg_get_expr(ix.indpred, ix.indrelid), ix.indnkeyatts,
+    ARRAY(SELECT coalesce(a.attname::text, '')
+        FROM unnest(ix.indkey::int2[]) WITH ORDINALITY k(attnum, position)
+        LEFT JOIN pg_attribute a ON a.attrelid = ix.indrelid AND a.attnum = k.attnum
+        ORDER BY k.position),
+    ARRAY(SELECT unnest(ix.indoption::int2[]))
+FROM pg_index ix
+JOIN pg_class i ON i.oid = ix.indexrelid
+JOIN pg_class t ON t.oid = ix.indrelid
+JOIN pg_namespace n ON n.oid = t.relnamespace
+JOIN pg_am am ON am.oid = i.relam
+WHERE n.nspname = $1 AND t.relkind IN ('r', 'p') AND NOT t.relispartition
+    AND NOT EXISTS (
+        SELECT 1 FROM pg_constraint con
+        WHERE con.conindid = ix.indexrelid AND con.contype IN ('p', 'u', 'x')
+    )
+ORDER BY t.relname, i.relname";
+
+/// Reads the schema of a live PostgreSQL database from its catalog.
+pub struct PostgresIntrospector {
+    engine: PostgresEngine,
+    schema_name: String,
+}
+
+impl Default for PostgresIntrospector {
+    /// Introspects the `public` schema with the default type mapping.
+    fn default() -> Self {
+        Self::new(PostgresEngine::default(), "public")
+    }
+}
+
+impl PostgresIntrospector {
+    /// Creates an introspector for a database schema, mapping native types back to data
+    /// types through the type mapping of the engine.
+    pub fn new(engine: PostgresEngine, schema_name: &str) -> Self {
+        Self {
+            engine,
+            schema_name: schema_name.to_string(),
+        }
+    }
+
+    pub fn get_schema_name(&self) -> &str {
+        &self.schema_name
+    }
+
+    /// Reconstructs the enums, tables, columns, keys, checks, indexes and relationships of
+    /// the database schema, named and flagged the way the DDL importer reads them.
+    ///
+    /// # Returns
+    /// - The schema together with an issue for everything the schema can't model and was
+    ///   skipped, such as a column type without a data type or an index on an expression, or
+    ///   a `DatabaseError` if querying the catalog fails.
+    pub fn introspect(&self, client: &mut Client) -> Result<ImportResult, CoreError> {
+        let mut schema = Schema::new();
+        let mut issues = vec![];
+        self.add_enums(client, &mut schema, &mut issues)?;
+        self.add_tables(client, &mut schema, &mut issues)?;
+        self.add_constraints(client, &mut schema, &mut issues)?;
+        self.add_indexes(client, &mut schema, &mut issues)?;
+        Ok(ImportResult::new(schema, issues))
+    }
+
+    fn add_enums(
+        &self,
+        client: &mut Client,
+        schema: &mut Schema,
+        issues: &mut Vec<ImportIssue>,
+    ) -> Result<(), CoreError> {
+        for row in client
+            .query(ENUMS_QUERY, &[&self.schema_name])
+            .map_err(database_error)?
+        {
+            let labels: Vec<String> = row.get(1);
+            if let Err(e) = schema.add_enum(EnumType::new(row.get(0), &as_strs(&labels))) {
+                issues.push(ImportIssue::without_position(&e));
+            }
+        }
+        Ok(())
+    }
+
+    fn add_tables(
+        &self,
+        client: &mut Client,
+        schema: &mut Schema,
+        issues: &mut Vec<ImportIssue>,
+    ) -> Result<(), CoreError> {
+        for row in client
+            .query(TABLES_QUERY, &[&self.schema_name])
+            .map_err(database_error)?
+        {
+            if let Err(e) = schema.add_table(row.get(0)) {
+                issues.push(ImportIssue::without_position(&e));
+            }
+        }
+
+        for row in client
+            .query(COLUMNS_QUERY, &[&self.schema_name])
+            .map_err(database_error)?
+        {
+            let table_name: String = row.get(0);
+            let column_name: String = row.get(1);
+            let db_type: String = row.get(2);
+            let unsupported = |what: &str| {
+                ImportIssue::without_position(&format!(
+                    "Column {} of table {} {}, so it is not imported",
+                    column_name, table_name, what
+                ))
+            };
+            if row.get::<_, String>(5) == "s" {
+                issues.push(unsupported("is generated, which is not supported"));
+                continue;
+            }
+
+            let data_type = match (row.get::<_, &str>(6), row.get::<_, Option<&str>>(8)) {
+                ("e", _) => DataType::Enum(row.get(7)),
+                (_, Some("e")) => DataType::Array(Box::new(DataType::Enum(row.get(9)))),
+                _ => match self.engine.get_data_type(&db_type) {
+                    Some(data_type) => data_type,
+                    None => {
+                        issues.push(unsupported(&format!(
+                            "has the type {}, which has no data type",
+                            db_type
+                        )));
+                        continue;
+                    }
+                },
+            };
+            let mut column = Column::new(
+                &column_name,
+                data_type,
+                false,
+                false,
+                !row.get::<_, bool>(3),
+            );
+            // the default of a serial column comes with its type
+            let serial: bool = row.get(10);
+            *column.get_default_mut() = row
+                .get::<_, Option<&str>>(4)
+                .map(parse_default)
+                .filter(|d| !(serial && nextval_sequence(d).is_some()));
+
+            if let Err(e) = schema
+                .get_table_mut(&table_name)
+                .and_then(|t| t.add_column(column).map(|_| ()))
+            {
+                issues.push(ImportIssue::without_position(&e));
+            }
+        }
+        Ok(())
+    }
+
+    fn add_constraints(
+        &self,
+        client: &mut Client,
+        schema: &mut Schema,
+        issues: &mut Vec<ImportIssue>,
+    ) -> Result<(), CoreError> {
+        let mut foreign_keys = vec![];
+        for row in client
+            .query(CONSTRAINTS_QUERY, &[&self.schema_name])
+            .map_err(database_error)?
+        {
+            let name: String = row.get(0);
+            let table_name: String = row.get(2);
+            let columns: Vec<String> = row.get(3);
+            let table = match schema.get_table_mut(&table_name) {
+                Ok(table) => table,
+                Err(e) => {
+                    issues.push(ImportIssue::without_position(&e));
+                    continue;
+                }
+            };
+            let result = match row.get::<_, &str>(1) {
+                "p" => table.set_primary_key(Some(primary_key(&table_name, Some(&name), &columns))),
+                "u" => add_unique_constraint(table, Some(&name), &columns),
+                "c" => {
+                    let expression = check_expression(row.get(10));
+                    // a check spanning several columns is kept with the first of them, as
+                    // the DDL importer does
+                    let (tokens, _) = tokenize(expression);
+                    let column_name = tokens
+                        .iter()
+                        .filter_map(|t| t.get_identifier())
+                        .find(|i| table.get_column(i).is_ok());
+                    let column = column_name.and_then(|column_name| {
+                        table
+                            .get_columns_mut()
+                            .iter_mut()
+                            .find(|c| c.get_name() == column_name)
+                    });
+                    match column {
+                        Some(column) => column
+                            .add_check(CheckConstraint::new(&name, expression))
+                            .map(|_| ()),
+                        None => Err(format!(
+                            "Check constraint {} of table {} doesn't refer to any of its columns",
+                            name, table_name
+                        )),
+                    }
+                }
+                _ => {
+                    foreign_keys.push((
+                        name.clone(),
+                        ForeignKey {
+                            table: table_name.clone(),
+                            columns,
+                            referenced_table: row.get(4),
+                            referenced_columns: row.get(5),
+                            on_delete: referential_action(row.get(6)),
+                            on_update: referential_action(row.get(7)),
+                            deferrable: row.get(8),
+                            initially_deferred: row.get(9),
+                        },
+                    ));
+                    Ok(())
+                }
+            };
+            if let Err(e) = result {
+                issues.push(ImportIssue::without_position(&format!(
+                    "Constraint {} of table {} is not imported: {}",
+                    name, table_name, e
+                )));
+            }
+        }
+
+        for (name, foreign_key) in foreign_keys {
+            if let Err(e) = add_foreign_key(schema, &foreign_key) {
+                issues.push(ImportIssue::without_position(&format!(
+                    "Constraint {} of table {} is not imported: {}",
+                    name, foreign_key.table, e
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn add_indexes(
+        &self,
+        client: &mut Client,
+        schema: &mut Schema,
+        issues: &mut Vec<ImportIssue>,
+    ) -> Result<(), CoreError> {
+        for row in client
+            .query(INDEXES_QUERY, &[&self.schema_name])
+            .map_err(database_error)?
+        {
+            let index_name: String = row.get(0);
+            let table_name: String = row.get(1);
+            let key_count = row.get::<_, i16>(5) as usize;
+            let columns: Vec<String> = row.get(6);
+            let options: Vec<i16> = row.get(7);
+            if columns.iter().any(|c| c.is_empty()) {
+                issues.push(ImportIssue::without_position(&format!(
+                    "Index {} of table {} is on an expression, which is not supported, so it is not imported",
+                    index_name, table_name
+                )));
+                continue;
+            }
+
+            let mut index = Index::new(&index_name, &[]);
+            index.set_unique(row.get(2));
+            index.set_method(match row.get::<_, &str>(3) {
+                "btree" => IndexMethod::BTree,
+                "hash" => IndexMethod::Hash,
+                "gin" => IndexMethod::Gin,
+                "gist" => IndexMethod::Gist,
+                "brin" => IndexMethod::Brin,
+                method => {
+                    issues.push(ImportIssue::without_position(&format!(
+                        "Index {} of table {} uses the method {}, which is not supported, so it is not imported",
+                        index_name, table_name, method
+                    )));
+                    continue;
+                }
+            });
+            index.set_predicate(row.get(4));
+            for (column, option) in columns.iter().zip(options).take(key_count) {
+                index.get_columns_mut().push(index_column(column, option));
+            }
+            index.set_include(&as_strs(&columns[key_count.min(columns.len())..]));
+
+            if let Err(e) = schema
+                .get_table_mut(&table_name)
+                .and_then(|t| t.add_index(index).map(|_| ()))
+            {
+                issues.push(ImportIssue::without_position(&format!(
+                    "Index {} of table {} is not imported: {}",
+                    index_name, table_name, e
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn database_error(error: postgres::Error) -> CoreError {
+    CoreError::DatabaseError(error.to_string())
+}
+
+/// Maps the action code of `pg_constraint.confdeltype` and `confupdtype`.
+fn referential_action(code: &str) -> ReferentialAction {
+    match code {
+        "c" => ReferentialAction::Cascade,
+        "n" => ReferentialAction::SetNull,
+        "d" => ReferentialAction::SetDefault,
+        "r" => ReferentialAction::Restrict,
+        _ => ReferentialAction::NoAction,
+    }
+}
+
+/// Returns the expression of a check constraint from its definition, e.g. `(a > 0)` from
+/// `CHECK ((a > 0)) NOT VALID`.
+fn check_expression(definition: &str) -> &str {
+    let definition = definition
+        .trim_end_matches(" NOT VALID")
+        .trim_end_matches(" NO INHERIT");
+    definition
+        .strip_prefix("CHECK (")
+        .and_then(|d| d.strip_suffix(')'))
+        .unwrap_or(definition)
+}
+
+/// Decodes an index column from the bits of `pg_index.indoption`: 1 for a descending
+/// column, 2 for NULLs sorted first. The order of NULLs is only kept when it isn't the
+/// default of the column's direction.
+fn index_column(name: &str, option: i16) -> IndexColumn {
+    let descending = option & 1 != 0;
+    let nulls_first = option & 2 != 0;
+    let mut column = IndexColumn::new(name);
+    if descending {
+        column.set_order(SortOrder::Desc);
+    }
+    if nulls_first != descending {
+        column.set_nulls(Some(match nulls_first {
+            true => NullsOrder::First,
+            false => NullsOrder::Last,
+        }));
+    }
+    column
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::import::ddl_parser::DdlParser;
+    use crate::migration::schema_diff::SchemaDiff;
+    use crate::schema::constraint::DefaultValue;
+    use crate::schema::relationship::RelationshipType;
+
+    use super::*;
+
+    #[test]
+    fn test_catalog_values() {
+        assert_eq!(
+            check_expression("CHECK ((total >= (0)::numeric)) NOT VALID"),
+            "(total >= (0)::numeric)"
+        );
+        assert_eq!(referential_action("c"), ReferentialAction::Cascade);
+        assert_eq!(referential_action("a"), ReferentialAction::NoAction);
+
+        let column = index_column("created_at", 3);
+        assert_eq!(column.get_order(), SortOrder::Desc);
+        assert_eq!(column.get_nulls(), None);
+        assert_eq!(
+            index_column("created_at", 1).get_nulls(),
+            Some(NullsOrder::Last)
+        );
+        assert_eq!(
+            index_column("created_at", 2).get_nulls(),
+            Some(NullsOrder::First)
+        );
+        assert_eq!(index_column("created_at", 0).get_nulls(), None);
+    }
+
+    /// Written the way PostgreSQL reports expressions, so the introspected schema is the same.
+    const DESIGN: &str = r#"
+CREATE TYPE order_status AS ENUM ('pending', 'paid', 'shipped');
+CREATE TABLE users (
+    id SERIAL PRIMARY KEY,
+    email character varying(255) NOT NULL UNIQUE,
+    "displayName" text,
+    active boolean DEFAULT true NOT NULL,
+    created_at timestamp(3) with time zone DEFAULT now() NOT NULL
+);
+CREATE TABLE profiles (
+    user_id integer PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    bio text DEFAULT 'Hello'::text
+);
+CREATE TABLE orders (
+    id bigint GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    user_id integer NOT NULL REFERENCES users ON UPDATE RESTRICT,
+    status order_status DEFAULT 'pending'::order_status NOT NULL,
+    previous_statuses order_status[],
+    total numeric(10,2) DEFAULT 0 CONSTRAINT orders_total_check CHECK ((total >= (0)::numeric)),
+    discount integer DEFAULT -1,
+    tags text[]
+);
+CREATE TABLE order_items (
+    order_id bigint NOT NULL REFERENCES orders DEFERRABLE INITIALLY DEFERRED,
+    "position" integer NOT NULL,
+    quantity smallint DEFAULT 1 NOT NULL,
+    CONSTRAINT order_items_key PRIMARY KEY (order_id, "position"),
+    CONSTRAINT order_items_quantity_key UNIQUE (order_id, quantity)
+);
+CREATE UNIQUE INDEX orders_user_status_idx ON orders (user_id, status DESC NULLS LAST)
+    INCLUDE (total) WHERE (status <> 'shipped'::order_status);
+CREATE INDEX orders_tags_idx ON orders USING gin (tags);
+"#;
+
+    #[test]
+    #[ignore = "needs a PostgreSQL server at THORN_ROOT_POSTGRES_URL"]
+    fn test_introspect() {
+        let url = std::env::var("THORN_ROOT_POSTGRES_URL")
+            .unwrap_or("host=localhost user=postgres".to_string());
+        let mut client = Client::connect(&url, postgres::NoTls).unwrap();
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS thorn_root_introspection CASCADE;
+                CREATE SCHEMA thorn_root_introspection;
+                SET search_path TO thorn_root_introspection;
+                {}",
+                DESIGN
+            ))
+            .unwrap();
+
+        let introspector =
+            PostgresIntrospector::new(PostgresEngine::default(), "thorn_root_introspection");
+        let result = introspector.introspect(&mut client).unwrap();
+        assert!(result.is_complete(), "{:?}", result.get_issues());
+        let schema = result.into_schema();
+
+        // what the schema can't model is skipped and reported
+        client
+            .batch_execute(
+                "CREATE TABLE points (id integer PRIMARY KEY, location point);
+                CREATE INDEX points_location_idx ON points USING gist (location);
+                CREATE INDEX users_lower_email_idx ON users (lower(email));",
+            )
+            .unwrap();
+        let result = introspector.introspect(&mut client).unwrap();
+        client
+            .batch_execute("DROP SCHEMA thorn_root_introspection CASCADE")
+            .unwrap();
+        let issues = result
+            .get_issues()
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            issues,
+            [
+                "Column location of table points has the type point, which has no data type, so it is not imported",
+                "Index points_location_idx of table points is not imported: Column location doesn't not exist in table points",
+                "Index users_lower_email_idx of table users is on an expression, which is not supported, so it is not imported",
+            ]
+        );
+        assert_eq!(
+            result
+                .get_schema()
+                .get_table("points")
+                .unwrap()
+                .get_columns()
+                .len(),
+            1
+        );
+
+        schema.validate().unwrap();
+        let users = schema.get_table("users").unwrap();
+        assert!(users.get_column("id").unwrap().is_primary_key());
+        assert_eq!(users.get_column("id").unwrap().get_default(), None);
+        assert!(users.get_column("email").unwrap().is_unique());
+        let orders = schema.get_table("orders").unwrap();
+        assert_eq!(
+            orders.get_column("discount").unwrap().get_default(),
+            Some(&DefaultValue::Integer(-1))
+        );
+        assert_eq!(
+            orders
+                .get_column("previous_statuses")
+                .unwrap()
+                .get_data_type(),
+            DataType::Array(Box::new(DataType::Enum("order_status".to_string())))
+        );
+        assert!(orders.get_column("user_id").unwrap().is_foreign_key());
+        assert_eq!(
+            schema
+                .get_relationship_with_code("users_id_profiles_user_id")
+                .unwrap()
+                .get_relationship_type(),
+            RelationshipType::OneToOne
+        );
+
+        let design = DdlParser::default().parse(DESIGN);
+        assert!(design.is_complete(), "{:?}", design.get_issues());
+        assert!(SchemaDiff::new(design.get_schema(), &schema)
+            .get_changes()
+            .is_empty());
+    }
+}
@@ -0,0 +1,484 @@
+use rusqlite::{Connection, Row};
+
+use crate::database::engine::DatabaseEngine;
+use crate::database::sqlite_engine::SqliteEngine;
+use crate::error::CoreError;
+use crate::schema::column::Column;
+use crate::schema::constraint::PrimaryKey;
+use crate::schema::index::{Index, IndexColumn, SortOrder};
+use crate::schema::relationship::ReferentialAction;
+use crate::schema::table::Table;
+use crate::schema::Schema;
+
+use super::ddl_parser::{parse_default, DdlParser};
+use super::lexer::tokenize;
+use super::{
+    add_foreign_key, add_unique_constraint, as_strs, ForeignKey, ImportIssue, ImportResult,
+};
+
+const TABLES_QUERY: &str = "
+SELECT name, sql FROM sqlite_master
+WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+ORDER BY name";
+
+const COLUMNS_QUERY: &str = "
+SELECT name, type, \"notnull\", dflt_value, pk FROM pragma_table_info(?1) ORDER BY cid";
+
+const INDEXES_QUERY: &str = "
+SELECT l.name, l.\"unique\", l.origin, m.sql
+FROM pragma_index_list(?1) l
+LEFT JOIN sqlite_master m ON m.type = 'index' AND m.name = l.name
+ORDER BY l.name";
+
+const INDEX_COLUMNS_QUERY: &str = "
+SELECT cid, name, \"desc\" FROM pragma_index_xinfo(?1) WHERE key ORDER BY seqno";
+
+const FOREIGN_KEYS_QUERY: &str = "
+SELECT id, \"table\", \"from\", \"to\", on_update, on_delete
+FROM pragma_foreign_key_list(?1)
+ORDER BY id, seq";
+
+/// Reads the schema of an SQLite database through its pragmas.
+///
+/// SQLite doesn't keep the names of primary keys and unique constraints, so they get the
+/// default names, nor whether a foreign key is deferrable. Check constraints have no pragma,
+/// so they are read from the SQL of the tables.
+#[derive(Default)]
+pub struct SqliteIntrospector {
+    engine: SqliteEngine,
+}
+
+impl SqliteIntrospector {
+    /// Creates an introspector mapping declared types to data types through the type mapping
+    /// of the engine.
+    pub fn new(engine: SqliteEngine) -> Self {
+        Self { engine }
+    }
+
+    /// Reconstructs the tables, columns, keys, checks, indexes and relationships of the
+    /// database, named and flagged the way the DDL importer reads them.
+    ///
+    /// # Returns
+    /// - The schema together with an issue for everything the schema can't model and was
+    ///   skipped, such as a column without a data type or an index on an expression, or a
+    ///   `DatabaseError` if reading the database fails.
+    pub fn introspect(&self, connection: &Connection) -> Result<ImportResult, CoreError> {
+        let mut schema = Schema::new();
+        let mut issues = vec![];
+        let tables = connection
+            .prepare(TABLES_QUERY)
+            .and_then(|mut statement| {
+                statement
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<Result<Vec<(String, Option<String>)>, _>>()
+            })
+            .map_err(database_error)?;
+
+        let mut foreign_keys = vec![];
+        for (table_name, sql) in tables {
+            let table = match schema.add_table(&table_name) {
+                Ok(_) => schema.get_table_mut(&table_name).unwrap(),
+                Err(e) => {
+                    issues.push(ImportIssue::without_position(&e));
+                    continue;
+                }
+            };
+            self.add_columns(connection, table, &mut issues)?;
+            if let Some(sql) = sql {
+                add_checks(table, &sql, &mut issues);
+            }
+            self.add_indexes(connection, table, &mut issues)?;
+            foreign_keys.extend(self.foreign_keys(connection, &table_name)?);
+        }
+
+        for foreign_key in foreign_keys {
+            if let Err(e) = add_foreign_key(&mut schema, &foreign_key) {
+                issues.push(ImportIssue::without_position(&format!(
+                    "Foreign key of table {} on {} is not imported: {}",
+                    foreign_key.table,
+                    foreign_key.columns.join(", "),
+                    e
+                )));
+            }
+        }
+        Ok(ImportResult::new(schema, issues))
+    }
+
+    fn add_columns(
+        &self,
+        connection: &Connection,
+        table: &mut Table,
+        issues: &mut Vec<ImportIssue>,
+    ) -> Result<(), CoreError> {
+        let table_name = table.get_name().to_string();
+        let rows = query(connection, COLUMNS_QUERY, &table_name, |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, bool>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, usize>(4)?,
+            ))
+        })?;
+
+        let mut primary_key = vec![];
+        for (column_name, db_type, not_null, default, key_position) in rows {
+            if key_position > 0 {
+                primary_key.push((key_position, column_name.clone()));
+            }
+            let data_type = match self.engine.get_data_type(&db_type) {
+                Some(data_type) => data_type,
+                None if db_type.is_empty() => {
+                    issues.push(ImportIssue::without_position(&format!(
+                        "Column {} of table {} has no declared type, so it is not imported",
+                        column_name, table_name
+                    )));
+                    continue;
+                }
+                None => {
+                    issues.push(ImportIssue::without_position(&format!(
+                        "Column {} of table {} has the type {}, which has no data type, so it is not imported",
+                        column_name, table_name, db_type
+                    )));
+                    continue;
+                }
+            };
+            // SQLite lets primary key columns hold NULL unless told otherwise, PostgreSQL doesn't
+            let mut column = Column::new(&column_name, data_type, false, false, !not_null);
+            *column.get_default_mut() = default.as_deref().map(parse_default);
+            if key_position > 0 {
+                *column.is_nullable_mut() = false;
+            }
+            if let Err(e) = table.add_column(column) {
+                issues.push(ImportIssue::without_position(&e));
+            }
+        }
+
+        if !primary_key.is_empty() {
+            primary_key.sort();
+            let columns = primary_key
+                .into_iter()
+                .map(|c| c.1)
+                .collect::<Vec<String>>();
+            if let Err(e) = table.set_primary_key(Some(PrimaryKey::new(&as_strs(&columns)))) {
+                issues.push(ImportIssue::without_position(&format!(
+                    "Primary key of table {} is not imported: {}",
+                    table_name, e
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn add_indexes(
+        &self,
+        connection: &Connection,
+        table: &mut Table,
+        issues: &mut Vec<ImportIssue>,
+    ) -> Result<(), CoreError> {
+        let table_name = table.get_name().to_string();
+        let rows = query(connection, INDEXES_QUERY, &table_name, |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, bool>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?;
+
+        for (index_name, unique, origin, sql) in rows {
+            let columns = query(connection, INDEX_COLUMNS_QUERY, &index_name, |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, bool>(2)?,
+                ))
+            })?;
+            if columns
+                .iter()
+                .any(|(cid, name, _)| *cid < 0 || name.is_none())
+            {
+                issues.push(ImportIssue::without_position(&format!(
+                    "Index {} of table {} is on an expression, which is not supported, so it is not imported",
+                    index_name, table_name
+                )));
+                continue;
+            }
+            let mut index = Index::new(&index_name, &[]);
+            for (_, column_name, descending) in columns {
+                let mut column = IndexColumn::new(&column_name.unwrap());
+                if descending {
+                    column.set_order(SortOrder::Desc);
+                }
+                index.get_columns_mut().push(column);
+            }
+
+            let result = match origin.as_str() {
+                // the primary key was read with the columns
+                "pk" => Ok(()),
+                "u" => {
+                    let columns = index
+                        .get_columns()
+                        .iter()
+                        .map(|c| c.get_name().to_string())
+                        .collect::<Vec<String>>();
+                    add_unique_constraint(table, None, &columns)
+                }
+                _ => {
+                    index.set_unique(unique);
+                    index.set_predicate(sql.as_deref().and_then(index_predicate).as_deref());
+                    table.add_index(index).map(|_| ())
+                }
+            };
+            if let Err(e) = result {
+                issues.push(ImportIssue::without_position(&format!(
+                    "Index {} of table {} is not imported: {}",
+                    index_name, table_name, e
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn foreign_keys(
+        &self,
+        connection: &Connection,
+        table_name: &str,
+    ) -> Result<Vec<ForeignKey>, CoreError> {
+        let rows = query(connection, FOREIGN_KEYS_QUERY, table_name, |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })?;
+
+        // a foreign key has a row per column
+        let mut foreign_keys: Vec<(i64, ForeignKey)> = vec![];
+        for (id, referenced_table, column, referenced_column, on_update, on_delete) in rows {
+            if foreign_keys
+                .last()
+                .is_none_or(|(last_id, _)| *last_id != id)
+            {
+                foreign_keys.push((
+                    id,
+                    ForeignKey {
+                        table: table_name.to_string(),
+                        columns: vec![],
+                        referenced_table,
+                        referenced_columns: vec![],
+                        on_delete: referential_action(&on_delete),
+                        on_update: referential_action(&on_update),
+                        deferrable: false,
+                        initially_deferred: false,
+                    },
+                ));
+            }
+            let foreign_key = &mut foreign_keys.last_mut().unwrap().1;
+            foreign_key.columns.push(column);
+            // without referenced columns the foreign key references the primary key
+            foreign_key.referenced_columns.extend(referenced_column);
+        }
+        Ok(foreign_keys.into_iter().map(|f| f.1).collect())
+    }
+}
+
+fn query<T>(
+    connection: &Connection,
+    sql: &str,
+    parameter: &str,
+    map: impl FnMut(&Row<'_>) -> rusqlite::Result<T>,
+) -> Result<Vec<T>, CoreError> {
+    connection
+        .prepare(sql)
+        .and_then(|mut statement| statement.query_map([parameter], map)?.collect())
+        .map_err(database_error)
+}
+
+fn database_error(error: rusqlite::Error) -> CoreError {
+    CoreError::DatabaseError(error.to_string())
+}
+
+/// Copies the check constraints of the `CREATE TABLE` statement of a table to its columns.
+fn add_checks(table: &mut Table, sql: &str, issues: &mut Vec<ImportIssue>) {
+    let parsed = DdlParser::default().parse(sql);
+    let Ok(parsed_table) = parsed.get_schema().get_table(table.get_name()) else {
+        return;
+    };
+    for column in table.get_columns_mut() {
+        let Ok(parsed_column) = parsed_table.get_column(column.get_name()) else {
+            continue;
+        };
+        for check in parsed_column.get_checks() {
+            if let Err(e) = column.add_check(check.clone()) {
+                issues.push(ImportIssue::without_position(&e));
+            }
+        }
+    }
+}
+
+/// Maps the action of a foreign key as `PRAGMA foreign_key_list` reports it.
+fn referential_action(action: &str) -> ReferentialAction {
+    match action {
+        "CASCADE" => ReferentialAction::Cascade,
+        "SET NULL" => ReferentialAction::SetNull,
+        "SET DEFAULT" => ReferentialAction::SetDefault,
+        "RESTRICT" => ReferentialAction::Restrict,
+        _ => ReferentialAction::NoAction,
+    }
+}
+
+/// Returns the `WHERE` condition of a `CREATE INDEX` statement, if it is a partial index.
+fn index_predicate(sql: &str) -> Option<String> {
+    let (tokens, _) = tokenize(sql);
+    let condition = tokens.iter().find(|t| t.is_keyword("WHERE"))?;
+    Some(
+        sql[condition.get_end()..]
+            .trim()
+            .trim_end_matches(';')
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::migration::schema_diff::SchemaDiff;
+    use crate::schema::constraint::DefaultValue;
+    use crate::schema::data_type::DataType;
+    use crate::schema::relationship::RelationshipType;
+
+    use super::*;
+
+    /// Declared with types both SQLite and PostgreSQL map to the same data types.
+    const DESIGN: &str = "
+CREATE TABLE users (
+    id INTEGER PRIMARY KEY,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    display_name TEXT,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    born DATE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+CREATE TABLE profiles (
+    user_id INTEGER PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    bio TEXT DEFAULT 'Hello'
+);
+CREATE TABLE orders (
+    id BIGINT NOT NULL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users ON UPDATE RESTRICT,
+    status TEXT NOT NULL DEFAULT 'pending',
+    total NUMERIC(10,2) DEFAULT 0 CONSTRAINT orders_total_check CHECK (total >= 0),
+    discount INTEGER DEFAULT -1
+);
+CREATE TABLE order_items (
+    order_id BIGINT NOT NULL,
+    position INTEGER NOT NULL,
+    quantity SMALLINT NOT NULL DEFAULT 1,
+    PRIMARY KEY (order_id, position),
+    UNIQUE (order_id, quantity),
+    FOREIGN KEY (order_id) REFERENCES orders (id)
+);
+CREATE UNIQUE INDEX orders_user_status_idx ON orders (user_id, status DESC)
+    WHERE status <> 'shipped';
+CREATE INDEX users_created_at_idx ON users (created_at);
+";
+
+    #[test]
+    fn test_introspect() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(DESIGN).unwrap();
+        let result = SqliteIntrospector::default()
+            .introspect(&connection)
+            .unwrap();
+        assert!(result.is_complete(), "{:?}", result.get_issues());
+        let schema = result.into_schema();
+        schema.validate().unwrap();
+
+        let users = schema.get_table("users").unwrap();
+        let id = users.get_column("id").unwrap();
+        assert!(id.is_primary_key() && !id.is_nullable());
+        assert!(users.get_column("email").unwrap().is_unique());
+        assert_eq!(
+            users.get_column("active").unwrap().get_default(),
+            Some(&DefaultValue::Boolean(true))
+        );
+        let orders = schema.get_table("orders").unwrap();
+        assert_eq!(
+            orders.get_column("discount").unwrap().get_default(),
+            Some(&DefaultValue::Integer(-1))
+        );
+        assert_eq!(
+            orders.get_column("total").unwrap().get_checks()[0].get_expression(),
+            "total >= 0"
+        );
+        assert_eq!(
+            orders.get_column("total").unwrap().get_data_type(),
+            DataType::Decimal {
+                precision: 10,
+                scale: 2
+            }
+        );
+        assert_eq!(
+            schema
+                .get_relationship_with_code("users_id_profiles_user_id")
+                .unwrap()
+                .get_relationship_type(),
+            RelationshipType::OneToOne
+        );
+        let order_items = schema.get_table("order_items").unwrap();
+        assert_eq!(
+            order_items.get_primary_key(),
+            Some(&PrimaryKey::new(&["order_id", "position"]))
+        );
+        assert!(order_items.get_column("order_id").unwrap().is_foreign_key());
+
+        let design = DdlParser::default().parse(DESIGN);
+        assert!(design.is_complete(), "{:?}", design.get_issues());
+        assert!(SchemaDiff::new(design.get_schema(), &schema)
+            .get_changes()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT, note);
+                CREATE INDEX users_email_idx ON users (lower(email));
+                CREATE INDEX users_id_idx ON users (id);
+                CREATE TABLE points (location GEOMETRY PRIMARY KEY, user_id INTEGER REFERENCES users);",
+            )
+            .unwrap();
+        let result = SqliteIntrospector::default()
+            .introspect(&connection)
+            .unwrap();
+        let issues = result
+            .get_issues()
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            issues,
+            [
+                "Column location of table points has the type GEOMETRY, which has no data type, so it is not imported",
+                "Primary key of table points is not imported: Column location doesn't not exist in table points",
+                "Column note of table users has no declared type, so it is not imported",
+                "Index users_email_idx of table users is on an expression, which is not supported, so it is not imported",
+            ]
+        );
+
+        let schema = result.get_schema();
+        assert_eq!(schema.get_table("points").unwrap().get_columns().len(), 1);
+        assert!(schema
+            .get_table("users")
+            .unwrap()
+            .get_column("email")
+            .is_ok());
+        assert_eq!(schema.get_table("users").unwrap().get_indexes().len(), 1);
+        assert_eq!(schema.get_relationships().len(), 1);
+    }
+}
@@ -1,4 +1,8 @@
 pub mod database;
 pub mod error;
+pub mod import;
 pub mod migration;
 pub mod schema;
+
+#[cfg(test)]
+pub(crate) mod test_fixtures;
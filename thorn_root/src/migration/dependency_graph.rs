@@ -0,0 +1,174 @@
+use crate::schema::relationship::Relationship;
+use crate::schema::Schema;
+
+/// Order in which the tables of a schema can be created, derived from its relationships:
+/// a referenced table comes before the tables referencing it.
+///
+/// Tables which reference each other in a cycle can't be ordered this way. The cycle is
+/// broken by deferring foreign keys, which then have to be created after all tables.
+/// Ties are resolved by the order in which tables were added to the schema, so the same
+/// schema always produces the same order.
+#[derive(Debug, Default, Clone)]
+pub struct DependencyGraph {
+    order: Vec<String>,
+    deferred: Vec<Relationship>,
+}
+
+impl DependencyGraph {
+    pub fn new(schema: &Schema) -> Self {
+        let tables = schema
+            .get_tables()
+            .iter()
+            .map(|t| t.get_name())
+            .collect::<Vec<&str>>();
+        // a many-to-many relationship has no foreign key of its own and a table can always
+        // reference itself
+        let mut pending = schema
+            .get_relationships()
+            .iter()
+            .filter(|r| !r.is_many_to_many() && r.get_from_table() != r.get_to_table())
+            .filter(|r| tables.contains(&r.get_from_table()) && tables.contains(&r.get_to_table()))
+            .collect::<Vec<&Relationship>>();
+        let mut order = Vec::<String>::new();
+        let mut deferred = Vec::<Relationship>::new();
+
+        while order.len() < tables.len() {
+            let remaining = tables
+                .iter()
+                .filter(|t| !order.iter().any(|o| o == *t))
+                .collect::<Vec<&&str>>();
+            // without a free table, every remaining table waits for another one, so there is a
+            // cycle; breaking it at a table outside of it would defer foreign keys needlessly
+            let next = remaining
+                .iter()
+                .find(|t| !pending.iter().any(|r| r.get_to_table() == ***t))
+                .or_else(|| remaining.iter().find(|t| is_on_cycle(t, &pending)))
+                .unwrap_or(&remaining[0]);
+            // anything still blocking the table closes a cycle
+            deferred.extend(
+                pending
+                    .iter()
+                    .filter(|r| r.get_to_table() == **next)
+                    .map(|r| (*r).clone()),
+            );
+            pending.retain(|r| r.get_to_table() != **next && r.get_from_table() != **next);
+            order.push(next.to_string());
+        }
+
+        Self { order, deferred }
+    }
+
+    /// Returns the table names, every referenced table before the tables referencing it.
+    pub fn get_order(&self) -> &[String] {
+        &self.order
+    }
+
+    /// Position of a table in the order, or `None` if the schema has no such table.
+    pub fn get_position(&self, table_name: &str) -> Option<usize> {
+        self.order.iter().position(|t| t == table_name)
+    }
+
+    /// Returns the relationships whose foreign key must be created after all tables
+    /// because they close a cycle.
+    pub fn get_deferred_relationships(&self) -> &[Relationship] {
+        &self.deferred
+    }
+
+    pub fn is_deferred(&self, relationship: &Relationship) -> bool {
+        self.deferred
+            .iter()
+            .any(|r| r.get_code() == relationship.get_code())
+    }
+}
+
+/// Whether `table` can reach itself by following the `pending` relationships.
+fn is_on_cycle(table: &str, pending: &[&Relationship]) -> bool {
+    let mut reached = Vec::<&str>::new();
+    let mut stack = vec![table];
+    while let Some(current) = stack.pop() {
+        for relationship in pending.iter().filter(|r| r.get_from_table() == current) {
+            let next = relationship.get_to_table();
+            if next == table {
+                return true;
+            }
+            if !reached.contains(&next) {
+                reached.push(next);
+                stack.push(next);
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::relationship::RelationshipType;
+    use crate::test_fixtures::schema_with_tables;
+
+    use super::*;
+
+    fn reference(schema: &mut Schema, from_table: &str, to_table: &str) {
+        schema
+            .add_relation(Relationship::new(
+                from_table,
+                "id",
+                to_table,
+                "ref_id",
+                RelationshipType::OneToMany,
+            ))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_referenced_tables_come_first() {
+        let mut schema = schema_with_tables(&["order_items", "orders", "users", "products"]);
+        reference(&mut schema, "orders", "order_items");
+        reference(&mut schema, "users", "orders");
+        reference(&mut schema, "order_items", "order_items");
+
+        let graph = DependencyGraph::new(&schema);
+        assert_eq!(
+            graph.get_order(),
+            ["users", "orders", "order_items", "products"]
+        );
+        assert!(graph.get_deferred_relationships().is_empty());
+        assert_eq!(graph.get_position("orders"), Some(1));
+        assert_eq!(graph.get_position("missing"), None);
+    }
+
+    #[test]
+    fn test_cycles_are_broken_by_deferring() {
+        let mut schema = schema_with_tables(&["employees", "departments", "offices"]);
+        reference(&mut schema, "departments", "employees");
+        reference(&mut schema, "employees", "departments");
+        reference(&mut schema, "departments", "offices");
+
+        let graph = DependencyGraph::new(&schema);
+        assert_eq!(graph.get_order(), ["employees", "departments", "offices"]);
+        assert_eq!(graph.get_deferred_relationships().len(), 1);
+        assert_eq!(
+            graph.get_deferred_relationships()[0].get_code(),
+            "departments_id_employees_ref_id"
+        );
+        assert_eq!(
+            graph.get_order(),
+            DependencyGraph::new(&schema.clone()).get_order()
+        );
+    }
+
+    #[test]
+    fn test_cycles_are_broken_inside_the_cycle() {
+        let mut schema = schema_with_tables(&["offices", "employees", "departments"]);
+        reference(&mut schema, "departments", "offices");
+        reference(&mut schema, "departments", "employees");
+        reference(&mut schema, "employees", "departments");
+
+        let graph = DependencyGraph::new(&schema);
+        assert_eq!(graph.get_order(), ["employees", "departments", "offices"]);
+        assert_eq!(graph.get_deferred_relationships().len(), 1);
+        assert_eq!(
+            graph.get_deferred_relationships()[0].get_code(),
+            "departments_id_employees_ref_id"
+        );
+    }
+}
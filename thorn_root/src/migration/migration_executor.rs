@@ -0,0 +1,258 @@
+use std::time::{Duration, SystemTime};
+
+use crate::error::CoreError;
+
+use super::migration_plan::MigrationPlan;
+
+/// A migration plan under an id, which a database records once the plan has been applied.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    id: String,
+    plan: MigrationPlan,
+    checksum: String,
+}
+
+impl Migration {
+    pub fn new(id: &str, plan: MigrationPlan) -> Self {
+        Self {
+            id: id.to_string(),
+            checksum: checksum(&plan.get_sql()),
+            plan,
+        }
+    }
+
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_plan(&self) -> &MigrationPlan {
+        &self.plan
+    }
+
+    /// Getter for `checksum`, a hash of the SQL of the plan
+    pub fn get_checksum(&self) -> &str {
+        &self.checksum
+    }
+}
+
+/// A migration as recorded in the history of a database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedMigration {
+    id: String,
+    checksum: String,
+    applied_at: SystemTime,
+    duration: Duration,
+}
+
+impl AppliedMigration {
+    pub fn new(id: &str, checksum: &str, applied_at: SystemTime, duration: Duration) -> Self {
+        Self {
+            id: id.to_string(),
+            checksum: checksum.to_string(),
+            applied_at,
+            duration,
+        }
+    }
+
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_checksum(&self) -> &str {
+        &self.checksum
+    }
+
+    pub fn get_applied_at(&self) -> SystemTime {
+        self.applied_at
+    }
+
+    /// Getter for `duration`, the time it took to apply the migration
+    pub fn get_duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+/// Applies migrations to a database and keeps the history of the applied ones.
+pub trait MigrationExecutor {
+    /// Returns the migrations recorded as applied, in the order they were applied.
+    fn get_applied_migrations(&mut self) -> Result<Vec<AppliedMigration>, CoreError>;
+
+    /// Runs the steps of a migration and records it as applied, whether or not it already is.
+    fn apply_migration(&mut self, migration: &Migration) -> Result<AppliedMigration, CoreError>;
+
+    /// Keeps other executors from applying migrations to the database until `release_lock`,
+    /// which `migrate` holds while it reads the history and applies what's missing.
+    /// Executors of databases without locks do nothing.
+    fn acquire_lock(&mut self) -> Result<(), CoreError> {
+        Ok(())
+    }
+
+    /// Releases the lock taken by `acquire_lock`.
+    fn release_lock(&mut self) -> Result<(), CoreError> {
+        Ok(())
+    }
+
+    /// Applies the migrations which aren't applied yet, in order, holding the lock.
+    ///
+    /// # Returns
+    /// - The migrations applied by this call, or a `MigrationError` before anything is run
+    ///   if the plan of an applied migration has changed since it was applied.
+    fn migrate(&mut self, migrations: &[Migration]) -> Result<Vec<AppliedMigration>, CoreError> {
+        self.acquire_lock()?;
+        let applied = apply_pending(self, migrations);
+        let released = self.release_lock();
+        let applied = applied?;
+        released?;
+        Ok(applied)
+    }
+}
+
+fn apply_pending<E: MigrationExecutor + ?Sized>(
+    executor: &mut E,
+    migrations: &[Migration],
+) -> Result<Vec<AppliedMigration>, CoreError> {
+    let applied = executor.get_applied_migrations()?;
+    let mut pending = vec![];
+    for migration in migrations {
+        match applied.iter().find(|a| a.get_id() == migration.get_id()) {
+            Some(applied) if applied.get_checksum() != migration.get_checksum() => {
+                return Err(CoreError::MigrationError(format!(
+                    "Migration {} was applied with checksum {}, but its plan now has checksum {}",
+                    migration.get_id(),
+                    applied.get_checksum(),
+                    migration.get_checksum()
+                )))
+            }
+            Some(_) => {}
+            None => pending.push(migration),
+        }
+    }
+
+    pending
+        .into_iter()
+        .map(|m| executor.apply_migration(m))
+        .collect()
+}
+
+/// Hashes SQL with 64-bit FNV-1a, which stays the same across platforms and Rust versions.
+/// The hash only detects changes, it doesn't protect against tampering.
+fn checksum(sql: &str) -> String {
+    let hash = sql.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::migration::migration_plan::MigrationStep;
+
+    use super::*;
+
+    /// Keeps the history in memory and the SQL it ran in a log.
+    #[derive(Default)]
+    struct LogExecutor {
+        history: Vec<AppliedMigration>,
+        log: Vec<String>,
+        locked: bool,
+    }
+
+    impl MigrationExecutor for LogExecutor {
+        fn acquire_lock(&mut self) -> Result<(), CoreError> {
+            assert!(!self.locked);
+            self.locked = true;
+            Ok(())
+        }
+
+        fn release_lock(&mut self) -> Result<(), CoreError> {
+            self.locked = false;
+            Ok(())
+        }
+
+        fn get_applied_migrations(&mut self) -> Result<Vec<AppliedMigration>, CoreError> {
+            Ok(self.history.clone())
+        }
+
+        fn apply_migration(
+            &mut self,
+            migration: &Migration,
+        ) -> Result<AppliedMigration, CoreError> {
+            assert!(self.locked);
+            self.log.push(migration.get_plan().get_sql());
+            let applied = AppliedMigration::new(
+                migration.get_id(),
+                migration.get_checksum(),
+                SystemTime::UNIX_EPOCH,
+                Duration::ZERO,
+            );
+            self.history.push(applied.clone());
+            Ok(applied)
+        }
+    }
+
+    fn create_table(id: &str, name: &str) -> Migration {
+        Migration::new(
+            id,
+            MigrationPlan::new(vec![MigrationStep::CreateTable {
+                name: name.to_string(),
+                sql_script: format!("CREATE TABLE {} (id integer);\n", name),
+                down_sql_script: Some(format!("DROP TABLE {};\n", name)),
+            }]),
+        )
+    }
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(checksum(""), "cbf29ce484222325");
+        assert_eq!(checksum("a"), "af63dc4c8601ec8c");
+        assert_eq!(
+            create_table("1", "users").get_checksum(),
+            create_table("2", "users").get_checksum()
+        );
+        assert_ne!(
+            create_table("1", "users").get_checksum(),
+            create_table("1", "orders").get_checksum()
+        );
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut executor = LogExecutor::default();
+        let applied = executor
+            .migrate(&[create_table("1", "users"), create_table("2", "orders")])
+            .unwrap();
+        assert_eq!(applied.len(), 2);
+        assert_eq!(applied[1].get_id(), "2");
+
+        let applied = executor
+            .migrate(&[
+                create_table("1", "users"),
+                create_table("2", "orders"),
+                create_table("3", "items"),
+            ])
+            .unwrap();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(executor.log.len(), 3);
+        assert_eq!(executor.log[2], "CREATE TABLE items (id integer);\n");
+    }
+
+    #[test]
+    fn test_migrate_refuses_changed_migration() {
+        let mut executor = LogExecutor::default();
+        executor.migrate(&[create_table("1", "users")]).unwrap();
+
+        let error = executor
+            .migrate(&[create_table("1", "accounts"), create_table("2", "orders")])
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Migration 1 was applied with checksum {}, but its plan now has checksum {}",
+                create_table("1", "users").get_checksum(),
+                create_table("1", "accounts").get_checksum()
+            )
+        );
+        assert_eq!(executor.log.len(), 1);
+        assert!(!executor.locked);
+    }
+}
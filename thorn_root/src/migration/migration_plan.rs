@@ -1,6 +1,13 @@
 use crate::schema::column::Column;
+use crate::schema::constraint::PrimaryKey;
+use crate::schema::enum_type::EnumType;
+use crate::schema::index::Index;
 use crate::schema::relationship::Relationship;
 
+/// Opens the scripts of steps which switch foreign key enforcement off, like SQLite's table
+/// rebuilds. SQLite ignores the switch inside a transaction.
+pub const FOREIGN_KEYS_OFF: &str = "PRAGMA foreign_keys = OFF;\n";
+
 #[derive(Debug, Default, Clone)]
 pub struct MigrationPlan {
     steps: Vec<MigrationStep>,
@@ -22,6 +29,82 @@ impl MigrationPlan {
         }
         res
     }
+
+    /// Returns the steps which have no inverse.
+    pub fn get_irreversible_steps(&self) -> Vec<&MigrationStep> {
+        self.steps.iter().filter(|s| !s.is_reversible()).collect()
+    }
+
+    /// Builds the plan which undoes this one: every step is inverted and the order is reversed.
+    ///
+    /// # Returns
+    /// - A `Result` containing the down plan, or an error message listing the steps that can't be reversed.
+    pub fn reverse(&self) -> Result<MigrationPlan, String> {
+        let irreversible = self.get_irreversible_steps();
+        if !irreversible.is_empty() {
+            return Err(format!(
+                "Migration plan can't be reversed, irreversible steps: {}",
+                irreversible
+                    .iter()
+                    .map(|s| s.get_sql_script().trim())
+                    .collect::<Vec<&str>>()
+                    .join(" ")
+            ));
+        }
+
+        Ok(MigrationPlan::new(
+            self.steps
+                .iter()
+                .rev()
+                .filter_map(|s| s.reverse())
+                .collect(),
+        ))
+    }
+
+    /// Returns the SQL which rolls this plan back.
+    pub fn get_down_sql(&self) -> Result<String, String> {
+        Ok(self.reverse()?.get_sql())
+    }
+
+    /// Splits the plan into runs of consecutive steps which are either all transactional or all
+    /// non-transactional, keeping the order of the steps.
+    pub fn get_phases(&self) -> Vec<MigrationPhase<'_>> {
+        let mut phases = Vec::<MigrationPhase>::new();
+        for step in self.steps.iter() {
+            match phases.last_mut() {
+                Some(phase) if phase.transactional == step.is_transactional() => {
+                    phase.steps.push(step)
+                }
+                _ => phases.push(MigrationPhase {
+                    transactional: step.is_transactional(),
+                    steps: vec![step],
+                }),
+            }
+        }
+        phases
+    }
+}
+
+/// Consecutive steps of a plan which run in a single transaction if the phase is transactional,
+/// or one by one outside of any transaction otherwise.
+#[derive(Debug, Clone)]
+pub struct MigrationPhase<'a> {
+    transactional: bool,
+    steps: Vec<&'a MigrationStep>,
+}
+
+impl MigrationPhase<'_> {
+    pub fn is_transactional(&self) -> bool {
+        self.transactional
+    }
+
+    pub fn get_steps(&self) -> &[&MigrationStep] {
+        &self.steps
+    }
+
+    pub fn get_sql(&self) -> String {
+        self.steps.iter().map(|s| s.get_sql_script()).collect()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -29,29 +112,90 @@ pub enum MigrationStep {
     CreateTable {
         name: String,
         sql_script: String,
+        down_sql_script: Option<String>,
     },
     DropTable {
         name: String,
         sql_script: String,
+        down_sql_script: Option<String>,
     },
     AddColumn {
         table: String,
         column: Column,
         sql_script: String,
+        down_sql_script: Option<String>,
     },
     RemoveColumn {
         table: String,
-        column_name: String,
+        column: Column,
         sql_script: String,
+        down_sql_script: Option<String>,
     },
     AlterColumn {
         table: String,
         column: Column,
+        previous_column: Column,
+        sql_script: String,
+        down_sql_script: Option<String>,
+    },
+    AddPrimaryKey {
+        table: String,
+        primary_key: PrimaryKey,
+        sql_script: String,
+        down_sql_script: Option<String>,
+    },
+    DropPrimaryKey {
+        table: String,
+        primary_key: PrimaryKey,
         sql_script: String,
+        down_sql_script: Option<String>,
+    },
+    /// Creates an index; `concurrently` if it is built without blocking writes to the table.
+    AddIndex {
+        table: String,
+        index: Index,
+        concurrently: bool,
+        sql_script: String,
+        down_sql_script: Option<String>,
+    },
+    DropIndex {
+        table: String,
+        index: Index,
+        concurrently: bool,
+        sql_script: String,
+        down_sql_script: Option<String>,
     },
     AddRelationship {
         relationship: Relationship,
         sql_script: String,
+        down_sql_script: Option<String>,
+    },
+    DropRelationship {
+        relationship: Relationship,
+        sql_script: String,
+        down_sql_script: Option<String>,
+    },
+    /// Checks the existing rows against a foreign key which was added without checking them.
+    ValidateRelationship {
+        relationship: Relationship,
+        sql_script: String,
+        down_sql_script: Option<String>,
+    },
+    CreateEnum {
+        enum_type: EnumType,
+        sql_script: String,
+        down_sql_script: Option<String>,
+    },
+    DropEnum {
+        enum_type: EnumType,
+        sql_script: String,
+        down_sql_script: Option<String>,
+    },
+    AlterEnum {
+        enum_type: EnumType,
+        previous_enum_type: EnumType,
+        sql_script: String,
+        down_sql_script: Option<String>,
     },
 }
 
@@ -63,7 +207,359 @@ impl MigrationStep {
             MigrationStep::AddColumn { sql_script, .. } => sql_script,
             MigrationStep::RemoveColumn { sql_script, .. } => sql_script,
             MigrationStep::AlterColumn { sql_script, .. } => sql_script,
+            MigrationStep::AddIndex { sql_script, .. } => sql_script,
+            MigrationStep::DropIndex { sql_script, .. } => sql_script,
             MigrationStep::AddRelationship { sql_script, .. } => sql_script,
+            MigrationStep::DropRelationship { sql_script, .. } => sql_script,
+            MigrationStep::ValidateRelationship { sql_script, .. } => sql_script,
+            MigrationStep::AddPrimaryKey { sql_script, .. } => sql_script,
+            MigrationStep::DropPrimaryKey { sql_script, .. } => sql_script,
+            MigrationStep::CreateEnum { sql_script, .. } => sql_script,
+            MigrationStep::DropEnum { sql_script, .. } => sql_script,
+            MigrationStep::AlterEnum { sql_script, .. } => sql_script,
+        }
+    }
+
+    /// Returns the SQL which undoes this step, if the step can be undone.
+    pub fn get_down_sql_script(&self) -> Option<&str> {
+        match self {
+            MigrationStep::CreateTable {
+                down_sql_script, ..
+            }
+            | MigrationStep::DropTable {
+                down_sql_script, ..
+            }
+            | MigrationStep::AddColumn {
+                down_sql_script, ..
+            }
+            | MigrationStep::RemoveColumn {
+                down_sql_script, ..
+            }
+            | MigrationStep::AlterColumn {
+                down_sql_script, ..
+            }
+            | MigrationStep::AddIndex {
+                down_sql_script, ..
+            }
+            | MigrationStep::DropIndex {
+                down_sql_script, ..
+            }
+            | MigrationStep::AddRelationship {
+                down_sql_script, ..
+            }
+            | MigrationStep::DropRelationship {
+                down_sql_script, ..
+            }
+            | MigrationStep::ValidateRelationship {
+                down_sql_script, ..
+            }
+            | MigrationStep::AddPrimaryKey {
+                down_sql_script, ..
+            }
+            | MigrationStep::DropPrimaryKey {
+                down_sql_script, ..
+            }
+            | MigrationStep::CreateEnum {
+                down_sql_script, ..
+            }
+            | MigrationStep::DropEnum {
+                down_sql_script, ..
+            }
+            | MigrationStep::AlterEnum {
+                down_sql_script, ..
+            } => down_sql_script.as_deref(),
+        }
+    }
+
+    pub fn is_reversible(&self) -> bool {
+        self.get_down_sql_script().is_some()
+    }
+
+    /// Whether the step can run inside a transaction. Concurrent index changes can't, and a
+    /// validation runs on its own so the lock taken when adding its constraint is released
+    /// before the table is scanned. Labels added to an enum type with `ADD VALUE` can't be
+    /// used before the transaction adding them commits, so these steps run on their own too,
+    /// as do steps switching foreign keys off, which would otherwise stay enforced.
+    pub fn is_transactional(&self) -> bool {
+        match self {
+            MigrationStep::AddIndex {
+                concurrently: true, ..
+            }
+            | MigrationStep::DropIndex {
+                concurrently: true, ..
+            }
+            | MigrationStep::ValidateRelationship { .. } => false,
+            MigrationStep::AlterEnum {
+                enum_type,
+                previous_enum_type,
+                ..
+            } => !previous_enum_type.is_extended_by(enum_type),
+            _ => !self.get_sql_script().starts_with(FOREIGN_KEYS_OFF),
+        }
+    }
+
+    /// Whether the step loses data, e.g. by dropping a column or removing labels from an
+    /// enum type. Reversing a destructive step restores the structure,
+    /// but not the rows or values that were removed.
+    pub fn is_destructive(&self) -> bool {
+        match self {
+            MigrationStep::DropTable { .. } | MigrationStep::RemoveColumn { .. } => true,
+            MigrationStep::AlterColumn {
+                column,
+                previous_column,
+                ..
+            } => column.get_data_type() != previous_column.get_data_type(),
+            MigrationStep::AlterEnum {
+                enum_type,
+                previous_enum_type,
+                ..
+            } => previous_enum_type
+                .get_labels()
+                .iter()
+                .any(|label| !enum_type.get_labels().contains(label)),
+            _ => false,
         }
     }
+
+    /// Returns the inverse step, or `None` if the step is irreversible.
+    pub fn reverse(&self) -> Option<MigrationStep> {
+        let down_sql_script = self.get_down_sql_script()?.to_string();
+        let sql_script = Some(self.get_sql_script().to_string());
+        let step = match self.clone() {
+            MigrationStep::CreateTable { name, .. } => MigrationStep::DropTable {
+                name,
+                sql_script: down_sql_script,
+                down_sql_script: sql_script,
+            },
+            MigrationStep::DropTable { name, .. } => MigrationStep::CreateTable {
+                name,
+                sql_script: down_sql_script,
+                down_sql_script: sql_script,
+            },
+            MigrationStep::AddColumn { table, column, .. } => MigrationStep::RemoveColumn {
+                table,
+                column,
+                sql_script: down_sql_script,
+                down_sql_script: sql_script,
+            },
+            MigrationStep::RemoveColumn { table, column, .. } => MigrationStep::AddColumn {
+                table,
+                column,
+                sql_script: down_sql_script,
+                down_sql_script: sql_script,
+            },
+            MigrationStep::AlterColumn {
+                table,
+                column,
+                previous_column,
+                ..
+            } => MigrationStep::AlterColumn {
+                table,
+                column: previous_column,
+                previous_column: column,
+                sql_script: down_sql_script,
+                down_sql_script: sql_script,
+            },
+            MigrationStep::AddIndex {
+                table,
+                index,
+                concurrently,
+                ..
+            } => MigrationStep::DropIndex {
+                table,
+                index,
+                concurrently,
+                sql_script: down_sql_script,
+                down_sql_script: sql_script,
+            },
+            MigrationStep::DropIndex {
+                table,
+                index,
+                concurrently,
+                ..
+            } => MigrationStep::AddIndex {
+                table,
+                index,
+                concurrently,
+                sql_script: down_sql_script,
+                down_sql_script: sql_script,
+            },
+            MigrationStep::AddRelationship { relationship, .. } => {
+                MigrationStep::DropRelationship {
+                    relationship,
+                    sql_script: down_sql_script,
+                    down_sql_script: sql_script,
+                }
+            }
+            MigrationStep::DropRelationship { relationship, .. } => {
+                MigrationStep::AddRelationship {
+                    relationship,
+                    sql_script: down_sql_script,
+                    down_sql_script: sql_script,
+                }
+            }
+            MigrationStep::AddPrimaryKey {
+                table, primary_key, ..
+            } => MigrationStep::DropPrimaryKey {
+                table,
+                primary_key,
+                sql_script: down_sql_script,
+                down_sql_script: sql_script,
+            },
+            MigrationStep::DropPrimaryKey {
+                table, primary_key, ..
+            } => MigrationStep::AddPrimaryKey {
+                table,
+                primary_key,
+                sql_script: down_sql_script,
+                down_sql_script: sql_script,
+            },
+            MigrationStep::ValidateRelationship { relationship, .. } => {
+                MigrationStep::ValidateRelationship {
+                    relationship,
+                    sql_script: down_sql_script,
+                    down_sql_script: sql_script,
+                }
+            }
+            MigrationStep::CreateEnum { enum_type, .. } => MigrationStep::DropEnum {
+                enum_type,
+                sql_script: down_sql_script,
+                down_sql_script: sql_script,
+            },
+            MigrationStep::DropEnum { enum_type, .. } => MigrationStep::CreateEnum {
+                enum_type,
+                sql_script: down_sql_script,
+                down_sql_script: sql_script,
+            },
+            MigrationStep::AlterEnum {
+                enum_type,
+                previous_enum_type,
+                ..
+            } => MigrationStep::AlterEnum {
+                enum_type: previous_enum_type,
+                previous_enum_type: enum_type,
+                sql_script: down_sql_script,
+                down_sql_script: sql_script,
+            },
+        };
+        Some(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::data_type::DataType;
+
+    use super::*;
+
+    fn add_column_step() -> MigrationStep {
+        MigrationStep::AddColumn {
+            table: "users".to_string(),
+            column: Column::new("email", DataType::String, false, false, true),
+            sql_script: "ALTER TABLE users ADD COLUMN email TEXT;\n".to_string(),
+            down_sql_script: Some("ALTER TABLE users DROP COLUMN email;\n".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_reverse_plan() {
+        let plan = MigrationPlan::new(vec![
+            MigrationStep::CreateTable {
+                name: "users".to_string(),
+                sql_script: "CREATE TABLE users (\n    id INTEGER\n);\n".to_string(),
+                down_sql_script: Some("DROP TABLE users;\n".to_string()),
+            },
+            add_column_step(),
+        ]);
+
+        let down = plan.reverse().unwrap();
+        let steps = down.get_steps();
+        assert_eq!(steps.len(), 2);
+        assert!(matches!(
+            &steps[0],
+            MigrationStep::RemoveColumn { column, .. } if column.get_name() == "email"
+        ));
+        assert!(steps[0].is_destructive());
+        assert!(matches!(&steps[1], MigrationStep::DropTable { name, .. } if name == "users"));
+        assert_eq!(
+            plan.get_down_sql().unwrap(),
+            "ALTER TABLE users DROP COLUMN email;\nDROP TABLE users;\n"
+        );
+        assert_eq!(down.reverse().unwrap().get_sql(), plan.get_sql());
+    }
+
+    #[test]
+    fn test_reverse_plan_with_irreversible_step() {
+        let plan = MigrationPlan::new(vec![
+            add_column_step(),
+            MigrationStep::AddColumn {
+                table: "users".to_string(),
+                column: Column::new("name", DataType::String, false, false, true),
+                sql_script: "ALTER TABLE users ADD COLUMN name TEXT;\n".to_string(),
+                down_sql_script: None,
+            },
+        ]);
+
+        assert_eq!(plan.get_irreversible_steps().len(), 1);
+        assert!(plan.reverse().is_err());
+        assert!(plan.get_down_sql().is_err());
+    }
+
+    #[test]
+    fn test_phases() {
+        let index_step = MigrationStep::AddIndex {
+            table: "users".to_string(),
+            index: Index::new("users_email", &["email"]),
+            concurrently: true,
+            sql_script: "CREATE INDEX CONCURRENTLY users_email ON users (email);\n".to_string(),
+            down_sql_script: Some("DROP INDEX CONCURRENTLY users_email;\n".to_string()),
+        };
+        let plan = MigrationPlan::new(vec![
+            add_column_step(),
+            add_column_step(),
+            index_step.clone(),
+            index_step,
+            add_column_step(),
+        ]);
+
+        let phases = plan.get_phases();
+        assert_eq!(phases.len(), 3);
+        assert!(phases[0].is_transactional());
+        assert_eq!(phases[0].get_steps().len(), 2);
+        assert!(!phases[1].is_transactional());
+        assert_eq!(phases[1].get_steps().len(), 2);
+        assert!(phases[2].is_transactional());
+        assert_eq!(
+            phases[2].get_sql(),
+            "ALTER TABLE users ADD COLUMN email TEXT;\n"
+        );
+        assert!(!plan.reverse().unwrap().get_steps()[1].is_transactional());
+        assert!(MigrationPlan::default().get_phases().is_empty());
+    }
+
+    #[test]
+    fn test_added_enum_labels_are_not_transactional() {
+        let add_labels = MigrationStep::AlterEnum {
+            enum_type: EnumType::new("status", &["new", "held", "paid"]),
+            previous_enum_type: EnumType::new("status", &["new", "paid"]),
+            sql_script: "ALTER TYPE status ADD VALUE 'held' AFTER 'new';\n".to_string(),
+            down_sql_script: Some(
+                "ALTER TYPE status RENAME TO status_old;\n\
+                 CREATE TYPE status AS ENUM ('new', 'paid');\n\
+                 DROP TYPE status_old;\n"
+                    .to_string(),
+            ),
+        };
+        assert!(!add_labels.is_transactional());
+        assert!(add_labels.reverse().unwrap().is_transactional());
+
+        let plan = MigrationPlan::new(vec![add_column_step(), add_labels, add_column_step()]);
+        let phases = plan.get_phases();
+        assert_eq!(phases.len(), 3);
+        assert!(!phases[1].is_transactional());
+        assert_eq!(
+            phases[1].get_sql(),
+            "ALTER TYPE status ADD VALUE 'held' AFTER 'new';\n"
+        );
+    }
 }
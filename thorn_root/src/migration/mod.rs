@@ -1 +1,4 @@
+pub mod dependency_graph;
+pub mod migration_executor;
 pub mod migration_plan;
+pub mod schema_diff;
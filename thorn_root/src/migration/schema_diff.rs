@@ -0,0 +1,495 @@
+use crate::schema::column::Column;
+use crate::schema::constraint::PrimaryKey;
+use crate::schema::enum_type::EnumType;
+use crate::schema::index::Index;
+use crate::schema::relationship::Relationship;
+use crate::schema::table::Table;
+use crate::schema::Schema;
+
+use super::dependency_graph::DependencyGraph;
+
+/// A single structural difference between a current and a desired schema.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaChange {
+    CreateTable(Table),
+    DropTable(Table),
+    AddColumn {
+        table: String,
+        column: Column,
+    },
+    RemoveColumn {
+        table: String,
+        column: Column,
+    },
+    AlterColumn {
+        table: String,
+        from: Column,
+        to: Column,
+    },
+    AddPrimaryKey {
+        table: String,
+        primary_key: PrimaryKey,
+    },
+    DropPrimaryKey {
+        table: String,
+        primary_key: PrimaryKey,
+    },
+    AddIndex {
+        table: String,
+        index: Index,
+    },
+    DropIndex {
+        table: String,
+        index: Index,
+    },
+    AddRelationship(Relationship),
+    DropRelationship(Relationship),
+    CreateEnum(EnumType),
+    DropEnum(EnumType),
+    AlterEnum {
+        from: EnumType,
+        to: EnumType,
+    },
+}
+
+impl SchemaChange {
+    /// Applies the change to a schema, bringing it one step closer to the desired schema.
+    ///
+    /// Engines use this to track the intermediate state of a table while a plan is built,
+    /// e.g. when a table has to be rebuilt with its current set of columns.
+    pub fn apply_to(&self, schema: &mut Schema) -> Result<(), String> {
+        match self {
+            SchemaChange::CreateTable(table) => schema.get_tables_mut().push(table.clone()),
+            SchemaChange::DropTable(table) => {
+                schema.remove_table(table.get_name())?;
+            }
+            // the key is changed by its own steps
+            SchemaChange::AddColumn { table, column } => schema
+                .get_table_mut(table)?
+                .get_columns_mut()
+                .push(column.clone()),
+            SchemaChange::RemoveColumn { table, column } => schema
+                .get_table_mut(table)?
+                .get_columns_mut()
+                .retain(|c| c.get_name() != column.get_name()),
+            SchemaChange::AlterColumn { table, to, .. } => {
+                let table = schema.get_table_mut(table)?;
+                let index = table
+                    .get_columns()
+                    .iter()
+                    .position(|c| c.get_name() == to.get_name())
+                    .ok_or(format!(
+                        "Column {} doesn't not exist in table {}",
+                        to.get_name(),
+                        table.get_name()
+                    ))?;
+                table.get_columns_mut()[index] = to.clone();
+            }
+            SchemaChange::AddPrimaryKey { table, primary_key } => schema
+                .get_table_mut(table)?
+                .set_primary_key(Some(primary_key.clone()))?,
+            SchemaChange::DropPrimaryKey { table, .. } => {
+                schema.get_table_mut(table)?.set_primary_key(None)?
+            }
+            SchemaChange::AddIndex { table, index } => {
+                schema.get_table_mut(table)?.add_index(index.clone())?;
+            }
+            SchemaChange::DropIndex { table, index } => {
+                schema
+                    .get_table_mut(table)?
+                    .remove_index(index.get_name())?;
+            }
+            SchemaChange::AddRelationship(relationship) => {
+                schema.get_relationships_mut().push(relationship.clone())
+            }
+            SchemaChange::DropRelationship(relationship) => {
+                schema.delete_relation(relationship.get_code())?;
+            }
+            SchemaChange::CreateEnum(enum_type) => {
+                schema.add_enum(enum_type.clone())?;
+            }
+            SchemaChange::DropEnum(enum_type) => {
+                schema.remove_enum(enum_type.get_name())?;
+            }
+            SchemaChange::AlterEnum { to, .. } => *schema.get_enum_mut(to.get_name())? = to.clone(),
+        }
+        Ok(())
+    }
+}
+
+/// The ordered list of changes required to turn a current schema into a desired one.
+///
+/// Tables and columns are matched by name, relationships by their code, so a renamed
+/// column shows up as a removal followed by an addition.
+#[derive(Debug, Default, Clone)]
+pub struct SchemaDiff {
+    changes: Vec<SchemaChange>,
+}
+
+impl SchemaDiff {
+    /// Computes the difference between two schemas.
+    ///
+    /// # Arguments
+    /// - `current`: The schema as it exists in the database.
+    /// - `desired`: The schema the database should be migrated to.
+    ///
+    /// # Returns
+    /// - A `SchemaDiff` whose changes are ordered so they can be applied one after another:
+    ///   relationships are dropped first, then tables are dropped, enum types are created and
+    ///   altered before the tables and columns using them, new relationships are added and
+    ///   enum types which are no longer used are dropped last. A changed primary key or index
+    ///   is dropped before the columns of its table change and added back afterwards. The
+    ///   indexes of a new table are part of its `CreateTable` change. Tables are visited in the
+    ///   order of their `DependencyGraph`, so referenced tables are created before and dropped
+    ///   after the tables referencing them, and foreign keys closing a cycle are added last.
+    pub fn new(current: &Schema, desired: &Schema) -> Self {
+        let mut changes = Vec::new();
+
+        for relationship in current.get_relationships() {
+            match desired.get_relationship_with_code(relationship.get_code()) {
+                Ok(desired_relationship) if desired_relationship == relationship => {}
+                _ => changes.push(SchemaChange::DropRelationship(relationship.clone())),
+            }
+        }
+
+        let current_graph = DependencyGraph::new(current);
+        let desired_graph = DependencyGraph::new(desired);
+
+        // referencing tables are dropped before the tables they reference
+        for table_name in current_graph.get_order().iter().rev() {
+            let table = current.get_table(table_name).unwrap();
+            if desired.get_table(table.get_name()).is_err() {
+                changes.push(SchemaChange::DropTable(table.clone()));
+            }
+        }
+
+        for enum_type in desired.get_enums() {
+            match current.get_enum(enum_type.get_name()) {
+                Ok(current_enum) if current_enum == enum_type => {}
+                Ok(current_enum) => changes.push(SchemaChange::AlterEnum {
+                    from: current_enum.clone(),
+                    to: enum_type.clone(),
+                }),
+                Err(_) => changes.push(SchemaChange::CreateEnum(enum_type.clone())),
+            }
+        }
+
+        for table_name in desired_graph.get_order() {
+            let desired_table = desired.get_table(table_name).unwrap();
+            let current_table = match current.get_table(desired_table.get_name()) {
+                Ok(table) => table,
+                Err(_) => {
+                    changes.push(SchemaChange::CreateTable(desired_table.clone()));
+                    continue;
+                }
+            };
+
+            for index in current_table.get_indexes() {
+                match desired_table.get_index(index.get_name()) {
+                    Ok(desired_index) if desired_index == index => {}
+                    _ => changes.push(SchemaChange::DropIndex {
+                        table: desired_table.get_name().to_string(),
+                        index: index.clone(),
+                    }),
+                }
+            }
+
+            let primary_key_changed =
+                current_table.get_primary_key() != desired_table.get_primary_key();
+            if let (true, Some(primary_key)) =
+                (primary_key_changed, current_table.get_primary_key())
+            {
+                changes.push(SchemaChange::DropPrimaryKey {
+                    table: desired_table.get_name().to_string(),
+                    primary_key: primary_key.clone(),
+                });
+            }
+
+            for column in current_table.get_columns() {
+                if desired_table.get_column(column.get_name()).is_err() {
+                    changes.push(SchemaChange::RemoveColumn {
+                        table: desired_table.get_name().to_string(),
+                        column: column.clone(),
+                    });
+                }
+            }
+
+            for column in desired_table.get_columns() {
+                match current_table.get_column(column.get_name()) {
+                    Ok(current_column) if current_column == column => {}
+                    Ok(current_column) => changes.push(SchemaChange::AlterColumn {
+                        table: desired_table.get_name().to_string(),
+                        from: current_column.clone(),
+                        to: column.clone(),
+                    }),
+                    Err(_) => changes.push(SchemaChange::AddColumn {
+                        table: desired_table.get_name().to_string(),
+                        column: column.clone(),
+                    }),
+                }
+            }
+
+            if let (true, Some(primary_key)) =
+                (primary_key_changed, desired_table.get_primary_key())
+            {
+                changes.push(SchemaChange::AddPrimaryKey {
+                    table: desired_table.get_name().to_string(),
+                    primary_key: primary_key.clone(),
+                });
+            }
+
+            for index in desired_table.get_indexes() {
+                match current_table.get_index(index.get_name()) {
+                    Ok(current_index) if current_index == index => {}
+                    _ => changes.push(SchemaChange::AddIndex {
+                        table: desired_table.get_name().to_string(),
+                        index: index.clone(),
+                    }),
+                }
+            }
+        }
+
+        // foreign keys follow the order of their tables, the ones closing a cycle come last
+        let mut relationships = desired.get_relationships().iter().collect::<Vec<_>>();
+        relationships.sort_by_key(|r| {
+            (
+                desired_graph.is_deferred(r),
+                desired_graph.get_position(r.get_to_table()),
+            )
+        });
+        for relationship in relationships {
+            match current.get_relationship_with_code(relationship.get_code()) {
+                Ok(current_relationship) if current_relationship == relationship => {}
+                _ => changes.push(SchemaChange::AddRelationship(relationship.clone())),
+            }
+        }
+
+        for enum_type in current.get_enums() {
+            if desired.get_enum(enum_type.get_name()).is_err() {
+                changes.push(SchemaChange::DropEnum(enum_type.clone()));
+            }
+        }
+
+        Self { changes }
+    }
+
+    pub fn get_changes(&self) -> &[SchemaChange] {
+        &self.changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::data_type::DataType;
+    use crate::schema::index::Index;
+    use crate::schema::relationship::RelationshipType;
+    use crate::test_fixtures::{related_users_and_orders, schema_with_tables};
+
+    use super::*;
+
+    #[test]
+    fn test_identical_schemas_have_no_changes() {
+        let diff = SchemaDiff::new(&related_users_and_orders(), &related_users_and_orders());
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn test_diff_from_empty_schema_creates_everything() {
+        let diff = SchemaDiff::new(&Schema::new(), &related_users_and_orders());
+        let changes = diff.get_changes();
+        assert_eq!(changes.len(), 3);
+        assert!(matches!(&changes[0], SchemaChange::CreateTable(t) if t.get_name() == "users"));
+        assert!(matches!(&changes[1], SchemaChange::CreateTable(t) if t.get_name() == "orders"));
+        assert!(matches!(&changes[2], SchemaChange::AddRelationship(_)));
+    }
+
+    #[test]
+    fn test_diff_column_changes() {
+        let current = related_users_and_orders();
+        let mut desired = related_users_and_orders();
+        let users_table = desired.get_table_mut("users").unwrap();
+        users_table
+            .add_column(Column::new("email", DataType::String, false, false, true))
+            .unwrap();
+        let orders_table = desired.get_table_mut("orders").unwrap();
+        *orders_table.get_columns_mut()[0].get_data_type_mut() = DataType::String;
+
+        let diff = SchemaDiff::new(&current, &desired);
+        let changes = diff.get_changes();
+        assert_eq!(changes.len(), 2);
+        assert!(matches!(
+            &changes[0],
+            SchemaChange::AddColumn { table, column } if table == "users" && column.get_name() == "email"
+        ));
+        assert!(matches!(
+            &changes[1],
+            SchemaChange::AlterColumn { table, from, to }
+                if table == "orders"
+                    && from.get_data_type() == DataType::Integer
+                    && to.get_data_type() == DataType::String
+        ));
+    }
+
+    #[test]
+    fn test_diff_drops_relationship_before_table() {
+        let current = related_users_and_orders();
+        let mut desired = related_users_and_orders();
+        let code = desired.get_relationships()[0].get_code().to_string();
+        desired.delete_relation(&code).unwrap();
+        desired.remove_table("users").unwrap();
+
+        let diff = SchemaDiff::new(&current, &desired);
+        let changes = diff.get_changes();
+        assert_eq!(changes.len(), 2);
+        assert!(matches!(&changes[0], SchemaChange::DropRelationship(r) if r.get_code() == code));
+        assert!(matches!(&changes[1], SchemaChange::DropTable(t) if t.get_name() == "users"));
+    }
+
+    #[test]
+    fn test_applying_changes_yields_desired_schema() {
+        let current = related_users_and_orders();
+        let mut desired = related_users_and_orders();
+        let code = desired.get_relationships()[0].get_code().to_string();
+        desired.delete_relation(&code).unwrap();
+        let orders_table = desired.get_table_mut("orders").unwrap();
+        *orders_table.get_columns_mut()[1].get_data_type_mut() = DataType::String;
+        orders_table
+            .add_column(Column::new("total", DataType::Float, false, false, true))
+            .unwrap();
+
+        let mut working = current.clone();
+        for change in SchemaDiff::new(&current, &desired).get_changes() {
+            change.apply_to(&mut working).unwrap();
+        }
+        assert!(SchemaDiff::new(&working, &desired).is_empty());
+    }
+
+    #[test]
+    fn test_diff_enums() {
+        let mut current = related_users_and_orders();
+        current
+            .add_enum(EnumType::new("status", &["new", "paid"]))
+            .unwrap();
+        current.add_enum(EnumType::new("legacy", &["a"])).unwrap();
+
+        let mut desired = related_users_and_orders();
+        desired
+            .add_enum(EnumType::new("status", &["new", "paid", "refunded"]))
+            .unwrap();
+        desired
+            .add_enum(EnumType::new("priority", &["low", "high"]))
+            .unwrap();
+        desired
+            .get_table_mut("orders")
+            .unwrap()
+            .add_column(Column::new(
+                "priority",
+                DataType::Enum("priority".to_string()),
+                false,
+                false,
+                false,
+            ))
+            .unwrap();
+
+        let diff = SchemaDiff::new(&current, &desired);
+        let changes = diff.get_changes();
+        assert_eq!(changes.len(), 4);
+        assert!(
+            matches!(&changes[0], SchemaChange::AlterEnum { to, .. } if to.get_labels().len() == 3)
+        );
+        assert!(matches!(&changes[1], SchemaChange::CreateEnum(e) if e.get_name() == "priority"));
+        assert!(matches!(&changes[2], SchemaChange::AddColumn { .. }));
+        assert!(matches!(&changes[3], SchemaChange::DropEnum(e) if e.get_name() == "legacy"));
+
+        let mut working = current.clone();
+        for change in changes {
+            change.apply_to(&mut working).unwrap();
+        }
+        assert!(SchemaDiff::new(&working, &desired).is_empty());
+    }
+
+    #[test]
+    fn test_diff_indexes() {
+        let mut current = related_users_and_orders();
+        let orders_table = current.get_table_mut("orders").unwrap();
+        orders_table
+            .add_index(Index::new("orders_user_id", &["user_id"]))
+            .unwrap();
+        orders_table
+            .add_index(Index::new("orders_legacy", &["id"]))
+            .unwrap();
+
+        let mut desired = related_users_and_orders();
+        let mut unique = Index::new("orders_user_id", &["user_id"]);
+        unique.set_unique(true);
+        desired
+            .get_table_mut("orders")
+            .unwrap()
+            .add_index(unique)
+            .unwrap();
+
+        let diff = SchemaDiff::new(&current, &desired);
+        let changes = diff.get_changes();
+        assert_eq!(changes.len(), 3);
+        assert!(
+            matches!(&changes[0], SchemaChange::DropIndex { index, .. } if index.get_name() == "orders_user_id")
+        );
+        assert!(
+            matches!(&changes[1], SchemaChange::DropIndex { index, .. } if index.get_name() == "orders_legacy")
+        );
+        assert!(matches!(&changes[2], SchemaChange::AddIndex { index, .. } if index.is_unique()));
+
+        let mut working = current.clone();
+        for change in changes {
+            change.apply_to(&mut working).unwrap();
+        }
+        assert!(SchemaDiff::new(&working, &desired).is_empty());
+    }
+
+    #[test]
+    fn test_diff_follows_dependencies() {
+        let mut desired = schema_with_tables(&["orders", "users"]);
+        for (from_table, to_table) in [("orders", "users"), ("users", "orders")] {
+            desired
+                .add_relation(Relationship::new(
+                    from_table,
+                    "id",
+                    to_table,
+                    "ref_id",
+                    RelationshipType::OneToMany,
+                ))
+                .unwrap();
+        }
+        desired.delete_relation("orders_id_users_ref_id").unwrap();
+
+        let diff = SchemaDiff::new(&Schema::new(), &desired);
+        let changes = diff.get_changes();
+        assert!(matches!(&changes[0], SchemaChange::CreateTable(t) if t.get_name() == "users"));
+        assert!(matches!(&changes[1], SchemaChange::CreateTable(t) if t.get_name() == "orders"));
+
+        let diff = SchemaDiff::new(&desired, &Schema::new());
+        let changes = diff.get_changes();
+        assert!(matches!(&changes[1], SchemaChange::DropTable(t) if t.get_name() == "orders"));
+        assert!(matches!(&changes[2], SchemaChange::DropTable(t) if t.get_name() == "users"));
+
+        desired
+            .add_relation(Relationship::new(
+                "orders",
+                "id",
+                "users",
+                "ref_id",
+                RelationshipType::OneToMany,
+            ))
+            .unwrap();
+        let diff = SchemaDiff::new(&Schema::new(), &desired);
+        let changes = diff.get_changes();
+        assert!(matches!(&changes[0], SchemaChange::CreateTable(t) if t.get_name() == "orders"));
+        assert!(
+            matches!(&changes[3], SchemaChange::AddRelationship(r) if r.get_code() == "users_id_orders_ref_id")
+        );
+    }
+}
@@ -1,12 +1,20 @@
+use super::constraint::{CheckConstraint, DefaultValue};
 use super::data_type::DataType;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Column {
     name: String,
     data_type: DataType,
     primary_key: bool,
     foreign_key: bool,
     nullable: bool,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    default: Option<DefaultValue>,
+    unique: bool,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    checks: Vec<CheckConstraint>,
 }
 
 impl Default for Column {
@@ -17,6 +25,9 @@ impl Default for Column {
             primary_key: Default::default(),
             foreign_key: Default::default(),
             nullable: Default::default(),
+            default: Default::default(),
+            unique: Default::default(),
+            checks: Default::default(),
         }
     }
 }
@@ -35,9 +46,35 @@ impl Column {
             primary_key,
             foreign_key,
             nullable,
+            default: None,
+            unique: false,
+            checks: vec![],
         }
     }
 
+    /// Creates a column after checking the parameters of its data type.
+    ///
+    /// # Returns
+    /// - A `Result` containing the column, or an error message if the data type is invalid.
+    pub fn try_new(
+        name: &str,
+        data_type: DataType,
+        primary_key: bool,
+        foreign_key: bool,
+        nullable: bool,
+    ) -> Result<Self, String> {
+        data_type
+            .validate()
+            .map_err(|e| format!("Column {}: {}", name, e))?;
+        Ok(Self::new(
+            name,
+            data_type,
+            primary_key,
+            foreign_key,
+            nullable,
+        ))
+    }
+
     pub fn get_name(&self) -> &str {
         &self.name
     }
@@ -58,6 +95,36 @@ impl Column {
         self.nullable
     }
 
+    pub fn get_default(&self) -> Option<&DefaultValue> {
+        self.default.as_ref()
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+
+    pub fn get_checks(&self) -> &[CheckConstraint] {
+        &self.checks
+    }
+
+    /// Returns the name of the column's `UNIQUE` constraint within `table`.
+    pub fn get_unique_constraint_name(&self, table: &str) -> String {
+        format!("{}_{}_key", table, self.name)
+    }
+
+    /// Adds a `CHECK` constraint, or fails if the column already has one with the same name.
+    pub fn add_check(&mut self, check: CheckConstraint) -> Result<&CheckConstraint, String> {
+        if self.checks.iter().any(|c| c.get_name() == check.get_name()) {
+            return Err(format!(
+                "Column {} already has a check constraint {}",
+                self.name,
+                check.get_name()
+            ));
+        }
+        self.checks.push(check);
+        Ok(self.checks.last().unwrap())
+    }
+
     pub fn get_name_mut(&mut self) -> &mut String {
         &mut self.name
     }
@@ -77,4 +144,16 @@ impl Column {
     pub fn is_nullable_mut(&mut self) -> &mut bool {
         &mut self.nullable
     }
+
+    pub fn get_default_mut(&mut self) -> &mut Option<DefaultValue> {
+        &mut self.default
+    }
+
+    pub fn is_unique_mut(&mut self) -> &mut bool {
+        &mut self.unique
+    }
+
+    pub fn get_checks_mut(&mut self) -> &mut Vec<CheckConstraint> {
+        &mut self.checks
+    }
 }
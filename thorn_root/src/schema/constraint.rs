@@ -0,0 +1,123 @@
+/// Value a column takes when a row is inserted without it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DefaultValue {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    /// A string literal, quoted when rendered.
+    String(String),
+    /// An SQL expression rendered as it is, e.g. `CURRENT_TIMESTAMP` or `now()`.
+    Expression(String),
+}
+
+impl DefaultValue {
+    /// Renders the default in standard SQL, with booleans as `TRUE` and `FALSE`.
+    pub fn to_sql(&self) -> String {
+        match self {
+            DefaultValue::Null => "NULL".to_string(),
+            DefaultValue::Boolean(true) => "TRUE".to_string(),
+            DefaultValue::Boolean(false) => "FALSE".to_string(),
+            DefaultValue::Integer(value) => value.to_string(),
+            DefaultValue::Float(value) => value.to_string(),
+            DefaultValue::String(value) => format!("'{}'", value.replace('\'', "''")),
+            DefaultValue::Expression(expression) => expression.clone(),
+        }
+    }
+}
+
+/// Named `CHECK` constraint of a column, so later migrations can drop it by its name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CheckConstraint {
+    name: String,
+    expression: String,
+}
+
+impl CheckConstraint {
+    pub fn new(name: &str, expression: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            expression: expression.to_string(),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Getter for `expression`, the boolean SQL expression rows must satisfy.
+    pub fn get_expression(&self) -> &str {
+        &self.expression
+    }
+}
+
+/// Primary key of a table: an ordered list of columns and an optional constraint name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrimaryKey {
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    name: Option<String>,
+    columns: Vec<String>,
+}
+
+impl PrimaryKey {
+    pub fn new(columns: &[&str]) -> Self {
+        Self {
+            name: None,
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    /// Creates a primary key whose constraint gets the given name instead of the database's default.
+    pub fn with_name(name: &str, columns: &[&str]) -> Self {
+        Self {
+            name: Some(name.to_string()),
+            ..Self::new(columns)
+        }
+    }
+
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn get_columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn contains(&self, column_name: &str) -> bool {
+        self.columns.iter().any(|c| c == column_name)
+    }
+
+    /// Whether the key can be declared on its column, i.e. it is unnamed and has a single column.
+    pub fn is_inline(&self) -> bool {
+        self.name.is_none() && self.columns.len() == 1
+    }
+
+    pub(crate) fn push_column(&mut self, column_name: &str) {
+        self.columns.push(column_name.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_value_to_sql() {
+        assert_eq!(DefaultValue::Null.to_sql(), "NULL");
+        assert_eq!(DefaultValue::Boolean(true).to_sql(), "TRUE");
+        assert_eq!(DefaultValue::Integer(-3).to_sql(), "-3");
+        assert_eq!(DefaultValue::Float(1.5).to_sql(), "1.5");
+        assert_eq!(DefaultValue::String("it's".to_string()).to_sql(), "'it''s'");
+        assert_eq!(
+            DefaultValue::Expression("CURRENT_TIMESTAMP".to_string()).to_sql(),
+            "CURRENT_TIMESTAMP"
+        );
+    }
+}
@@ -1,34 +1,76 @@
 use strum_macros::EnumIter;
 
-use crate::database::{engine::DatabaseEngine, postgres_engine::PostgresEngine};
+/// Largest fractional-seconds precision accepted for `Timestamp` and `Time`.
+pub const MAX_TIME_PRECISION: u8 = 6;
 
-#[derive(Debug, Clone, PartialEq, Eq, EnumIter)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, EnumIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum DataType {
+    #[default]
     Integer,
     Float,
     String,
     Boolean,
     Date,
+    SmallInt,
+    BigInt,
+    /// Exact numeric with `precision` significant digits, `scale` of them after the point.
+    Decimal {
+        precision: u16,
+        scale: u16,
+    },
+    /// String of at most the given number of characters.
+    Varchar(u32),
+    /// Date and time, with an optional fractional-seconds precision.
+    Timestamp {
+        precision: Option<u8>,
+        with_time_zone: bool,
+    },
+    /// Time of day, with an optional fractional-seconds precision.
+    Time {
+        precision: Option<u8>,
+        with_time_zone: bool,
+    },
+    Uuid,
+    Json,
+    /// JSON stored in a decomposed binary format which can be indexed.
+    Jsonb,
+    Binary,
+    /// A user-defined enum type of the schema, referred to by its name.
+    Enum(String),
+    /// Variable-length array of the element type.
+    Array(Box<DataType>),
 }
+
 impl DataType {
-    pub fn to_db_type(&self, db: &str) -> Option<&'static str> {
-        let _postgres_value = PostgresEngine::name();
-        match db {
-            _postgres_value => match self {
-                DataType::Integer => Some("INTEGER"),
-                DataType::Float => Some("DOUBLE PRECISION"),
-                DataType::String => Some("TEXT"),
-                DataType::Boolean => Some("BOOLEAN"),
-                DataType::Date => Some("DATE"),
-            },
-            #[allow(unreachable_patterns)]
-            "oracle" => match self {
-                DataType::Integer => Some("NUMBER"),
-                DataType::Float => Some("FLOAT"),
-                DataType::String => Some("VARCHAR2"),
-                DataType::Boolean => Some("NUMBER(1)"),
-                DataType::Date => Some("DATE"),
-            },
+    /// Checks the parameters of the data type.
+    ///
+    /// # Returns
+    /// - `Ok(())` if the data type is valid, or an error message describing the invalid parameter.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            DataType::Decimal { precision: 0, .. } => {
+                Err("Decimal precision must be greater than 0".to_string())
+            }
+            DataType::Decimal { precision, scale } if scale > precision => Err(format!(
+                "Decimal scale {} can't be greater than precision {}",
+                scale, precision
+            )),
+            DataType::Varchar(0) => Err("Varchar length must be greater than 0".to_string()),
+            DataType::Timestamp {
+                precision: Some(precision),
+                ..
+            }
+            | DataType::Time {
+                precision: Some(precision),
+                ..
+            } if *precision > MAX_TIME_PRECISION => Err(format!(
+                "Time precision {} can't be greater than {}",
+                precision, MAX_TIME_PRECISION
+            )),
+            DataType::Array(element_type) => element_type.validate(),
+            _ => Ok(()),
         }
     }
 }
@@ -41,7 +83,46 @@ mod tests {
     #[test]
     fn test_iterate() {
         for (index, enum_value) in DataType::iter().enumerate() {
-            assert_eq!(index, enum_value as usize);
+            assert_eq!(DataType::iter().position(|d| d == enum_value), Some(index));
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(DataType::Integer.validate().is_ok());
+        assert!(DataType::Decimal {
+            precision: 10,
+            scale: 2
+        }
+        .validate()
+        .is_ok());
+        assert!(DataType::Decimal {
+            precision: 2,
+            scale: 10
+        }
+        .validate()
+        .is_err());
+        assert!(DataType::Decimal {
+            precision: 0,
+            scale: 0
+        }
+        .validate()
+        .is_err());
+        assert!(DataType::Varchar(0).validate().is_err());
+        assert!(DataType::Timestamp {
+            precision: Some(7),
+            with_time_zone: true
+        }
+        .validate()
+        .is_err());
+        assert!(DataType::Time {
+            precision: None,
+            with_time_zone: false
         }
+        .validate()
+        .is_ok());
+        assert!(DataType::Array(Box::new(DataType::Varchar(0)))
+            .validate()
+            .is_err());
     }
 }
@@ -0,0 +1,88 @@
+/// A named, user-defined type whose values are one of an ordered list of labels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnumType {
+    name: String,
+    labels: Vec<String>,
+}
+
+impl EnumType {
+    pub fn new(name: &str, labels: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+        }
+    }
+
+    /// Getter for `name`
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Getter for `labels`
+    pub fn get_labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    /// Appends a label, or fails if the enum already has it.
+    pub fn add_label(&mut self, label: &str) -> Result<(), String> {
+        if self.labels.iter().any(|l| l == label) {
+            return Err(format!("Enum {} already has a label {}", self.name, label));
+        }
+        self.labels.push(label.to_string());
+        Ok(())
+    }
+
+    /// Removes a label, or fails if the enum doesn't have it.
+    pub fn remove_label(&mut self, label: &str) -> Result<(), String> {
+        let index = self
+            .labels
+            .iter()
+            .position(|l| l == label)
+            .ok_or(format!("Enum {} doesn't have a label {}", self.name, label))?;
+        self.labels.remove(index);
+        Ok(())
+    }
+
+    /// Checks that the enum has at least one label and no duplicates.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.labels.is_empty() {
+            return Err(format!("Enum {} must have at least one label", self.name));
+        }
+        for (index, label) in self.labels.iter().enumerate() {
+            if self.labels[..index].contains(label) {
+                return Err(format!(
+                    "Enum {} has a duplicate label {}",
+                    self.name, label
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `other` only appends or inserts labels, keeping the order of the existing ones.
+    pub fn is_extended_by(&self, other: &EnumType) -> bool {
+        let mut other_labels = other.labels.iter();
+        self.labels
+            .iter()
+            .all(|label| other_labels.any(|other_label| other_label == label))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_extended_by() {
+        let status = EnumType::new("status", &["new", "paid"]);
+        assert!(status.is_extended_by(&EnumType::new("status", &["new", "paid", "refunded"])));
+        assert!(status.is_extended_by(&EnumType::new("status", &["draft", "new", "paid"])));
+        assert!(!status.is_extended_by(&EnumType::new("status", &["paid", "new"])));
+        assert!(!status.is_extended_by(&EnumType::new("status", &["new"])));
+    }
+}
@@ -0,0 +1,173 @@
+/// Direction in which an index sorts the values of a column.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Whether an index sorts NULLs before or after the other values of a column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+/// Data structure the database uses for an index.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum IndexMethod {
+    #[default]
+    #[cfg_attr(feature = "serde", serde(rename = "btree"))]
+    BTree,
+    Hash,
+    /// Inverted index for composite values such as arrays and JSONB.
+    Gin,
+    Gist,
+    Brin,
+}
+
+impl IndexMethod {
+    /// Renders the method as it follows `USING`.
+    pub fn to_sql(&self) -> &'static str {
+        match self {
+            IndexMethod::BTree => "btree",
+            IndexMethod::Hash => "hash",
+            IndexMethod::Gin => "gin",
+            IndexMethod::Gist => "gist",
+            IndexMethod::Brin => "brin",
+        }
+    }
+}
+
+/// A column of an index together with the order of its values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IndexColumn {
+    name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    order: SortOrder,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    nulls: Option<NullsOrder>,
+}
+
+impl IndexColumn {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            order: SortOrder::default(),
+            nulls: None,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_order(&self) -> SortOrder {
+        self.order.to_owned()
+    }
+
+    /// Getter for `nulls`, `None` if NULLs are placed where the database puts them by default
+    pub fn get_nulls(&self) -> Option<NullsOrder> {
+        self.nulls.to_owned()
+    }
+
+    pub fn set_order(&mut self, order: SortOrder) {
+        self.order = order;
+    }
+
+    pub fn set_nulls(&mut self, nulls: Option<NullsOrder>) {
+        self.nulls = nulls;
+    }
+}
+
+/// A named index over ordered columns of a table, optionally unique, partial or covering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Index {
+    name: String,
+    columns: Vec<IndexColumn>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    unique: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    predicate: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    method: IndexMethod,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    include: Vec<String>,
+}
+
+impl Index {
+    /// Creates a non-unique btree index over the columns, sorted ascending.
+    pub fn new(name: &str, columns: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            columns: columns.iter().map(|c| IndexColumn::new(c)).collect(),
+            unique: false,
+            predicate: None,
+            method: IndexMethod::default(),
+            include: vec![],
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_columns(&self) -> &[IndexColumn] {
+        &self.columns
+    }
+
+    pub fn get_columns_mut(&mut self) -> &mut Vec<IndexColumn> {
+        &mut self.columns
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+
+    /// Getter for `predicate`, the SQL condition of a partial index which only covers matching rows
+    pub fn get_predicate(&self) -> Option<&str> {
+        self.predicate.as_deref()
+    }
+
+    pub fn get_method(&self) -> IndexMethod {
+        self.method.to_owned()
+    }
+
+    /// Getter for `include`, the columns stored in the index without being part of its key
+    pub fn get_include(&self) -> &[String] {
+        &self.include
+    }
+
+    pub fn set_unique(&mut self, unique: bool) {
+        self.unique = unique;
+    }
+
+    pub fn set_predicate(&mut self, predicate: Option<&str>) {
+        self.predicate = predicate.map(|p| p.to_string());
+    }
+
+    pub fn set_method(&mut self, method: IndexMethod) {
+        self.method = method;
+    }
+
+    pub fn set_include(&mut self, include: &[&str]) {
+        self.include = include.iter().map(|c| c.to_string()).collect();
+    }
+}
@@ -1,17 +1,36 @@
-use relationship::Relationship;
+use column::Column;
+use data_type::DataType;
+use enum_type::EnumType;
+use relationship::{Cardinality, ReferentialAction, Relationship, RelationshipType};
 use table::Table;
 
 pub mod column;
+pub mod constraint;
 pub mod data_type;
+pub mod enum_type;
+pub mod index;
 pub mod relationship;
+#[cfg(feature = "serde")]
+mod serialization;
 pub mod table;
 
+#[cfg(feature = "serde")]
+pub use serialization::FORMAT_VERSION;
+
 /// Represents the schema of a database, which includes tables and relationships.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "serialization::SchemaDocument", try_from = "serde_json::Value")
+)]
 pub struct Schema {
     /// The collection of tables in the schema.
     tables: Vec<Table>,
     /// The collection of relationships between tables in the schema.
     relations: Vec<Relationship>,
+    /// The collection of user-defined enum types columns can refer to.
+    enums: Vec<EnumType>,
 }
 
 impl Default for Schema {
@@ -30,6 +49,7 @@ impl Schema {
         Self {
             tables: Vec::new(),
             relations: Vec::new(),
+            enums: Vec::new(),
         }
     }
 
@@ -219,7 +239,9 @@ impl Schema {
     /// - `relationship`: The `Relationship` to add.
     ///
     /// # Returns
-    /// - A `Result` containing a reference to the newly added relationship, or an error message if the relationship already exists.
+    /// - A `Result` containing a reference to the newly added relationship, or an error message if the relationship
+    ///   already exists, its column lists differ in length, paired columns have different data types
+    ///   or a `SetNull` action targets a column which isn't nullable.
     pub fn add_relation(&mut self, relationship: Relationship) -> Result<&Relationship, String> {
         let from_table = self.get_table(relationship.get_from_table());
 
@@ -237,29 +259,460 @@ impl Schema {
 
         let to_table = to_table.unwrap();
 
-        let from_column = from_table.get_column(relationship.get_from_column());
+        if relationship.is_initially_deferred() && !relationship.is_deferrable() {
+            return Err(format!(
+                "Relationship {} can't be initially deferred without being deferrable",
+                relationship.get_code()
+            ));
+        }
+
+        if relationship.is_many_to_many() {
+            return self.add_many_to_many_relation(relationship);
+        }
+
+        if relationship.get_from_columns().is_empty()
+            || relationship.get_from_columns().len() != relationship.get_to_columns().len()
+        {
+            return Err(format!(
+                "Relationship {} must have the same, non-zero number of columns on both sides",
+                relationship.get_code()
+            ));
+        }
+
+        let column_pairs = relationship
+            .get_from_columns()
+            .iter()
+            .zip(relationship.get_to_columns());
+        for (from_column_name, to_column_name) in column_pairs {
+            let from_column = from_table.get_column(from_column_name);
+
+            if from_column.is_err() {
+                return Err("`From column` must be defined".to_string());
+            }
+
+            let to_column = to_table.get_column(to_column_name);
+
+            if to_column.is_err() {
+                return Err("`To column` must be defined".to_string());
+            }
 
-        if from_column.is_err() {
-            return Err("`From column` must be defined".to_string());
+            let set_null = relationship.get_on_delete() == ReferentialAction::SetNull
+                || relationship.get_on_update() == ReferentialAction::SetNull;
+            if set_null && !to_column.as_ref()?.is_nullable() {
+                return Err(format!(
+                    "Relationship {} sets column {} of table {} to NULL, but the column isn't nullable",
+                    relationship.get_code(),
+                    to_column_name,
+                    to_table.get_name()
+                ));
+            }
+
+            if from_column?.get_data_type() != to_column?.get_data_type() {
+                return Err(format!(
+                    "Column {} of table {} and column {} of table {} have different data types",
+                    from_column_name,
+                    from_table.get_name(),
+                    to_column_name,
+                    to_table.get_name()
+                ));
+            }
         }
 
-        let to_column = to_table.get_column(relationship.get_to_column());
+        if self
+            .get_relationship_with_code(relationship.get_code())
+            .is_ok()
+        {
+            return Err("Such a relationship is already created".to_string());
+        }
 
-        if to_column.is_err() {
-            return Err("`To column` must be defined".to_string());
+        if relationship.get_relationship_type() == RelationshipType::OneToOne {
+            let to_table = self.get_table_mut(relationship.get_to_table())?;
+            if !to_table.is_unique_key(relationship.get_to_columns()) {
+                let [to_column_name] = relationship.get_to_columns() else {
+                    return Err(format!(
+                        "One-to-one relationship {} needs the primary key of table {} among its columns",
+                        relationship.get_code(),
+                        to_table.get_name()
+                    ));
+                };
+                let to_column = to_table
+                    .get_columns_mut()
+                    .iter_mut()
+                    .find(|c| c.get_name() == to_column_name)
+                    .unwrap();
+                *to_column.is_unique_mut() = true;
+            }
         }
 
+        self.relations.push(relationship);
+        Ok(self.relations.last().unwrap())
+    }
+
+    /// Adds a many-to-many relationship. Its junction table is created with a column per referenced
+    /// column of both sides (named `{table}_{column}`) and a primary key over all of them, or checked
+    /// for these columns if it already exists. The junction table gets a one-to-many relationship from
+    /// each side, deleting its rows together with the rows they link.
+    ///
+    /// # Arguments
+    /// - `relationship`: The many-to-many `Relationship` to add.
+    ///
+    /// # Returns
+    /// - A `Result` containing a reference to the newly added relationship, or an error message if the
+    ///   relationship already exists or an existing junction table doesn't match it.
+    fn add_many_to_many_relation(
+        &mut self,
+        relationship: Relationship,
+    ) -> Result<&Relationship, String> {
         if self
             .get_relationship_with_code(relationship.get_code())
             .is_ok()
         {
             return Err("Such a relationship is already created".to_string());
         }
+        if relationship.get_from_table() == relationship.get_to_table() {
+            return Err(format!(
+                "Many-to-many relationship {} must link two different tables",
+                relationship.get_code()
+            ));
+        }
+
+        let junction_name = relationship.get_junction_table_name();
+        let mut junction_columns = Vec::<Column>::new();
+        let mut sides = Vec::new();
+        for (table_name, column_names) in [
+            (
+                relationship.get_from_table(),
+                relationship.get_from_columns(),
+            ),
+            (relationship.get_to_table(), relationship.get_to_columns()),
+        ] {
+            if column_names.is_empty() {
+                return Err(format!(
+                    "Relationship {} must name the referenced columns of table {}",
+                    relationship.get_code(),
+                    table_name
+                ));
+            }
+            let table = self.get_table(table_name)?;
+            let mut junction_column_names = Vec::new();
+            for column_name in column_names {
+                let column = table.get_column(column_name)?;
+                let junction_column_name = format!("{}_{}", table_name, column_name);
+                junction_columns.push(Column::new(
+                    &junction_column_name,
+                    column.get_data_type(),
+                    true,
+                    true,
+                    false,
+                ));
+                junction_column_names.push(junction_column_name);
+            }
+            sides.push((table_name, column_names, junction_column_names));
+        }
+
+        // everything is checked and built before the schema is changed, so a failure leaves
+        // it as it was
+        let new_junction = match self.get_table(&junction_name) {
+            Ok(junction) => {
+                for expected in junction_columns.iter() {
+                    let column = junction.get_column(expected.get_name())?;
+                    if column.get_data_type() != expected.get_data_type() {
+                        return Err(format!(
+                            "Column {} of junction table {} must have the data type {:?}",
+                            column.get_name(),
+                            junction_name,
+                            expected.get_data_type()
+                        ));
+                    }
+                }
+                let mut key_columns = junction
+                    .get_primary_key()
+                    .map(|p| p.get_columns().to_vec())
+                    .unwrap_or_default();
+                let mut expected_key_columns = junction_columns
+                    .iter()
+                    .map(|c| c.get_name().to_string())
+                    .collect::<Vec<String>>();
+                key_columns.sort();
+                expected_key_columns.sort();
+                if key_columns != expected_key_columns {
+                    return Err(format!(
+                        "Junction table {} must have a primary key over the columns {}",
+                        junction_name,
+                        expected_key_columns.join(", ")
+                    ));
+                }
+                None
+            }
+            Err(_) => {
+                let mut junction = Table::new(&junction_name);
+                for column in junction_columns {
+                    junction.add_column(column)?;
+                }
+                Some(junction)
+            }
+        };
+
+        // the columns of the junction table match the referenced ones, so these relationships
+        // need no further checks
+        let mut underlying_relationships = Vec::new();
+        for (table_name, column_names, junction_column_names) in sides {
+            let column_names = column_names
+                .iter()
+                .map(|c| c.as_str())
+                .collect::<Vec<&str>>();
+            let junction_column_names = junction_column_names
+                .iter()
+                .map(|c| c.as_str())
+                .collect::<Vec<&str>>();
+            let mut underlying = Relationship::new_composite(
+                table_name,
+                &column_names,
+                &junction_name,
+                &junction_column_names,
+                RelationshipType::OneToMany,
+            );
+            underlying.set_on_delete(ReferentialAction::Cascade);
+            if self
+                .get_relationship_with_code(underlying.get_code())
+                .is_err()
+            {
+                underlying_relationships.push(underlying);
+            }
+        }
 
+        self.tables.extend(new_junction);
+        self.relations.extend(underlying_relationships);
         self.relations.push(relationship);
         Ok(self.relations.last().unwrap())
     }
 
+    /// Retrieves all enum types in the schema.
+    ///
+    /// # Returns
+    /// - A slice of all enum types in the schema.
+    pub fn get_enums(&self) -> &[EnumType] {
+        &self.enums
+    }
+
+    /// Retrieves an enum type by its name.
+    ///
+    /// # Arguments
+    /// - `enum_name`: The name of the enum type to retrieve.
+    ///
+    /// # Returns
+    /// - A `Result` containing a reference to the `EnumType` if found, or an error message.
+    pub fn get_enum(&self, enum_name: &str) -> Result<&EnumType, String> {
+        match self.enums.iter().find(|e| e.get_name() == enum_name) {
+            Some(enum_type) => Ok(enum_type),
+            None => Err(format!("Enum with name {} doesn't exist", enum_name)),
+        }
+    }
+
+    /// Retrieves a mutable reference to an enum type by its name.
+    ///
+    /// # Arguments
+    /// - `enum_name`: The name of the enum type to retrieve.
+    ///
+    /// # Returns
+    /// - A `Result` containing a mutable reference to the `EnumType` if found, or an error message.
+    pub fn get_enum_mut(&mut self, enum_name: &str) -> Result<&mut EnumType, String> {
+        match self.enums.iter_mut().find(|e| e.get_name() == enum_name) {
+            Some(enum_type) => Ok(enum_type),
+            None => Err(format!("Enum with name {} doesn't exist", enum_name)),
+        }
+    }
+
+    /// Adds a new enum type to the schema.
+    ///
+    /// # Arguments
+    /// - `enum_type`: The `EnumType` to add.
+    ///
+    /// # Returns
+    /// - A `Result` containing a reference to the newly added enum type, or an error message if
+    ///   an enum type with the same name already exists or the labels are invalid.
+    pub fn add_enum(&mut self, enum_type: EnumType) -> Result<&EnumType, String> {
+        if self.get_enum(enum_type.get_name()).is_ok() {
+            return Err(format!(
+                "Enum with name {} already exists",
+                enum_type.get_name()
+            ));
+        }
+        enum_type.validate()?;
+        self.enums.push(enum_type);
+        Ok(self.enums.last().unwrap())
+    }
+
+    /// Removes an enum type from the schema.
+    ///
+    /// # Arguments
+    /// - `enum_name`: The name of the enum type to remove.
+    ///
+    /// # Returns
+    /// - A `Result` containing the removed enum type, or an error message if it doesn't exist
+    ///   or a column still uses it.
+    pub fn remove_enum(&mut self, enum_name: &str) -> Result<EnumType, String> {
+        let index = self
+            .enums
+            .iter()
+            .position(|e| e.get_name() == enum_name)
+            .ok_or(format!("Enum with name {} doesn't exist", enum_name))?;
+        for table in self.tables.iter() {
+            for column in table.get_columns() {
+                if column.get_data_type() == DataType::Enum(enum_name.to_string()) {
+                    return Err(format!(
+                        "Enum {} is used by column {} of table {}",
+                        enum_name,
+                        column.get_name(),
+                        table.get_name()
+                    ));
+                }
+            }
+        }
+        Ok(self.enums.remove(index))
+    }
+
+    /// Checks the parts of the schema which can't be checked when they are added,
+    /// e.g. columns referring to enum types which don't exist, check constraints
+    /// sharing a name within a table, primary key flags out of sync with the table's key
+    /// one-to-one relationships whose referencing columns are no longer unique,
+    /// or indexes on removed columns and indexes sharing a name.
+    ///
+    /// # Returns
+    /// - `Ok(())` if the schema is consistent, or an error message describing the first problem found.
+    pub fn validate(&self) -> Result<(), String> {
+        for enum_type in self.enums.iter() {
+            enum_type.validate()?;
+        }
+        for table in self.tables.iter() {
+            if let Some(primary_key) = table.get_primary_key() {
+                for column_name in primary_key.get_columns() {
+                    table.get_column(column_name)?;
+                }
+            }
+            let mut check_names = Vec::<&str>::new();
+            for column in table.get_columns() {
+                let in_primary_key = table
+                    .get_primary_key()
+                    .is_some_and(|p| p.contains(column.get_name()));
+                if column.is_primary_key() != in_primary_key {
+                    return Err(format!(
+                        "Primary key flag of column {} doesn't match the primary key of table {}",
+                        column.get_name(),
+                        table.get_name()
+                    ));
+                }
+                for check in column.get_checks() {
+                    if check.get_expression().trim().is_empty() {
+                        return Err(format!(
+                            "Check constraint {} of table {} has no expression",
+                            check.get_name(),
+                            table.get_name()
+                        ));
+                    }
+                    if check_names.contains(&check.get_name()) {
+                        return Err(format!(
+                            "Table {} has more than one check constraint named {}",
+                            table.get_name(),
+                            check.get_name()
+                        ));
+                    }
+                    check_names.push(check.get_name());
+                }
+                if let DataType::Enum(enum_name) = column.get_data_type() {
+                    if self.get_enum(&enum_name).is_err() {
+                        return Err(format!(
+                            "Column {} of table {} uses enum {} which doesn't exist",
+                            column.get_name(),
+                            table.get_name(),
+                            enum_name
+                        ));
+                    }
+                }
+            }
+        }
+        let mut index_names = Vec::<&str>::new();
+        for table in self.tables.iter() {
+            for index in table.get_indexes() {
+                table.check_index(index)?;
+                if index_names.contains(&index.get_name()) {
+                    return Err(format!("More than one index is named {}", index.get_name()));
+                }
+                index_names.push(index.get_name());
+            }
+        }
+        for relationship in self.relations.iter() {
+            if relationship.get_relationship_type() != RelationshipType::OneToOne {
+                continue;
+            }
+            let to_table = self.get_table(relationship.get_to_table())?;
+            if !to_table.is_unique_key(relationship.get_to_columns()) {
+                return Err(format!(
+                    "One-to-one relationship {} references from columns of table {} which aren't unique",
+                    relationship.get_code(),
+                    to_table.get_name()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Retrieves the cardinality of the referenced end of a relationship.
+    ///
+    /// # Arguments
+    /// - `relationship`: The `Relationship` to inspect.
+    ///
+    /// # Returns
+    /// - `Cardinality::Optional` if any referencing column is nullable, `Cardinality::Mandatory` otherwise,
+    ///   or an error message if the referencing table or columns don't exist.
+    pub fn get_cardinality(&self, relationship: &Relationship) -> Result<Cardinality, String> {
+        let to_table = self.get_table(relationship.get_to_table())?;
+        for column_name in relationship.get_to_columns() {
+            if to_table.get_column(column_name)?.is_nullable() {
+                return Ok(Cardinality::Optional);
+            }
+        }
+        Ok(Cardinality::Mandatory)
+    }
+
+    /// Sets the cardinality of the referenced end of a relationship by changing the nullability of
+    /// its referencing columns.
+    ///
+    /// # Arguments
+    /// - `code`: The code of the relationship.
+    /// - `cardinality`: The new `Cardinality`.
+    ///
+    /// # Returns
+    /// - An error message if the relationship doesn't exist, models a many-to-many link, or is mandatory
+    ///   while setting its columns to NULL.
+    pub fn set_cardinality(&mut self, code: &str, cardinality: Cardinality) -> Result<(), String> {
+        let relationship = self.get_relationship_with_code(code)?.clone();
+        if relationship.is_many_to_many() {
+            return Err(format!(
+                "Many-to-many relationship {} has no cardinality of its own",
+                code
+            ));
+        }
+        let set_null = relationship.get_on_delete() == ReferentialAction::SetNull
+            || relationship.get_on_update() == ReferentialAction::SetNull;
+        if set_null && cardinality == Cardinality::Mandatory {
+            return Err(format!(
+                "Relationship {} sets its columns to NULL, so it can't be mandatory",
+                code
+            ));
+        }
+        let to_table = self.get_table_mut(relationship.get_to_table())?;
+        for column in to_table.get_columns_mut().iter_mut() {
+            if relationship
+                .get_to_columns()
+                .contains(&column.get_name().to_string())
+            {
+                *column.is_nullable_mut() = cardinality == Cardinality::Optional;
+            }
+        }
+        Ok(())
+    }
+
     /// Updates an existing relationship in the schema.
     ///
     /// # Arguments
@@ -277,43 +730,36 @@ impl Schema {
             .ok_or("Such a realtion doesn't exist".to_string())?;
 
         if relationship.get_from_table() == relationship.get_to_table()
-            && relationship.get_from_column() == relationship.get_to_column()
+            && relationship.get_from_columns() == relationship.get_to_columns()
         {
             return Err(
                 "You can't create a relations to the same column of the same table".to_string(),
             );
         }
 
-        let from_table = self.get_table(relationship.get_from_table())?;
-        let to_table = self.get_table(relationship.get_to_table())?;
-
-        let from_column = from_table.get_column(relationship.get_from_column())?;
-        let to_column = to_table.get_column(relationship.get_to_column())?;
-
-        if from_column.get_data_type() != to_column.get_data_type() {
-            return Err(
-                "You can't create a relations to columns of different data types".to_string(),
-            );
-        }
-
+        // deleting a many-to-many relationship also removes its junction table, so both are
+        // restored if the updated relationship can't be added
+        let tables = self.tables.clone();
+        let relations = self.relations.clone();
         self.delete_relation(relationship.get_code())?;
 
-        self.add_relation(Relationship::new(
-            relationship.get_from_table(),
-            relationship.get_from_column(),
-            relationship.get_to_table(),
-            relationship.get_to_column(),
-            relationship.get_relationship_type(),
-        ))
+        if let Err(e) = self.add_relation(relationship.clone()) {
+            self.tables = tables;
+            self.relations = relations;
+            return Err(e);
+        }
+        Ok(self.relations.last().unwrap())
     }
 
-    /// Deletes a relationship from the schema.
+    /// Deletes a relationship from the schema. Deleting a many-to-many relationship also removes
+    /// its junction table together with the relationships linking it to both sides.
     ///
     /// # Arguments
     /// - `relation_code`: The code of the relationship to delete.
     ///
     /// # Returns
-    /// - A `Result` containing the deleted relationship, or an error message if the relationship does not exist.
+    /// - A `Result` containing the deleted relationship, or an error message if the relationship does not exist
+    ///   or other relationships still use its junction table.
     pub fn delete_relation(&mut self, relation_code: &str) -> Result<Relationship, String> {
         let index = self
             .relations
@@ -321,13 +767,45 @@ impl Schema {
             .position(|r| r.get_code() == relation_code)
             .ok_or("Such a realtion doesn't exist".to_string())?;
 
-        Ok(self.relations.swap_remove(index))
+        let relationship = &self.relations[index];
+        if relationship.is_many_to_many() {
+            let junction_name = relationship.get_junction_table_name();
+            let uses_junction = |r: &Relationship| {
+                !r.is_many_to_many()
+                    && (r.get_from_table() == junction_name || r.get_to_table() == junction_name)
+            };
+            let underlying = |r: &Relationship| {
+                r.get_to_table() == junction_name
+                    && (r.get_from_table() == relationship.get_from_table()
+                        || r.get_from_table() == relationship.get_to_table())
+            };
+            if let Some(other) = self
+                .relations
+                .iter()
+                .find(|r| uses_junction(r) && !underlying(r))
+            {
+                return Err(format!(
+                    "Junction table {} of relationship {} is still used by relationship {}",
+                    junction_name,
+                    relation_code,
+                    other.get_code()
+                ));
+            }
+            let relationship = self.relations.remove(index);
+            self.relations.retain(|r| !uses_junction(r));
+            self.tables.retain(|t| t.get_name() != junction_name);
+            return Ok(relationship);
+        }
+
+        Ok(self.relations.remove(index))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use column::Column;
+    use crate::test_fixtures::{posts_and_tags, tenants_and_users};
+    use constraint::{CheckConstraint, PrimaryKey};
+    use index::Index;
 
     use super::*;
 
@@ -692,4 +1170,461 @@ mod tests {
         assert_eq!(deleted_relationship.get_code(), code);
         assert!(schema.get_relationship_with_code(&code).is_err());
     }
+
+    #[test]
+    fn test_add_column_with_invalid_data_type() {
+        let mut schema = Schema::new();
+        schema.add_table("payments").unwrap();
+        let payments_table = schema.get_table_mut("payments").unwrap();
+
+        let amount = data_type::DataType::Decimal {
+            precision: 2,
+            scale: 4,
+        };
+        assert!(Column::try_new("amount", amount.clone(), false, false, false).is_err());
+        assert!(payments_table
+            .add_column(Column::new("amount", amount, false, false, false))
+            .is_err());
+        assert!(payments_table.get_columns().is_empty());
+    }
+
+    #[test]
+    fn test_enums() {
+        let mut schema = Schema::new();
+        schema
+            .add_enum(EnumType::new("status", &["new", "paid"]))
+            .unwrap();
+        assert!(schema
+            .add_enum(EnumType::new("status", &["other"]))
+            .is_err());
+        assert!(schema
+            .add_enum(EnumType::new("duplicate", &["a", "a"]))
+            .is_err());
+        assert!(schema.add_enum(EnumType::new("empty", &[])).is_err());
+
+        schema.add_table("orders").unwrap();
+        schema
+            .get_table_mut("orders")
+            .unwrap()
+            .add_column(Column::new(
+                "status",
+                DataType::Enum("status".to_string()),
+                false,
+                false,
+                false,
+            ))
+            .unwrap();
+        assert!(schema.validate().is_ok());
+        assert!(schema.remove_enum("status").is_err());
+
+        schema
+            .get_table_mut("orders")
+            .unwrap()
+            .add_column(Column::new(
+                "kind",
+                DataType::Enum("kind".to_string()),
+                false,
+                false,
+                false,
+            ))
+            .unwrap();
+        assert!(schema.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_check_constraints() {
+        let mut schema = Schema::new();
+        schema.add_table("accounts").unwrap();
+        let table = schema.get_table_mut("accounts").unwrap();
+        for name in ["balance", "limit"] {
+            let mut column = Column::new(name, DataType::Integer, false, false, false);
+            column
+                .add_check(CheckConstraint::new("accounts_check", "balance >= 0"))
+                .unwrap();
+            assert!(column
+                .add_check(CheckConstraint::new("accounts_check", "balance >= 1"))
+                .is_err());
+            table.add_column(column).unwrap();
+        }
+        assert!(schema.validate().is_err());
+
+        let limit = &mut schema.get_table_mut("accounts").unwrap().get_columns_mut()[1];
+        *limit.get_checks_mut() = vec![CheckConstraint::new("accounts_limit_check", " ")];
+        assert!(schema.validate().is_err());
+        schema.get_table_mut("accounts").unwrap().get_columns_mut()[1]
+            .get_checks_mut()
+            .clear();
+        assert!(schema.validate().is_ok());
+    }
+
+    #[test]
+    fn test_composite_primary_key() {
+        let mut schema = Schema::new();
+        schema.add_table("order_items").unwrap();
+        let table = schema.get_table_mut("order_items").unwrap();
+        for name in ["order_id", "product_id", "quantity"] {
+            table
+                .add_column(Column::new(name, DataType::Integer, false, false, false))
+                .unwrap();
+        }
+        assert!(table.get_primary_key().is_none());
+        assert!(table
+            .set_primary_key(Some(PrimaryKey::new(&["order_id", "order_id"])))
+            .is_err());
+        assert!(table
+            .set_primary_key(Some(PrimaryKey::new(&["order_id", "missing"])))
+            .is_err());
+        table
+            .set_primary_key(Some(PrimaryKey::new(&["order_id", "product_id"])))
+            .unwrap();
+        assert!(table.get_column("product_id").unwrap().is_primary_key());
+        assert!(!table.get_column("quantity").unwrap().is_primary_key());
+        assert!(schema.validate().is_ok());
+
+        let table = schema.get_table_mut("order_items").unwrap();
+        *table.get_columns_mut()[2].is_primary_key_mut() = true;
+        assert!(schema.validate().is_err());
+    }
+
+    #[test]
+    fn test_add_composite_relation() {
+        let mut schema = tenants_and_users();
+        assert!(schema
+            .add_relation(Relationship::new_composite(
+                "users",
+                &["tenant_id", "id"],
+                "orders",
+                &["tenant_id"],
+                RelationshipType::OneToMany,
+            ))
+            .is_err());
+        assert!(schema
+            .add_relation(Relationship::new_composite(
+                "users",
+                &["tenant_id", "id"],
+                "orders",
+                &["user_id", "tenant_id"],
+                RelationshipType::OneToMany,
+            ))
+            .is_err());
+
+        let relationship = schema
+            .add_relation(Relationship::new_composite(
+                "users",
+                &["tenant_id", "id"],
+                "orders",
+                &["tenant_id", "user_id"],
+                RelationshipType::OneToMany,
+            ))
+            .unwrap();
+        assert_eq!(
+            relationship.get_code(),
+            "users_tenant_id_id_orders_tenant_id_user_id"
+        );
+        assert_eq!(
+            relationship.get_constraint_name(),
+            "orders_tenant_id_user_id_fk"
+        );
+    }
+
+    #[test]
+    fn test_add_relation_with_referential_actions() {
+        let mut schema = tenants_and_users();
+        let mut relationship = Relationship::new_composite(
+            "users",
+            &["tenant_id", "id"],
+            "orders",
+            &["tenant_id", "user_id"],
+            RelationshipType::OneToMany,
+        );
+        relationship.set_on_delete(ReferentialAction::SetNull);
+        assert!(schema.add_relation(relationship.clone()).is_err());
+
+        relationship.set_on_delete(ReferentialAction::Cascade);
+        relationship.set_initially_deferred(true);
+        assert!(schema.add_relation(relationship.clone()).is_err());
+
+        relationship.set_deferrable(true);
+        let relationship = schema.add_relation(relationship).unwrap();
+        assert_eq!(relationship.get_on_delete(), ReferentialAction::Cascade);
+        assert_eq!(relationship.get_on_update(), ReferentialAction::NoAction);
+    }
+
+    #[test]
+    fn test_add_many_to_many_relation() {
+        let mut schema = posts_and_tags();
+        schema
+            .add_relation(Relationship::new(
+                "posts",
+                "id",
+                "tags",
+                "id",
+                RelationshipType::ManyToMany,
+            ))
+            .unwrap();
+
+        let junction = schema.get_table("posts_tags").unwrap();
+        assert_eq!(junction.get_columns().len(), 2);
+        assert_eq!(
+            junction.get_column("posts_id").unwrap().get_data_type(),
+            DataType::BigInt
+        );
+        assert_eq!(
+            junction.get_column("tags_id").unwrap().get_data_type(),
+            DataType::Integer
+        );
+        assert_eq!(
+            junction.get_primary_key().unwrap().get_columns(),
+            ["posts_id", "tags_id"]
+        );
+        assert_eq!(schema.get_relationships().len(), 3);
+        let underlying = schema
+            .get_relationship_with_code("tags_id_posts_tags_tags_id")
+            .unwrap();
+        assert_eq!(
+            underlying.get_relationship_type(),
+            RelationshipType::OneToMany
+        );
+        assert_eq!(underlying.get_on_delete(), ReferentialAction::Cascade);
+        assert!(schema.validate().is_ok());
+        assert!(schema
+            .add_relation(Relationship::new(
+                "posts",
+                "id",
+                "tags",
+                "id",
+                RelationshipType::ManyToMany,
+            ))
+            .is_err());
+    }
+
+    #[test]
+    fn test_add_many_to_many_relation_with_existing_junction_table() {
+        let mut schema = posts_and_tags();
+        schema.add_table("taggings").unwrap();
+        schema
+            .get_table_mut("taggings")
+            .unwrap()
+            .add_column(Column::new("posts_id", DataType::BigInt, true, true, false))
+            .unwrap();
+        let mut relationship =
+            Relationship::new("posts", "id", "tags", "id", RelationshipType::ManyToMany);
+        relationship.set_junction_table("taggings");
+        assert!(schema.add_relation(relationship.clone()).is_err());
+
+        schema
+            .get_table_mut("taggings")
+            .unwrap()
+            .add_column(Column::new(
+                "tags_id",
+                DataType::Integer,
+                false,
+                true,
+                false,
+            ))
+            .unwrap();
+        assert!(schema.add_relation(relationship.clone()).is_err());
+
+        schema
+            .get_table_mut("taggings")
+            .unwrap()
+            .set_primary_key(Some(PrimaryKey::new(&["tags_id", "posts_id"])))
+            .unwrap();
+        schema.add_relation(relationship).unwrap();
+        assert_eq!(schema.get_table("taggings").unwrap().get_columns().len(), 2);
+        assert_eq!(schema.get_relationships().len(), 3);
+    }
+
+    #[test]
+    fn test_add_many_to_many_relation_leaves_schema_unchanged_on_error() {
+        let mut schema = posts_and_tags();
+        *schema.get_table_mut("tags").unwrap().get_columns_mut()[0].get_data_type_mut() =
+            DataType::Varchar(0);
+        assert!(schema
+            .add_relation(Relationship::new(
+                "posts",
+                "id",
+                "tags",
+                "id",
+                RelationshipType::ManyToMany,
+            ))
+            .is_err());
+        assert!(schema.get_table("posts_tags").is_err());
+        assert!(schema.get_relationships().is_empty());
+    }
+
+    #[test]
+    fn test_delete_many_to_many_relation() {
+        let mut schema = posts_and_tags();
+        let code = schema
+            .add_relation(Relationship::new(
+                "posts",
+                "id",
+                "tags",
+                "id",
+                RelationshipType::ManyToMany,
+            ))
+            .unwrap()
+            .get_code()
+            .to_string();
+        schema.add_table("votes").unwrap();
+        schema
+            .get_table_mut("votes")
+            .unwrap()
+            .add_column(Column::new(
+                "posts_id",
+                DataType::BigInt,
+                false,
+                true,
+                false,
+            ))
+            .unwrap();
+        schema
+            .get_table_mut("votes")
+            .unwrap()
+            .add_column(Column::new(
+                "tags_id",
+                DataType::Integer,
+                false,
+                true,
+                false,
+            ))
+            .unwrap();
+        let votes = schema
+            .add_relation(Relationship::new_composite(
+                "posts_tags",
+                &["posts_id", "tags_id"],
+                "votes",
+                &["posts_id", "tags_id"],
+                RelationshipType::OneToMany,
+            ))
+            .unwrap()
+            .get_code()
+            .to_string();
+        assert!(schema.delete_relation(&code).is_err());
+        assert_eq!(schema.get_relationships().len(), 4);
+
+        schema.delete_relation(&votes).unwrap();
+        schema.delete_relation(&code).unwrap();
+        assert!(schema.get_table("posts_tags").is_err());
+        assert!(schema.get_relationships().is_empty());
+        assert!(schema.validate().is_ok());
+    }
+
+    #[test]
+    fn test_one_to_one_relation() {
+        let mut schema = tenants_and_users();
+        schema
+            .add_relation(Relationship::new(
+                "users",
+                "id",
+                "orders",
+                "user_id",
+                RelationshipType::OneToOne,
+            ))
+            .unwrap();
+        let orders_table = schema.get_table("orders").unwrap();
+        assert!(orders_table.get_column("user_id").unwrap().is_unique());
+        assert!(!orders_table.get_column("tenant_id").unwrap().is_unique());
+        assert!(schema.validate().is_ok());
+
+        *schema.get_table_mut("orders").unwrap().get_columns_mut()[1].is_unique_mut() = false;
+        assert!(schema.validate().is_err());
+
+        let mut schema = tenants_and_users();
+        assert!(schema
+            .add_relation(Relationship::new_composite(
+                "users",
+                &["tenant_id", "id"],
+                "orders",
+                &["tenant_id", "user_id"],
+                RelationshipType::OneToOne,
+            ))
+            .is_err());
+        schema
+            .get_table_mut("orders")
+            .unwrap()
+            .set_primary_key(Some(PrimaryKey::new(&["tenant_id", "user_id"])))
+            .unwrap();
+        schema
+            .add_relation(Relationship::new_composite(
+                "users",
+                &["tenant_id", "id"],
+                "orders",
+                &["tenant_id", "user_id"],
+                RelationshipType::OneToOne,
+            ))
+            .unwrap();
+        assert!(schema.validate().is_ok());
+    }
+
+    #[test]
+    fn test_cardinality() {
+        let mut schema = tenants_and_users();
+        let relationship = schema
+            .add_relation(Relationship::new(
+                "users",
+                "id",
+                "orders",
+                "user_id",
+                RelationshipType::OneToOne,
+            ))
+            .unwrap()
+            .clone();
+        let cardinality = schema.get_cardinality(&relationship).unwrap();
+        assert_eq!(cardinality, Cardinality::Mandatory);
+        assert_eq!(cardinality.get_notation(), "1..1");
+
+        schema
+            .set_cardinality(relationship.get_code(), Cardinality::Optional)
+            .unwrap();
+        let orders_table = schema.get_table("orders").unwrap();
+        assert!(orders_table.get_column("user_id").unwrap().is_nullable());
+        assert_eq!(
+            schema.get_cardinality(&relationship).unwrap(),
+            Cardinality::Optional
+        );
+
+        let mut set_null = relationship.clone();
+        set_null.set_on_delete(ReferentialAction::SetNull);
+        schema.update_relation(&set_null).unwrap();
+        assert!(schema
+            .set_cardinality(relationship.get_code(), Cardinality::Mandatory)
+            .is_err());
+    }
+
+    #[test]
+    fn test_indexes() {
+        let mut schema = tenants_and_users();
+        let orders_table = schema.get_table_mut("orders").unwrap();
+        assert!(orders_table
+            .add_index(Index::new("orders_empty", &[]))
+            .is_err());
+        assert!(orders_table
+            .add_index(Index::new("orders_total", &["total"]))
+            .is_err());
+        let mut covering = Index::new("orders_user_id", &["user_id"]);
+        covering.set_include(&["user_id"]);
+        assert!(orders_table.add_index(covering).is_err());
+        orders_table
+            .add_index(Index::new("orders_user_id", &["user_id"]))
+            .unwrap();
+        assert!(orders_table
+            .add_index(Index::new("orders_user_id", &["tenant_id"]))
+            .is_err());
+        assert!(schema.validate().is_ok());
+
+        schema
+            .get_table_mut("users")
+            .unwrap()
+            .add_index(Index::new("orders_user_id", &["id"]))
+            .unwrap();
+        assert!(schema.validate().is_err());
+        schema
+            .get_table_mut("users")
+            .unwrap()
+            .remove_index("orders_user_id")
+            .unwrap();
+        assert!(schema.validate().is_ok());
+    }
 }
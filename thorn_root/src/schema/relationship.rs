@@ -1,11 +1,29 @@
+/// A foreign key from the `to_columns` of `to_table` referencing the `from_columns`
+/// of `from_table`. The column lists are ordered and pair up position by position.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Relationship {
     from_table: String,
-    from_column: String,
+    from_columns: Vec<String>,
     to_table: String,
-    to_column: String,
+    to_columns: Vec<String>,
     relationship_type: RelationshipType,
+    // derived from the columns, recomputed when a schema is loaded
+    #[cfg_attr(feature = "serde", serde(skip_serializing, default))]
     code: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    on_delete: ReferentialAction,
+    #[cfg_attr(feature = "serde", serde(default))]
+    on_update: ReferentialAction,
+    #[cfg_attr(feature = "serde", serde(default))]
+    deferrable: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    initially_deferred: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    junction_table: Option<String>,
 }
 
 impl Relationship {
@@ -15,14 +33,42 @@ impl Relationship {
         to_table: &str,
         to_column: &str,
         relationship_type: RelationshipType,
+    ) -> Self {
+        Self::new_composite(
+            from_table,
+            &[from_column],
+            to_table,
+            &[to_column],
+            relationship_type,
+        )
+    }
+
+    /// Creates a relationship over several columns, e.g. `(tenant_id, user_id)` referencing `(tenant_id, id)`.
+    pub fn new_composite(
+        from_table: &str,
+        from_columns: &[&str],
+        to_table: &str,
+        to_columns: &[&str],
+        relationship_type: RelationshipType,
     ) -> Self {
         Self {
             from_table: from_table.to_string(),
-            from_column: from_column.to_string(),
+            from_columns: from_columns.iter().map(|c| c.to_string()).collect(),
             to_table: to_table.to_string(),
-            to_column: to_column.to_string(),
+            to_columns: to_columns.iter().map(|c| c.to_string()).collect(),
             relationship_type,
-            code: format!("{}_{}_{}_{}", from_table, from_column, to_table, to_column),
+            code: format!(
+                "{}_{}_{}_{}",
+                from_table,
+                from_columns.join("_"),
+                to_table,
+                to_columns.join("_")
+            ),
+            on_delete: ReferentialAction::default(),
+            on_update: ReferentialAction::default(),
+            deferrable: false,
+            initially_deferred: false,
+            junction_table: None,
         }
     }
 
@@ -31,9 +77,9 @@ impl Relationship {
         &self.from_table
     }
 
-    /// Getter for `from_column`
-    pub fn get_from_column(&self) -> &str {
-        &self.from_column
+    /// Getter for `from_columns`
+    pub fn get_from_columns(&self) -> &[String] {
+        &self.from_columns
     }
 
     /// Getter for `to_table`
@@ -41,9 +87,9 @@ impl Relationship {
         &self.to_table
     }
 
-    /// Getter for `to_column`
-    pub fn get_to_column(&self) -> &str {
-        &self.to_column
+    /// Getter for `to_columns`
+    pub fn get_to_columns(&self) -> &[String] {
+        &self.to_columns
     }
 
     /// Getter for `relationship_type`
@@ -56,29 +102,142 @@ impl Relationship {
         &self.code
     }
 
+    /// Getter for `on_delete`, the action taken when a referenced row is deleted
+    pub fn get_on_delete(&self) -> ReferentialAction {
+        self.on_delete.to_owned()
+    }
+
+    /// Getter for `on_update`, the action taken when a referenced key is updated
+    pub fn get_on_update(&self) -> ReferentialAction {
+        self.on_update.to_owned()
+    }
+
+    /// Whether checking the foreign key can be deferred to the end of a transaction
+    pub fn is_deferrable(&self) -> bool {
+        self.deferrable
+    }
+
+    /// Whether checking the foreign key is deferred unless a transaction asks otherwise
+    pub fn is_initially_deferred(&self) -> bool {
+        self.initially_deferred
+    }
+
+    /// Getter for `junction_table`, the explicitly named junction table of a many-to-many relationship
+    pub fn get_junction_table(&self) -> Option<&str> {
+        self.junction_table.as_deref()
+    }
+
+    /// Name of the junction table of a many-to-many relationship, `{from_table}_{to_table}` unless named explicitly
+    pub fn get_junction_table_name(&self) -> String {
+        self.junction_table
+            .clone()
+            .unwrap_or(format!("{}_{}", self.from_table, self.to_table))
+    }
+
+    /// Whether the relationship is modeled by a junction table instead of a foreign key of its own
+    pub fn is_many_to_many(&self) -> bool {
+        self.relationship_type == RelationshipType::ManyToMany
+    }
+
+    /// Name of the foreign key constraint generated for this relationship
+    pub fn get_constraint_name(&self) -> String {
+        format!("{}_{}_fk", self.to_table, self.to_columns.join("_"))
+    }
+
     pub fn set_from_table(&mut self, from_table: &str) {
         self.from_table = from_table.to_string();
     }
 
-    pub fn set_from_column(&mut self, from_column: &str) {
-        self.from_column = from_column.to_string();
+    pub fn set_from_columns(&mut self, from_columns: &[&str]) {
+        self.from_columns = from_columns.iter().map(|c| c.to_string()).collect();
     }
 
     pub fn set_to_table(&mut self, to_table: &str) {
         self.to_table = to_table.to_string();
     }
 
-    pub fn set_to_column(&mut self, to_column: &str) {
-        self.to_column = to_column.to_string();
+    pub fn set_to_columns(&mut self, to_columns: &[&str]) {
+        self.to_columns = to_columns.iter().map(|c| c.to_string()).collect();
     }
 
     pub fn set_relationship_type(&mut self, relationship_type: RelationshipType) {
         self.relationship_type = relationship_type;
     }
+
+    pub fn set_junction_table(&mut self, junction_table: &str) {
+        self.junction_table = Some(junction_table.to_string());
+    }
+
+    pub fn set_on_delete(&mut self, on_delete: ReferentialAction) {
+        self.on_delete = on_delete;
+    }
+
+    pub fn set_on_update(&mut self, on_update: ReferentialAction) {
+        self.on_update = on_update;
+    }
+
+    pub fn set_deferrable(&mut self, deferrable: bool) {
+        self.deferrable = deferrable;
+    }
+
+    /// Makes the check initially deferred, which requires the relationship to be deferrable.
+    pub fn set_initially_deferred(&mut self, initially_deferred: bool) {
+        self.initially_deferred = initially_deferred;
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum RelationshipType {
     OneToOne,
     OneToMany,
+    /// Rows of both tables are linked through a junction table holding a foreign key to each side.
+    ManyToMany,
+}
+
+/// Whether a referencing row must reference a row, i.e. the `0..1` or `1..1` end of a relationship.
+/// It follows the nullability of the referencing columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cardinality {
+    /// `0..1`: the referencing columns are nullable.
+    Optional,
+    /// `1..1`: the referencing columns are `NOT NULL`.
+    Mandatory,
+}
+
+impl Cardinality {
+    /// Renders the cardinality in UML notation.
+    pub fn get_notation(&self) -> &'static str {
+        match self {
+            Cardinality::Optional => "0..1",
+            Cardinality::Mandatory => "1..1",
+        }
+    }
+}
+
+/// What the database does with referencing rows when the referenced row changes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ReferentialAction {
+    Cascade,
+    SetNull,
+    SetDefault,
+    Restrict,
+    #[default]
+    NoAction,
+}
+
+impl ReferentialAction {
+    /// Renders the action as it follows `ON DELETE` or `ON UPDATE`.
+    pub fn to_sql(&self) -> &'static str {
+        match self {
+            ReferentialAction::Cascade => "CASCADE",
+            ReferentialAction::SetNull => "SET NULL",
+            ReferentialAction::SetDefault => "SET DEFAULT",
+            ReferentialAction::Restrict => "RESTRICT",
+            ReferentialAction::NoAction => "NO ACTION",
+        }
+    }
 }
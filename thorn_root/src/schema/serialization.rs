@@ -0,0 +1,566 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::CoreError;
+
+use super::enum_type::EnumType;
+use super::relationship::Relationship;
+use super::table::Table;
+use super::Schema;
+
+/// Version of the schema document format written by this library.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Upgraders turning a document of one format version into the next, starting at version 1.
+/// Empty until the format first changes.
+const UPGRADERS: [fn(Value) -> Result<Value, String>; FORMAT_VERSION as usize - 1] = [];
+
+/// The envelope a schema is saved in: the format version, followed by the schema.
+/// `Schema` is serialized as this document and deserialized through `load_document`, so
+/// serializers other than the ones of `to_json` and friends keep the format version too.
+#[derive(Serialize, Deserialize)]
+pub(super) struct SchemaDocument {
+    version: u32,
+    schema: SchemaDefinition,
+}
+
+/// The serialized representation of a `Schema`.
+///
+/// Loading it rebuilds the schema through `Schema::add_table`, `Table::add_column` and
+/// `Schema::add_relation`, followed by `Schema::validate`, so a hand-edited file is held
+/// to the same rules as a schema built in code.
+#[derive(Serialize, Deserialize)]
+struct SchemaDefinition {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    enums: Vec<EnumType>,
+    #[serde(default)]
+    tables: Vec<Table>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    relationships: Vec<Relationship>,
+}
+
+impl From<Schema> for SchemaDefinition {
+    fn from(schema: Schema) -> Self {
+        Self {
+            enums: schema.enums,
+            tables: schema.tables,
+            relationships: schema.relations,
+        }
+    }
+}
+
+impl From<Schema> for SchemaDocument {
+    fn from(schema: Schema) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            schema: schema.into(),
+        }
+    }
+}
+
+impl TryFrom<Value> for Schema {
+    type Error = CoreError;
+
+    fn try_from(document: Value) -> Result<Self, Self::Error> {
+        load_document(document)
+    }
+}
+
+impl TryFrom<SchemaDefinition> for Schema {
+    type Error = String;
+
+    fn try_from(definition: SchemaDefinition) -> Result<Self, Self::Error> {
+        let mut schema = Schema::new();
+        for enum_type in definition.enums {
+            enum_type.validate()?;
+            schema.add_enum(enum_type)?;
+        }
+        for table in definition.tables {
+            schema.add_table(table.get_name())?;
+            let new_table = schema.get_table_mut(table.get_name())?;
+            for column in table.get_columns() {
+                if new_table.get_column(column.get_name()).is_ok() {
+                    return Err(format!(
+                        "Column {} of table {} is defined more than once",
+                        column.get_name(),
+                        table.get_name()
+                    ));
+                }
+                new_table.add_column(column.clone())?;
+            }
+            if let Some(primary_key) = table.get_primary_key() {
+                new_table.set_primary_key(Some(primary_key.clone()))?;
+            }
+            for index in table.get_indexes() {
+                new_table.add_index(index.clone())?;
+            }
+        }
+        for relationship in definition.relationships {
+            let relationship = rebuild_relationship(&relationship);
+            // a many-to-many relationship listed before its junction relationships has already
+            // created them
+            if schema
+                .get_relationship_with_code(relationship.get_code())
+                .is_ok_and(|r| *r == relationship && !r.is_many_to_many())
+            {
+                continue;
+            }
+            schema.add_relation(relationship)?;
+        }
+        schema.validate()?;
+        Ok(schema)
+    }
+}
+
+/// Recreates a loaded relationship through its constructor, which derives its code.
+fn rebuild_relationship(loaded: &Relationship) -> Relationship {
+    let from_columns = loaded
+        .get_from_columns()
+        .iter()
+        .map(|c| c.as_str())
+        .collect::<Vec<&str>>();
+    let to_columns = loaded
+        .get_to_columns()
+        .iter()
+        .map(|c| c.as_str())
+        .collect::<Vec<&str>>();
+    let mut relationship = Relationship::new_composite(
+        loaded.get_from_table(),
+        &from_columns,
+        loaded.get_to_table(),
+        &to_columns,
+        loaded.get_relationship_type(),
+    );
+    relationship.set_on_delete(loaded.get_on_delete());
+    relationship.set_on_update(loaded.get_on_update());
+    relationship.set_deferrable(loaded.is_deferrable());
+    relationship.set_initially_deferred(loaded.is_initially_deferred());
+    if let Some(junction_table) = loaded.get_junction_table() {
+        relationship.set_junction_table(junction_table);
+    }
+    relationship
+}
+
+/// Converts YAML to the JSON data model, turning tagged values such as `!varchar 36` into
+/// single-entry maps like `{varchar: 36}`.
+fn yaml_to_json(value: serde_yaml::Value) -> Result<Value, String> {
+    match value {
+        serde_yaml::Value::Tagged(tagged) => {
+            let mut map = serde_json::Map::new();
+            map.insert(
+                tagged.tag.to_string().trim_start_matches('!').to_string(),
+                yaml_to_json(tagged.value)?,
+            );
+            Ok(Value::Object(map))
+        }
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut map = serde_json::Map::new();
+            for (key, value) in mapping {
+                let key = key
+                    .as_str()
+                    .ok_or(format!("Schema document has a non-string key {:?}", key))?
+                    .to_string();
+                map.insert(key, yaml_to_json(value)?);
+            }
+            Ok(Value::Object(map))
+        }
+        serde_yaml::Value::Sequence(sequence) => Ok(Value::Array(
+            sequence
+                .into_iter()
+                .map(yaml_to_json)
+                .collect::<Result<Vec<Value>, String>>()?,
+        )),
+        value => serde_json::to_value(value).map_err(|e| e.to_string()),
+    }
+}
+
+/// Brings a parsed document of any known format version up to `FORMAT_VERSION` and loads it.
+fn load_document(mut document: Value) -> Result<Schema, CoreError> {
+    let version = document
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or(CoreError::FormatError(
+            "Schema document has no format version".to_string(),
+        ))?;
+    if version > FORMAT_VERSION as u64 {
+        return Err(CoreError::UnsupportedFormatVersion {
+            version,
+            supported: FORMAT_VERSION,
+        });
+    }
+    if version == 0 {
+        return Err(CoreError::FormatError(
+            "Schema format versions start at 1".to_string(),
+        ));
+    }
+    for upgrader in UPGRADERS[version as usize - 1..].iter() {
+        document = upgrader(document).map_err(CoreError::FormatError)?;
+    }
+    let document = serde_json::from_value::<SchemaDocument>(document)
+        .map_err(|e| CoreError::FormatError(e.to_string()))?;
+    Schema::try_from(document.schema).map_err(CoreError::SchemaValidationError)
+}
+
+impl Schema {
+    fn to_document(&self) -> SchemaDocument {
+        self.clone().into()
+    }
+
+    /// Serializes the schema to a pretty-printed JSON document.
+    pub fn to_json(&self) -> Result<String, CoreError> {
+        serde_json::to_string_pretty(&self.to_document())
+            .map_err(|e| CoreError::FormatError(e.to_string()))
+    }
+
+    /// Loads a schema from a JSON document, upgrading documents saved in older formats.
+    pub fn from_json(json: &str) -> Result<Schema, CoreError> {
+        load_document(
+            serde_json::from_str(json).map_err(|e| CoreError::FormatError(e.to_string()))?,
+        )
+    }
+
+    /// Serializes the schema to a YAML document, writing enum values as single-entry maps.
+    pub fn to_yaml(&self) -> Result<String, CoreError> {
+        let mut yaml = Vec::new();
+        serde_yaml::with::singleton_map_recursive::serialize(
+            &self.to_document(),
+            &mut serde_yaml::Serializer::new(&mut yaml),
+        )
+        .map_err(|e| CoreError::FormatError(e.to_string()))?;
+        String::from_utf8(yaml).map_err(|e| CoreError::FormatError(e.to_string()))
+    }
+
+    /// Loads a schema from a YAML document, upgrading documents saved in older formats.
+    pub fn from_yaml(yaml: &str) -> Result<Schema, CoreError> {
+        let document =
+            serde_yaml::from_str(yaml).map_err(|e| CoreError::FormatError(e.to_string()))?;
+        load_document(yaml_to_json(document).map_err(CoreError::FormatError)?)
+    }
+
+    /// Serializes the schema to a TOML document.
+    pub fn to_toml(&self) -> Result<String, CoreError> {
+        toml::to_string_pretty(&self.to_document())
+            .map_err(|e| CoreError::FormatError(e.to_string()))
+    }
+
+    /// Loads a schema from a TOML document, upgrading documents saved in older formats.
+    pub fn from_toml(toml: &str) -> Result<Schema, CoreError> {
+        load_document(toml::from_str(toml).map_err(|e| CoreError::FormatError(e.to_string()))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::column::Column;
+    use crate::schema::constraint::{CheckConstraint, DefaultValue, PrimaryKey};
+    use crate::schema::data_type::DataType;
+    use crate::schema::index::{Index, IndexMethod, SortOrder};
+    use crate::schema::relationship::{ReferentialAction, RelationshipType};
+
+    use super::*;
+
+    fn shop() -> Schema {
+        let mut schema = Schema::new();
+        schema
+            .add_enum(EnumType::new("status", &["new", "paid"]))
+            .unwrap();
+        schema.add_table("users").unwrap();
+        schema.add_table("orders").unwrap();
+        schema.add_table("tags").unwrap();
+        let users_table = schema.get_table_mut("users").unwrap();
+        users_table
+            .add_column(Column::new("id", DataType::BigInt, true, false, false))
+            .unwrap();
+        let mut email = Column::new("email", DataType::Varchar(255), false, false, false);
+        *email.is_unique_mut() = true;
+        users_table.add_column(email).unwrap();
+
+        let orders_table = schema.get_table_mut("orders").unwrap();
+        for name in ["user_id", "number"] {
+            orders_table
+                .add_column(Column::new(
+                    name,
+                    DataType::BigInt,
+                    false,
+                    name == "user_id",
+                    false,
+                ))
+                .unwrap();
+        }
+        let mut status = Column::new(
+            "status",
+            DataType::Enum("status".to_string()),
+            false,
+            false,
+            false,
+        );
+        *status.get_default_mut() = Some(DefaultValue::String("new".to_string()));
+        orders_table.add_column(status).unwrap();
+        let mut total = Column::new(
+            "total",
+            DataType::Decimal {
+                precision: 10,
+                scale: 2,
+            },
+            false,
+            false,
+            true,
+        );
+        total
+            .add_check(CheckConstraint::new("orders_total_check", "total >= 0"))
+            .unwrap();
+        orders_table.add_column(total).unwrap();
+        orders_table
+            .add_column(Column::new(
+                "labels",
+                DataType::Array(Box::new(DataType::String)),
+                false,
+                false,
+                true,
+            ))
+            .unwrap();
+        orders_table
+            .set_primary_key(Some(PrimaryKey::with_name(
+                "orders_pk",
+                &["user_id", "number"],
+            )))
+            .unwrap();
+        let mut labels = Index::new("orders_labels", &["labels"]);
+        labels.set_method(IndexMethod::Gin);
+        orders_table.add_index(labels).unwrap();
+        let mut recent = Index::new("orders_recent", &["number"]);
+        recent.get_columns_mut()[0].set_order(SortOrder::Desc);
+        recent.set_predicate(Some("total > 0"));
+        orders_table.add_index(recent).unwrap();
+
+        schema
+            .get_table_mut("tags")
+            .unwrap()
+            .add_column(Column::new("id", DataType::Integer, true, false, false))
+            .unwrap();
+
+        let mut relationship = Relationship::new(
+            "users",
+            "id",
+            "orders",
+            "user_id",
+            RelationshipType::OneToMany,
+        );
+        relationship.set_on_delete(ReferentialAction::Cascade);
+        relationship.set_deferrable(true);
+        schema.add_relation(relationship).unwrap();
+        schema
+            .add_relation(Relationship::new(
+                "users",
+                "id",
+                "tags",
+                "id",
+                RelationshipType::ManyToMany,
+            ))
+            .unwrap();
+        schema
+    }
+
+    fn assert_same_schema(left: &Schema, right: &Schema) {
+        assert_eq!(left.get_tables(), right.get_tables());
+        assert_eq!(left.get_relationships(), right.get_relationships());
+        assert_eq!(left.get_enums(), right.get_enums());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let schema = shop();
+        assert_same_schema(
+            &Schema::from_json(&schema.to_json().unwrap()).unwrap(),
+            &schema,
+        );
+        assert_same_schema(
+            &Schema::from_yaml(&schema.to_yaml().unwrap()).unwrap(),
+            &schema,
+        );
+        assert_same_schema(
+            &Schema::from_toml(&schema.to_toml().unwrap()).unwrap(),
+            &schema,
+        );
+    }
+
+    #[test]
+    fn test_round_trip_after_deleting_relationship() {
+        let mut schema = shop();
+        schema.delete_relation("users_id_orders_user_id").unwrap();
+        assert!(schema.get_relationships().last().unwrap().is_many_to_many());
+        assert_same_schema(
+            &Schema::from_json(&schema.to_json().unwrap()).unwrap(),
+            &schema,
+        );
+
+        // junction relationships listed after their many-to-many relationship
+        let mut document = serde_json::to_value(&schema).unwrap();
+        let relationships = document["schema"]["relationships"].as_array_mut().unwrap();
+        relationships.rotate_right(1);
+        assert_eq!(relationships[0]["relationship_type"], "many_to_many");
+        let loaded = Schema::from_json(&document.to_string()).unwrap();
+        assert_eq!(loaded.get_relationships().len(), 3);
+        assert_eq!(loaded.get_tables(), schema.get_tables());
+    }
+
+    #[test]
+    fn test_serde_uses_document() {
+        let schema = shop();
+        let json = serde_json::to_string(&schema).unwrap();
+        assert!(json.starts_with("{\"version\":1,\"schema\":"));
+        assert_same_schema(&Schema::from_json(&json).unwrap(), &schema);
+        assert_same_schema(
+            &serde_json::from_str::<Schema>(&schema.to_json().unwrap()).unwrap(),
+            &schema,
+        );
+        assert_same_schema(
+            &toml::from_str::<Schema>(&toml::to_string(&schema).unwrap()).unwrap(),
+            &schema,
+        );
+
+        let schema = serde_json::from_str::<Schema>(
+            r#"{"version": 1, "schema": {"tables": [{"name": "users"}]}}"#,
+        )
+        .unwrap();
+        assert!(schema.get_table("users").is_ok());
+        assert!(serde_json::from_str::<Schema>(r#"{"version": 2}"#)
+            .unwrap_err()
+            .to_string()
+            .contains("only reads up to 1"));
+    }
+
+    #[test]
+    fn test_stable_representation() {
+        let mut schema = Schema::new();
+        schema.add_table("users").unwrap();
+        schema
+            .get_table_mut("users")
+            .unwrap()
+            .add_column(Column::new("id", DataType::Varchar(36), true, false, false))
+            .unwrap();
+        assert_eq!(
+            schema.to_json().unwrap(),
+            r#"{
+  "version": 1,
+  "schema": {
+    "tables": [
+      {
+        "name": "users",
+        "columns": [
+          {
+            "name": "id",
+            "data_type": {
+              "varchar": 36
+            },
+            "primary_key": true,
+            "foreign_key": false,
+            "nullable": false,
+            "unique": false
+          }
+        ],
+        "primary_key": {
+          "columns": [
+            "id"
+          ]
+        }
+      }
+    ]
+  }
+}"#
+        );
+    }
+
+    #[test]
+    fn test_loading_validates() {
+        let yaml = r#"
+version: 1
+schema:
+  tables:
+    - name: users
+      columns:
+        - name: id
+          data_type: integer
+          primary_key: true
+    - name: orders
+      columns:
+        - name: user_id
+          data_type: big_int
+  relationships:
+    - from_table: users
+      from_columns: [id]
+      to_table: orders
+      to_columns: [user_id]
+      relationship_type: one_to_many
+"#;
+        assert!(matches!(
+            Schema::from_yaml(yaml),
+            Err(CoreError::SchemaValidationError(e)) if e.contains("different data types")
+        ));
+        let schema = Schema::from_yaml(&yaml.replace("big_int", "integer")).unwrap();
+        assert_eq!(
+            schema.get_relationships()[0].get_code(),
+            "users_id_orders_user_id"
+        );
+
+        assert!(Schema::from_json(
+            r#"{"version": 1, "schema": {"tables": [{"name": "a"}, {"name": "a"}]}}"#
+        )
+        .is_err());
+        assert!(Schema::from_json(
+            r#"{"version": 1, "schema": {"tables": [{"name": "a", "columns": [{"name": "v", "data_type": {"varchar": 0}}]}]}}"#
+        )
+        .is_err());
+        assert!(Schema::from_json(
+            r#"{"version": 1, "schema": {"tables": [{"name": "a", "columns": [{"name": "s", "data_type": {"enum": "missing"}}]}]}}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_document_versions() {
+        let version_1 = r#"{"version": 1, "schema": {"tables": [{"name": "users"}]}}"#;
+        let schema = Schema::from_json(version_1).unwrap();
+        assert!(schema.get_table("users").is_ok());
+        assert!(schema
+            .to_json()
+            .unwrap()
+            .starts_with("{\n  \"version\": 1,"));
+
+        let yaml = "version: 1\nschema:\n  tables:\n  - name: users\n    columns:\n    - name: id\n      data_type: !varchar 36\n";
+        let schema = Schema::from_yaml(yaml).unwrap();
+        let column = schema.get_table("users").unwrap().get_column("id").unwrap();
+        assert_eq!(column.get_data_type(), DataType::Varchar(36));
+        assert!(schema
+            .to_yaml()
+            .unwrap()
+            .contains("data_type:\n        varchar: 36\n"));
+
+        let toml = "version = 1\n\n[[schema.tables]]\nname = \"users\"\n";
+        assert!(Schema::from_toml(toml).unwrap().get_table("users").is_ok());
+
+        assert!(matches!(
+            Schema::from_toml("version = 2\n"),
+            Err(CoreError::UnsupportedFormatVersion {
+                version: 2,
+                supported: 1
+            })
+        ));
+        assert_eq!(
+            Schema::from_yaml("version: 9\n").unwrap_err().to_string(),
+            "Schema document has format version 9, but this version of ThornRoot only reads up to 1"
+        );
+        assert!(matches!(
+            Schema::from_json(r#"{"tables": []}"#),
+            Err(CoreError::FormatError(_))
+        ));
+        assert!(matches!(
+            Schema::from_json(r#"{"version": 0, "schema": {}}"#),
+            Err(CoreError::FormatError(_))
+        ));
+        assert!(matches!(
+            Schema::from_json("{"),
+            Err(CoreError::FormatError(_))
+        ));
+    }
+}
@@ -1,11 +1,19 @@
 use super::column::Column;
+use super::constraint::PrimaryKey;
+use super::index::Index;
 use super::relationship::Relationship;
 use super::Schema;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Table {
     name: String,
     columns: Vec<Column>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    primary_key: Option<PrimaryKey>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    indexes: Vec<Index>,
 }
 
 impl Table {
@@ -13,6 +21,8 @@ impl Table {
         Self {
             name: table_name.to_string(),
             columns: vec![],
+            primary_key: None,
+            indexes: vec![],
         }
     }
 
@@ -46,11 +56,146 @@ impl Table {
         todo!()
     }
 
+    /// Adds a column. A column flagged as primary key is appended to the table's primary key.
     pub fn add_column(&mut self, column: Column) -> Result<&Column, String> {
+        column
+            .get_data_type()
+            .validate()
+            .map_err(|e| format!("Column {}: {}", column.get_name(), e))?;
+        if column.is_primary_key() {
+            self.primary_key
+                .get_or_insert_with(|| PrimaryKey::new(&[]))
+                .push_column(column.get_name());
+        }
         self.columns.push(column);
         Ok(self.columns.last().unwrap())
     }
 
+    pub fn get_primary_key(&self) -> Option<&PrimaryKey> {
+        self.primary_key.as_ref()
+    }
+
+    /// Replaces the primary key of the table and updates the primary key flag of its columns.
+    ///
+    /// # Returns
+    /// - An error message if the key has no columns, repeats a column or names one the table doesn't have.
+    pub fn set_primary_key(&mut self, primary_key: Option<PrimaryKey>) -> Result<(), String> {
+        if let Some(primary_key) = &primary_key {
+            if primary_key.get_columns().is_empty() {
+                return Err(format!(
+                    "Primary key of table {} must have at least one column",
+                    self.name
+                ));
+            }
+            for (index, column_name) in primary_key.get_columns().iter().enumerate() {
+                self.get_column(column_name)?;
+                if primary_key.get_columns()[..index].contains(column_name) {
+                    return Err(format!(
+                        "Primary key of table {} has the column {} more than once",
+                        self.name, column_name
+                    ));
+                }
+            }
+        }
+        for column in self.columns.iter_mut() {
+            *column.is_primary_key_mut() = primary_key
+                .as_ref()
+                .is_some_and(|p| p.contains(column.get_name()));
+        }
+        self.primary_key = primary_key;
+        Ok(())
+    }
+
+    pub fn get_indexes(&self) -> &[Index] {
+        &self.indexes
+    }
+
+    pub fn get_index(&self, index_name: &str) -> Result<&Index, String> {
+        self.indexes
+            .iter()
+            .find(|i| i.get_name() == index_name)
+            .ok_or(format!(
+                "Index {} doesn't exist in table {}",
+                index_name, self.name
+            ))
+    }
+
+    /// Adds an index.
+    ///
+    /// # Returns
+    /// - An error message if the table already has an index of that name, the index has no columns
+    ///   or it names a column the table doesn't have.
+    pub fn add_index(&mut self, index: Index) -> Result<&Index, String> {
+        if self.get_index(index.get_name()).is_ok() {
+            return Err(format!(
+                "Index {} already exists in table {}",
+                index.get_name(),
+                self.name
+            ));
+        }
+        self.check_index(&index)?;
+        self.indexes.push(index);
+        Ok(self.indexes.last().unwrap())
+    }
+
+    pub fn remove_index(&mut self, index_name: &str) -> Result<Index, String> {
+        self.get_index(index_name)?;
+        let index = self
+            .indexes
+            .iter()
+            .position(|i| i.get_name() == index_name)
+            .unwrap();
+        Ok(self.indexes.remove(index))
+    }
+
+    /// Checks that an index has columns, all of which exist in the table.
+    pub(crate) fn check_index(&self, index: &Index) -> Result<(), String> {
+        if index.get_columns().is_empty() {
+            return Err(format!(
+                "Index {} of table {} must have at least one column",
+                index.get_name(),
+                self.name
+            ));
+        }
+        for column in index.get_columns() {
+            self.get_column(column.get_name())?;
+        }
+        for column_name in index.get_include() {
+            self.get_column(column_name)?;
+            if index
+                .get_columns()
+                .iter()
+                .any(|c| c.get_name() == column_name)
+            {
+                return Err(format!(
+                    "Index {} of table {} both includes and is keyed by column {}",
+                    index.get_name(),
+                    self.name,
+                    column_name
+                ));
+            }
+        }
+        if index.get_predicate().is_some_and(|p| p.trim().is_empty()) {
+            return Err(format!(
+                "Index {} of table {} has an empty predicate",
+                index.get_name(),
+                self.name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether no two rows can share values in the given columns, because they include
+    /// the whole primary key or a unique column.
+    pub fn is_unique_key(&self, column_names: &[String]) -> bool {
+        self.primary_key
+            .as_ref()
+            .is_some_and(|p| p.get_columns().iter().all(|c| column_names.contains(c)))
+            || column_names
+                .iter()
+                .any(|c| self.get_column(c).is_ok_and(|c| c.is_unique()))
+    }
+
     pub fn udpate_column(&mut self, _updated_column: Column) -> Result<&Column, String> {
         todo!()
     }
@@ -0,0 +1,105 @@
+//! Schemas shared by the unit tests of the engines, the migration planner and the schema.
+
+use crate::schema::column::Column;
+use crate::schema::data_type::DataType;
+use crate::schema::relationship::{Relationship, RelationshipType};
+use crate::schema::Schema;
+
+/// `users (id)` and `orders (id, user_id)` with integer keys and no relationship yet.
+pub(crate) fn users_and_orders() -> Schema {
+    users_and_orders_keyed_by(DataType::Integer)
+}
+
+/// `users (id)` and `orders (id, user_id)`, where the user keys have the given type.
+pub(crate) fn users_and_orders_keyed_by(key_type: DataType) -> Schema {
+    let mut schema = Schema::new();
+    schema.add_table("users").unwrap();
+    schema.add_table("orders").unwrap();
+    schema
+        .get_table_mut("users")
+        .unwrap()
+        .add_column(Column::new("id", key_type.clone(), true, false, false))
+        .unwrap();
+    let orders_table = schema.get_table_mut("orders").unwrap();
+    orders_table
+        .add_column(Column::new("id", DataType::Integer, true, false, false))
+        .unwrap();
+    orders_table
+        .add_column(Column::new("user_id", key_type, false, true, false))
+        .unwrap();
+    schema
+}
+
+/// The relationship from `users.id` to `orders.user_id`.
+pub(crate) fn users_orders_relationship() -> Relationship {
+    Relationship::new(
+        "users",
+        "id",
+        "orders",
+        "user_id",
+        RelationshipType::OneToMany,
+    )
+}
+
+/// `users` and `orders` joined by [`users_orders_relationship`].
+pub(crate) fn related_users_and_orders() -> Schema {
+    let mut schema = users_and_orders();
+    schema.add_relation(users_orders_relationship()).unwrap();
+    schema
+}
+
+/// `users (tenant_id, id)` with a composite primary key and `orders (tenant_id, user_id)`.
+pub(crate) fn tenants_and_users() -> Schema {
+    let mut schema = Schema::new();
+    schema.add_table("users").unwrap();
+    schema.add_table("orders").unwrap();
+    let users_table = schema.get_table_mut("users").unwrap();
+    users_table
+        .add_column(Column::new("tenant_id", DataType::Uuid, true, false, false))
+        .unwrap();
+    users_table
+        .add_column(Column::new("id", DataType::BigInt, true, false, false))
+        .unwrap();
+    let orders_table = schema.get_table_mut("orders").unwrap();
+    orders_table
+        .add_column(Column::new("tenant_id", DataType::Uuid, false, true, false))
+        .unwrap();
+    orders_table
+        .add_column(Column::new("user_id", DataType::BigInt, false, true, false))
+        .unwrap();
+    schema
+}
+
+/// `posts (id)` and `tags (id)`, keyed by different integer types.
+pub(crate) fn posts_and_tags() -> Schema {
+    let mut schema = Schema::new();
+    schema.add_table("posts").unwrap();
+    schema.add_table("tags").unwrap();
+    schema
+        .get_table_mut("posts")
+        .unwrap()
+        .add_column(Column::new("id", DataType::BigInt, true, false, false))
+        .unwrap();
+    schema
+        .get_table_mut("tags")
+        .unwrap()
+        .add_column(Column::new("id", DataType::Integer, true, false, false))
+        .unwrap();
+    schema
+}
+
+/// Tables with an `id` primary key and a nullable `ref_id` foreign key column each.
+pub(crate) fn schema_with_tables(tables: &[&str]) -> Schema {
+    let mut schema = Schema::new();
+    for table in tables {
+        schema.add_table(table).unwrap();
+        let table = schema.get_table_mut(table).unwrap();
+        table
+            .add_column(Column::new("id", DataType::Integer, true, false, false))
+            .unwrap();
+        table
+            .add_column(Column::new("ref_id", DataType::Integer, false, true, true))
+            .unwrap();
+    }
+    schema
+}